@@ -0,0 +1,242 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Error};
+
+use clap::{Args, Parser};
+
+use sfs_core::{
+    spectrum::{self, io::Metadata},
+    Input, Scs,
+};
+
+use crate::view::Format;
+
+/// Add SFS.
+///
+/// All input SFS must have the same shape, and the sum is taken element-wise.
+#[derive(Debug, Parser)]
+#[clap(name = crate::NAME, about)]
+pub struct Add {
+    /// Input SFS.
+    ///
+    /// At least two input SFS must be provided, each in any of the supported formats.
+    #[arg(required = true, num_args = 2.., value_name = "PATH")]
+    inputs: Vec<PathBuf>,
+
+    #[command(flatten)]
+    output: Output,
+}
+
+impl Add {
+    pub fn run(self) -> Result<(), Error> {
+        let (first, rest) = read_inputs(self.inputs)?;
+
+        let (scs, metadata) = rest.into_iter().try_fold(first, |(scs, metadata), other| {
+            let metadata = combine_metadata(metadata, &other.1)?;
+            Ok::<_, Error>(((scs + other.0)?, metadata))
+        })?;
+
+        self.output.write(&scs, metadata)
+    }
+}
+
+/// Subtract SFS.
+///
+/// All input SFS must have the same shape, and all but the first input SFS are subtracted from the
+/// first element-wise.
+#[derive(Debug, Parser)]
+#[clap(name = crate::NAME, about)]
+pub struct Sub {
+    /// Input SFS.
+    ///
+    /// At least two input SFS must be provided, each in any of the supported formats.
+    #[arg(required = true, num_args = 2.., value_name = "PATH")]
+    inputs: Vec<PathBuf>,
+
+    #[command(flatten)]
+    output: Output,
+}
+
+impl Sub {
+    pub fn run(self) -> Result<(), Error> {
+        let (first, rest) = read_inputs(self.inputs)?;
+
+        let (scs, metadata) = rest.into_iter().try_fold(first, |(scs, metadata), other| {
+            let metadata = combine_metadata(metadata, &other.1)?;
+            Ok::<_, Error>(((scs - other.0)?, metadata))
+        })?;
+
+        if scs.inner().iter().any(|&x| x < 0.0) {
+            log::warn!("Subtracted SFS contains negative values.");
+        }
+
+        self.output.write(&scs, metadata)
+    }
+}
+
+/// Scale SFS.
+///
+/// Each element of the SFS is multiplied by the provided factor.
+#[derive(Debug, Parser)]
+#[clap(name = crate::NAME, about)]
+pub struct Scale {
+    /// Input SFS.
+    ///
+    /// The input SFS can be provided here or read from stdin in any of the supported formats.
+    #[arg(value_name = "PATH")]
+    input: Option<PathBuf>,
+
+    /// Scaling factor.
+    #[arg(short = 'f', long, allow_negative_numbers = true, value_name = "FLOAT")]
+    factor: f64,
+
+    #[command(flatten)]
+    output: Output,
+}
+
+impl Scale {
+    pub fn run(self) -> Result<(), Error> {
+        let (scs, metadata) = spectrum::io::read::Builder::default()
+            .set_input(Input::new(self.input)?)
+            .read_with_metadata()?;
+
+        self.output.write(&(scs * self.factor), metadata)
+    }
+}
+
+#[derive(Args, Debug)]
+struct Output {
+    /// Output path.
+    ///
+    /// If no path is given, SFS will be output to stdout.
+    #[arg(short = 'o', long, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Output format.
+    #[arg(short = 'O', long, default_value_t = Format::Text, value_name = "FORMAT")]
+    output_format: Format,
+
+    /// Print precision.
+    ///
+    /// This is only used for printing SFS to plain text, dadi, fastsimcoal2, or ANGSD format,
+    /// and will be ignored otherwise.
+    #[arg(long, default_value_t = 6, value_name = "INT")]
+    precision: usize,
+}
+
+impl Output {
+    fn write(self, scs: &Scs, metadata: Metadata) -> Result<(), Error> {
+        spectrum::io::write::Builder::default()
+            .set_precision(self.precision)
+            .set_format(spectrum::io::Format::from(self.output_format))
+            .set_metadata(metadata)
+            .write_to_path_or_stdout(self.output, scs)?;
+
+        Ok(())
+    }
+}
+
+type Spectrum = (Scs, Metadata);
+
+/// Reads all inputs, returning the first separately from the rest.
+fn read_inputs(inputs: Vec<PathBuf>) -> Result<(Spectrum, Vec<Spectrum>), Error> {
+    let mut spectra = inputs
+        .into_iter()
+        .map(|path| {
+            spectrum::io::read::Builder::default()
+                .set_input(Input::new(Some(path))?)
+                .read_with_metadata()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let first = spectra.remove(0);
+
+    Ok((first, spectra))
+}
+
+/// Combines the metadata of two spectra.
+///
+/// Population names must match where provided by both, while differences in folding or
+/// polarization are logged, keeping the status of the first spectrum. Elements masked in either
+/// spectrum are masked in the combination.
+fn combine_metadata(first: Metadata, other: &Metadata) -> Result<Metadata, Error> {
+    let populations = match (first.populations, other.populations.as_ref()) {
+        (Some(first), Some(other)) if &first != other => {
+            return Err(anyhow!(
+                "cannot combine SFS with different populations {} and {}",
+                first.join("/"),
+                other.join("/")
+            ))
+        }
+        (Some(populations), _) => Some(populations),
+        (None, other) => other.cloned(),
+    };
+
+    if first.folded != other.folded || first.polarized != other.polarized {
+        log::warn!("Combining SFS with different folding or polarization.");
+    }
+
+    let mask = match (first.mask, other.mask.as_ref()) {
+        (Some(first), Some(other)) => Some(
+            first
+                .into_iter()
+                .zip(other)
+                .map(|(first, &other)| first || other)
+                .collect(),
+        ),
+        (Some(mask), None) => Some(mask),
+        (None, other) => other.cloned(),
+    };
+
+    Ok(Metadata {
+        populations,
+        mask,
+        ..first
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use clap::error::ErrorKind as ClapErrorKind;
+
+    use crate::tests::{parse_subcmd, try_parse_subcmd};
+
+    #[test]
+    fn test_add_requires_two_inputs() {
+        let result = try_parse_subcmd::<Add>("sfs add a.sfs");
+
+        assert_eq!(result.unwrap_err().kind(), ClapErrorKind::TooFewValues);
+    }
+
+    #[test]
+    fn test_scale_negative_factor() {
+        let args = parse_subcmd::<Scale>("sfs scale -f -1 a.sfs");
+
+        assert_eq!(args.factor, -1.0);
+    }
+
+    #[test]
+    fn test_combine_metadata() {
+        let named = Metadata {
+            populations: Some(vec![String::from("a"), String::from("b")]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            combine_metadata(Metadata::default(), &named).unwrap(),
+            named
+        );
+        assert_eq!(
+            combine_metadata(named.clone(), &Metadata::default()).unwrap(),
+            named
+        );
+
+        let other = Metadata {
+            populations: Some(vec![String::from("b"), String::from("a")]),
+            ..Default::default()
+        };
+        assert!(combine_metadata(named, &other).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use std::{io::BufRead, path::PathBuf};
+
+use anyhow::Error;
+
+use clap::{Args, Parser};
+
+use sfs_core::{
+    array::Shape,
+    input::{self, counts},
+    spectrum, Input,
+};
+
+use crate::{parse_population_value, view::order_individuals};
+
+/// Create SFS from allele counts table.
+///
+/// An allele counts table can be written using the `--counts-out` argument of the `create`
+/// subcommand, and gives the derived and total allele counts of each population at each site.
+/// Creating an SFS from the table is much faster than reading the original input again, and any
+/// projection can be used.
+#[derive(Debug, Parser)]
+#[clap(name = crate::NAME, about)]
+pub struct Counts {
+    /// Input allele counts table.
+    ///
+    /// The input table can be provided here or read from stdin.
+    #[arg(value_name = "PATH")]
+    input: Option<PathBuf>,
+
+    /// Output path.
+    ///
+    /// If no path is given, SFS will be output to stdout.
+    #[arg(short = 'o', long, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Output precision.
+    ///
+    /// This option is only used when projecting, and otherwise set to zero since the SFS must
+    /// consist only of integers.
+    #[arg(long, default_value_t = 6, value_name = "INT")]
+    precision: usize,
+
+    #[command(flatten)]
+    project: Option<Project>,
+}
+
+#[derive(Args, Debug, Eq, PartialEq)]
+#[group(required = false, multiple = false)]
+struct Project {
+    /// Projected individuals.
+    ///
+    /// By default, only sites where all individuals have data are counted. Using this argument,
+    /// sites with missing data are projected to a lower number of individuals using
+    /// hypergeometric sampling, and only sites with fewer than the projected number of
+    /// individuals are skipped. Use a comma-separated list of values giving the new shape of the
+    /// SFS. For example, `--project-individuals 3,2` would project to three individuals in the
+    /// first dimension and two in the second. Where the table has population names, populations
+    /// may also be addressed by name using `population=INT` pairs, in which case each population
+    /// must be named once, in any order. Assumes diploid individuals.
+    #[arg(
+        short = 'p',
+        long = "project-individuals",
+        use_value_delimiter = true,
+        value_parser = parse_population_value::<usize>,
+        value_name = "[POPULATION=]INT,..."
+    )]
+    individuals: Option<Vec<(Option<String>, usize)>>,
+
+    /// Projected shape.
+    ///
+    /// Alternative to `--project-individuals`, see documentation for background. Using this
+    /// argument, the projection can be specified by shape, rather than number of individuals.
+    /// For example, `--project-shape 7,5` would project down to three diploid individuals in
+    /// the first dimension and two in the second.
+    #[arg(
+        long = "project-shape",
+        use_value_delimiter = true,
+        value_name = "INT,..."
+    )]
+    shape: Option<Vec<usize>>,
+}
+
+impl Counts {
+    pub fn run(self) -> Result<(), Error> {
+        let inner: Box<dyn BufRead> = match Input::new(self.input)?.open()? {
+            input::Reader::File(reader) => Box::new(reader),
+            input::Reader::Stdin(reader) => Box::new(reader),
+        };
+        let mut reader = counts::Reader::new(inner)?;
+
+        let project_to = match self.project {
+            Some(Project {
+                individuals: Some(individuals),
+                shape: None,
+            }) => {
+                let individuals = order_individuals(individuals, reader.metadata())?;
+
+                Some(Shape(individuals.into_iter().map(|i| 2 * i + 1).collect()))
+            }
+            Some(Project {
+                individuals: None,
+                shape: Some(shape),
+            }) => Some(Shape(shape)),
+            Some(_) => unreachable!("checked by clap"),
+            None => None,
+        };
+
+        let precision = if project_to.is_some() {
+            self.precision
+        } else {
+            0
+        };
+
+        let (scs, skipped) = reader.read_scs(project_to)?;
+        if skipped > 0 {
+            log::info!("Skipped {skipped} sites with insufficient data.");
+        }
+
+        spectrum::io::write::Builder::default()
+            .set_precision(precision)
+            .set_metadata(reader.metadata().clone())
+            .write_to_path_or_stdout(self.output, &scs)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use clap::error::ErrorKind as ClapErrorKind;
+
+    use crate::tests::try_parse_subcmd;
+
+    #[test]
+    fn test_project_individuals_and_shape_conflict() {
+        let result = try_parse_subcmd::<Counts>("sfs counts -p 2 --project-shape 5 input.counts");
+
+        assert_eq!(result.unwrap_err().kind(), ClapErrorKind::ArgumentConflict)
+    }
+}
@@ -1,40 +1,286 @@
-use std::{num::NonZeroUsize, path::PathBuf};
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 
-use clap::{Args, Parser};
+use clap::{ArgGroup, Args, Parser};
 
 mod runner;
 use runner::Runner;
+
+use crate::parse_population_value;
 use sfs_core::{
     array::Shape,
-    input::{genotype, sample, site, Sample},
-    spectrum, Input,
+    input::{
+        self, counts, genotype, sample,
+        site::{
+            self,
+            reader::filter::{GenotypeFilter, SiteFilter, Threshold},
+        },
+        window::Windowed,
+        Region, Sample,
+    },
+    saf::Em,
+    spectrum::{
+        self,
+        io::{container::Record, Labelled, Metadata},
+    },
+    Input,
 };
 
-/// Create SFS from VCF/BCF.
+/// Create SFS from VCF/BCF or PLINK.
 #[derive(Debug, Parser)]
 #[clap(name = crate::NAME, about)]
+#[command(group(ArgGroup::new("Random").args(["bootstrap", "downsample"]).multiple(true)))]
 pub struct Create {
-    /// Input VCF/BCF.
+    /// Input VCF/BCF or PLINK .bed.
     ///
     /// If no file is provided, stdin will be used. Input may be BGZF-compressed or uncompressed.
+    /// PLINK 1 binary input is detected from the .bed file, which must be uncompressed and have
+    /// .bim and .fam files with the same name next to it. PLINK genotypes are taken to be diploid,
+    /// with the second .bim allele as the reference allele, and cannot be used with
+    /// `--likelihoods`.
+    ///
+    /// Multiple files may be provided, e.g. one per contig, in which case they are read one after
+    /// the other and combined into a single SFS. All files must have the same samples in the same
+    /// order.
     #[arg(value_name = "FILE")]
-    input: Option<PathBuf>,
+    input: Vec<PathBuf>,
+
+    /// Input list file.
+    ///
+    /// Alternative to providing input files as arguments. Using this argument, input files are
+    /// given by a file with one path per line. Empty lines and lines starting with `#` are
+    /// ignored.
+    #[arg(long, conflicts_with = "input", value_name = "FILE")]
+    inputs_file: Option<PathBuf>,
 
     /// Output precision.
     ///
-    /// This option is only used when projecting, and otherwise set to zero since the output must
-    /// be integer counts.
+    /// This option is only used when projecting or using genotype likelihoods, and otherwise set
+    /// to zero since the output must be integer counts.
     #[arg(long, default_value_t = 6, value_name = "INT")]
     precision: usize,
 
     #[command(flatten)]
     project: Option<Project>,
 
+    /// Downsample rather than project.
+    ///
+    /// Only used with `--project-individuals` or `--project-shape`. By default, projection
+    /// spreads each site over the projected SFS using hypergeometric weights, so that the SFS
+    /// has fractional counts. Using this flag, the projected number of alleles is instead drawn
+    /// at random without replacement at each site, so that the SFS consists only of integers.
+    /// See `--seed` for reproducibility.
+    #[arg(long, requires = "Project", conflicts_with = "likelihoods")]
+    downsample: bool,
+
+    #[command(flatten)]
+    polarize: Option<Polarize>,
+
+    #[command(flatten)]
+    mutation_classes: Option<MutationClasses>,
+
+    /// Stratify SFS by functional annotation category.
+    ///
+    /// Using this argument, a separate SFS is created for each of the provided categories, for
+    /// example `synonymous_variant,missense_variant`. Categories are matched against the
+    /// consequence terms in the VEP `CSQ` or SnpEff `ANN` INFO field, taken from the second
+    /// `|`-separated field of each annotation as in the default layout of both. Where a site has
+    /// consequence terms matching several categories, e.g. for different transcripts, the
+    /// category given first is used. Sites without a matching category are skipped. The output
+    /// contains the spectra in plain text format, one after the other, each with an additional
+    /// `#LABEL=<category>` header line giving the category, which can be selected using `--label`
+    /// with `view` or `stat`. Multiple, comma-separated values may be provided.
+    #[arg(
+        long,
+        use_value_delimiter = true,
+        conflicts_with_all = ["MutationClasses", "Window", "bootstrap", "callable", "likelihoods"],
+        value_name = "CATEGORY,..."
+    )]
+    annotation_categories: Option<Vec<String>>,
+
+    #[command(flatten)]
+    regions: Option<Regions>,
+
     #[command(flatten)]
     samples: Option<Samples>,
 
+    /// Excluded samples.
+    ///
+    /// Using this argument, the provided samples are left out, regardless of how samples are
+    /// otherwise chosen. Populations left without samples are removed. Multiple,
+    /// comma-separated values may be provided.
+    #[arg(long, use_value_delimiter = true, value_name = "SAMPLE,...")]
+    exclude_samples: Option<Vec<String>>,
+
+    /// Population order.
+    ///
+    /// By default, the ordering of populations in the resulting SFS corresponds to the order of
+    /// appearance of population names, unless given by a samples file header. Using this
+    /// argument, the order can be given as a comma-separated list of population names, where
+    /// each population must be named exactly once.
+    #[arg(long, use_value_delimiter = true, value_name = "POPULATION,...")]
+    population_order: Option<Vec<String>>,
+
+    #[command(flatten)]
+    window: Option<Window>,
+
+    /// Window step.
+    ///
+    /// Only used with `--window-size` or `--window-sites`, and given in the same unit. By
+    /// default, the step equals the window size, so that windows are tiled. Using a smaller step
+    /// gives overlapping, sliding windows. For sliding windows with `--window-sites`, windows at
+    /// the end of a contig with fewer than the provided number of sites are not output, unless
+    /// it is the only window on the contig.
+    #[arg(long, requires = "Window", value_name = "INT")]
+    window_step: Option<NonZeroUsize>,
+
+    /// Number of block bootstrap replicates.
+    ///
+    /// By default, a single SFS is created from all sites. Using this argument, sites are instead
+    /// partitioned into blocks, and the provided number of bootstrap replicates of the SFS are
+    /// created by resampling blocks with replacement. By default, each contig makes up a block.
+    /// The output contains the replicate spectra in plain text format, one after the other, each
+    /// with an additional `#LABEL=<replicate_n>` header line giving the 1-based replicate number.
+    #[arg(long, conflicts_with_all = ["Window", "likelihoods"], value_name = "INT")]
+    bootstrap: Option<NonZeroUsize>,
+
+    /// Bootstrap block size in bases.
+    ///
+    /// Only used with `--bootstrap`. Using this argument, sites are partitioned into blocks of the
+    /// provided number of bases along each contig, rather than into contigs.
+    #[arg(long, requires = "bootstrap", value_name = "INT")]
+    bootstrap_block_size: Option<NonZeroUsize>,
+
+    /// Random seed.
+    ///
+    /// Only used with `--bootstrap` or `--downsample`. By default, a seed is chosen based on the
+    /// current time and logged, so that results can be reproduced.
+    #[arg(long, requires = "Random", value_name = "INT")]
+    seed: Option<u64>,
+
+    /// Sample ploidy.
+    ///
+    /// The ploidy of all samples, used to determine the shape of the SFS. Genotypes with a
+    /// different ploidy than their sample cause an error. Use `--ploidy-file` to set the ploidy
+    /// of individual samples.
+    #[arg(long, default_value_t = NonZeroUsize::new(2).unwrap(), value_name = "INT")]
+    ploidy: NonZeroUsize,
+
+    /// Sample ploidy file.
+    ///
+    /// Using this argument, the ploidy of individual samples can be set in a file, taking
+    /// precedence over `--ploidy`. Each line should contain the name of a sample and its ploidy,
+    /// separated by a tab. For example, this can be used to set the ploidy of males to one when
+    /// creating an SFS from the X chromosome.
+    #[arg(long, value_name = "FILE")]
+    ploidy_file: Option<PathBuf>,
+
+    /// Callable regions BED file.
+    ///
+    /// Input containing only variant sites lacks the monomorphic sites required for the SFS to be
+    /// correctly normalised. Using this argument, the bases in the provided callable regions
+    /// without any record in the input are counted as monomorphic, and added to the first cell of
+    /// the SFS. Records outside the callable regions are skipped. When using `--regions` or
+    /// `--regions-file`, only callable bases inside the regions are counted. Input must be sorted
+    /// by position within contigs. Note that monomorphic bases are taken to carry the ancestral
+    /// allele, also when polarizing.
+    #[arg(long, conflicts_with_all = ["Window", "bootstrap", "likelihoods"], value_name = "FILE")]
+    callable: Option<PathBuf>,
+
+    /// Allele counts output file.
+    ///
+    /// Using this argument, the derived and total allele counts of each population at each site
+    /// are also written to the provided file, which can be used to create an SFS with the `counts`
+    /// subcommand without reading the input again. All sites passing filters are written,
+    /// including sites with missing genotypes, so that the SFS can later be created with any
+    /// projection.
+    #[arg(long, conflicts_with_all = ["callable", "likelihoods"], value_name = "FILE")]
+    counts_out: Option<PathBuf>,
+
+    /// Only use sites passing all VCF filters.
+    ///
+    /// By default, the FILTER field is ignored. Using this flag, sites where the FILTER field is
+    /// anything other than `PASS` are skipped, including sites where it is missing.
+    #[arg(long)]
+    filter_pass: bool,
+
+    /// Minimum site quality.
+    ///
+    /// Using this argument, sites where the QUAL field is missing or less than the provided value
+    /// are skipped.
+    #[arg(long, value_name = "FLOAT")]
+    filter_qual: Option<f64>,
+
+    /// Site INFO field thresholds.
+    ///
+    /// Using this argument, sites are skipped unless the provided numeric INFO fields pass the
+    /// provided thresholds. Each threshold has the form `KEY<op>VALUE`, where `<op>` is one of
+    /// `>=`, `>`, `<=`, `<`, `==`, or `!=`, for example `DP>=100`. Where an INFO field has
+    /// multiple values, only the first is used, and sites where the field is missing are
+    /// skipped. Multiple, comma-separated values may be provided.
+    #[arg(
+        long,
+        use_value_delimiter = true,
+        value_delimiter = ',',
+        value_name = "KEY<op>VALUE,..."
+    )]
+    filter_info: Vec<Threshold>,
+
+    /// Genotype FORMAT field thresholds.
+    ///
+    /// Using this argument, genotypes are skipped unless the provided numeric FORMAT fields pass
+    /// the provided thresholds, using the same form as `--filter-info`, for example `GQ>=20`.
+    /// Genotypes where the field is missing are skipped. Skipped genotypes are treated the same as
+    /// missing genotypes, so that sites with skipped genotypes are themselves skipped unless
+    /// projecting. Multiple, comma-separated values may be provided.
+    #[arg(
+        long,
+        use_value_delimiter = true,
+        value_delimiter = ',',
+        value_name = "KEY<op>VALUE,..."
+    )]
+    filter_format: Vec<Threshold>,
+
+    /// Estimate SFS from genotype likelihoods.
+    ///
+    /// By default, the SFS is created by counting called genotypes in the GT field. Using this
+    /// flag, the SFS will instead be estimated from the genotype likelihoods in the GL field,
+    /// falling back to the PL field, using expectation-maximisation. Samples with missing
+    /// likelihoods are treated as uninformative, while sites with multiallelic likelihoods are
+    /// skipped. Projection cannot be used with genotype likelihoods.
+    #[arg(long, conflicts_with_all = ["Project", "strict"])]
+    likelihoods: bool,
+
+    /// Maximum number of EM iterations.
+    ///
+    /// Only used with `--likelihoods`.
+    #[arg(
+        long,
+        default_value_t = 100,
+        requires = "likelihoods",
+        value_name = "INT"
+    )]
+    max_iterations: usize,
+
+    /// EM tolerance.
+    ///
+    /// The EM algorithm stops when the difference in log-likelihood between two successive
+    /// iterations falls below this value. Only used with `--likelihoods`.
+    #[arg(
+        long,
+        default_value_t = 1e-6,
+        requires = "likelihoods",
+        value_name = "FLOAT"
+    )]
+    tolerance: f64,
+
     /// Fail on missingness.
     ///
     /// By default, any site with missing and/or multiallelic genotypes in the applied sample
@@ -75,16 +321,188 @@ struct Samples {
     /// Alternative to `--samples`, see documentation for background. Using this argument, the
     /// sample subset can be provided as a file. Each line should contain the name of a sample.
     /// Optionally, the file may contain a second, tab-delimited column with population
-    /// identifiers.
+    /// identifiers. Files with a `.csv` extension are instead comma-delimited, and files with a
+    /// `.fam` extension are read as PLINK `.fam` files, using the family ID as population.
+    /// Lines starting with `#` are ignored, except for an optional `#populations=POPULATION,...`
+    /// header line giving the order of populations.
     #[arg(short = 'S', long = "samples-file", value_name = "FILE")]
     file: Option<PathBuf>,
+
+    /// Sample pattern.
+    ///
+    /// Alternative to `--samples`, see documentation for background. Using this argument,
+    /// samples are assigned to populations by matching a regular expression against all sample
+    /// names. The population is given by the first capture group, or by the whole match if the
+    /// expression has no capture groups, and samples that do not match are left out. For
+    /// example, `--samples-pattern '^[^_]+'` uses the part of each sample name before the first
+    /// underscore as its population.
+    #[arg(long = "samples-pattern", value_name = "REGEX")]
+    pattern: Option<String>,
 }
 
 impl From<Samples> for site::reader::builder::Samples {
     fn from(samples: Samples) -> Self {
-        match (samples.list, samples.file) {
-            (Some(list), None) => site::reader::builder::Samples::List(list),
-            (None, Some(path)) => site::reader::builder::Samples::Path(path),
+        match (samples.list, samples.file, samples.pattern) {
+            (Some(list), None, None) => site::reader::builder::Samples::List(list),
+            (None, Some(path), None) => site::reader::builder::Samples::Path(path),
+            (None, None, Some(pattern)) => site::reader::builder::Samples::Pattern(pattern),
+            _ => unreachable!("checked by clap"),
+        }
+    }
+}
+
+#[derive(Args, Debug, Eq, PartialEq)]
+#[group(required = false, multiple = false)]
+struct Regions {
+    /// Regions.
+    ///
+    /// By default, all records in the input are used. Using this argument, only records in the
+    /// provided regions will be used. Regions are given as `contig`, `contig:position`,
+    /// `contig:start-end`, or `contig:start-`, where positions are 1-based and inclusive.
+    /// Multiple, comma-separated values may be provided. The input must be BGZF-compressed
+    /// with a CSI (`<input>.csi`) or tabix (`<input>.tbi`) index.
+    #[arg(
+        id = "regions",
+        short = 'r',
+        long = "regions",
+        use_value_delimiter = true,
+        value_delimiter = ',',
+        value_name = "REGION,..."
+    )]
+    list: Option<Vec<Region>>,
+
+    /// Regions file.
+    ///
+    /// Alternative to `--regions`, see documentation for background. Using this argument, the
+    /// regions can be provided as a BED file.
+    #[arg(
+        id = "regions_file",
+        short = 'R',
+        long = "regions-file",
+        value_name = "FILE"
+    )]
+    file: Option<PathBuf>,
+}
+
+impl TryFrom<Regions> for Vec<Region> {
+    type Error = Error;
+
+    fn try_from(regions: Regions) -> Result<Self, Self::Error> {
+        match (regions.list, regions.file) {
+            (Some(list), None) => Ok(list),
+            (None, Some(path)) => Region::from_bed_path(&path)
+                .map_err(|e| anyhow!("failed to read regions file '{}': {e}", path.display())),
+            _ => unreachable!("checked by clap"),
+        }
+    }
+}
+
+#[derive(Args, Debug, Eq, PartialEq)]
+#[group(required = false, multiple = false)]
+struct Window {
+    /// Window size in bases.
+    ///
+    /// By default, a single SFS is created from all sites. Using this argument, one SFS will
+    /// instead be created for each window of the provided number of bases along each contig,
+    /// starting from the first base of the contig. Windows without any sites are not output. The
+    /// output contains a spectrum for each window in plain text format, each with an additional
+    /// `#REGION=<contig:start-end>` header line giving the window. Input must be sorted by
+    /// position within contigs.
+    #[arg(long = "window-size", value_name = "INT")]
+    size: Option<NonZeroUsize>,
+
+    /// Window size in sites.
+    ///
+    /// Alternative to `--window-size`, see documentation for background. Using this argument,
+    /// each window will instead contain the provided number of sites used in the SFS, and the
+    /// window regions span the positions of the first and last site in each window.
+    #[arg(long = "window-sites", value_name = "INT")]
+    sites: Option<NonZeroUsize>,
+}
+
+impl Window {
+    fn with_step(self, step: Option<NonZeroUsize>) -> input::Window {
+        match (self.size, self.sites) {
+            (Some(size), None) => input::Window::Bases {
+                size,
+                step: step.unwrap_or(size),
+            },
+            (None, Some(size)) => input::Window::Sites {
+                size,
+                step: step.unwrap_or(size),
+            },
+            _ => unreachable!("checked by clap"),
+        }
+    }
+}
+
+#[derive(Args, Debug, Eq, PartialEq)]
+#[group(required = false, multiple = false)]
+struct Polarize {
+    /// Polarize using ancestral allele INFO field.
+    ///
+    /// By default, the SFS counts alternative alleles, so that the reference allele is taken to
+    /// be ancestral. Using this flag, each site will be polarized using the `AA` INFO field:
+    /// sites where the alternative allele is ancestral are flipped, while sites where the
+    /// ancestral allele is missing, unknown, or matches neither the reference nor the
+    /// alternative allele are skipped.
+    #[arg(long = "ancestral-info")]
+    info: bool,
+
+    /// Polarize using ancestral FASTA.
+    ///
+    /// Alternative to `--ancestral-info`, see documentation for background. Using this argument,
+    /// the ancestral allele is instead taken from the base at the position of each site in the
+    /// provided FASTA. The FASTA must be indexed with a `.fai` index, and `N` bases, as well as
+    /// all bases on contigs missing from the FASTA, are treated as unknown.
+    #[arg(long = "ancestral-fasta", value_name = "FILE")]
+    fasta: Option<PathBuf>,
+}
+
+impl From<Polarize> for site::reader::builder::Polarize {
+    fn from(polarize: Polarize) -> Self {
+        match (polarize.info, polarize.fasta) {
+            (true, None) => site::reader::builder::Polarize::AncestralAllele,
+            (false, Some(path)) => site::reader::builder::Polarize::Fasta(path),
+            _ => unreachable!("checked by clap"),
+        }
+    }
+}
+
+#[derive(Args, Debug, Eq, PartialEq)]
+#[group(
+    required = false,
+    multiple = false,
+    conflicts_with_all = ["Window", "bootstrap", "callable", "likelihoods"]
+)]
+struct MutationClasses {
+    /// Stratify SFS by mutation class.
+    ///
+    /// By default, a single SFS is created from all sites. Using this flag, a separate SFS is
+    /// instead created for each of the six strand-collapsed substitution classes C>A, C>G, C>T,
+    /// T>A, T>C, and T>G, going from ancestral to derived allele. Unless polarizing, the reference
+    /// allele is taken to be ancestral. Sites that are not biallelic SNPs are skipped. The output
+    /// contains the spectra in plain text format, one after the other, each with an additional
+    /// `#LABEL=<class>` header line giving the class, which can be selected using `--label` with
+    /// `view` or `stat`.
+    #[arg(long = "mutation-classes")]
+    substitutions: bool,
+
+    /// Stratify SFS by mutation class and CpG context using reference FASTA.
+    ///
+    /// Alternative to `--mutation-classes`, see documentation for background. Using this
+    /// argument, substitutions from C are further split by whether they occur in a CpG context
+    /// in the provided FASTA, giving classes like `C>T_CpG` and `C>T_nonCpG`. The FASTA must be
+    /// indexed with a `.fai` index. Sites where the context cannot be determined are skipped.
+    #[arg(long = "mutation-classes-cpg", value_name = "FILE")]
+    cpg: Option<PathBuf>,
+}
+
+impl From<MutationClasses> for site::reader::builder::MutationClasses {
+    fn from(classes: MutationClasses) -> Self {
+        match (classes.substitutions, classes.cpg) {
+            (true, None) => site::reader::builder::MutationClasses::Substitutions,
+            (false, Some(path)) => site::reader::builder::MutationClasses::CpG(path),
             _ => unreachable!("checked by clap"),
         }
     }
@@ -102,14 +520,19 @@ struct Project {
     /// more data will be projected down. Use a comma-separated list of values giving the new
     /// shape of the SFS. For example, `--project-individuals 3,2` would project a
     /// two-dimensional SFS down to three individuals in the first dimension and two in the
-    /// second.
+    /// second. Populations may also be addressed by name using `population=INT` pairs, in which
+    /// case each population must be named once, in any order. Individuals are assumed to have
+    /// the ploidy of the samples in their population, as given by `--ploidy` or
+    /// `--ploidy-file`, and an error is given if the samples in a population have different
+    /// ploidies. Use `--project-shape` for such populations.
     #[clap(
         short = 'p',
         long = "project-individuals",
         use_value_delimiter = true,
-        value_name = "INT,..."
+        value_parser = parse_population_value::<usize>,
+        value_name = "[POPULATION=]INT,..."
     )]
-    individuals: Option<Vec<usize>>,
+    individuals: Option<Vec<(Option<String>, usize)>>,
 
     /// Projected shape.
     ///
@@ -125,16 +548,76 @@ struct Project {
     shape: Option<Vec<usize>>,
 }
 
-impl From<Project> for site::reader::builder::Project {
-    fn from(project: Project) -> Self {
+impl TryFrom<Project> for site::reader::builder::Project {
+    type Error = Error;
+
+    fn try_from(project: Project) -> Result<Self, Self::Error> {
         match (project.individuals, project.shape) {
-            (Some(individuals), None) => site::reader::builder::Project::Individuals(individuals),
-            (None, Some(shape)) => site::reader::builder::Project::Shape(Shape::from(shape)),
+            (Some(individuals), None) if individuals.iter().all(|(p, _)| p.is_none()) => {
+                Ok(site::reader::builder::Project::Individuals(
+                    individuals.into_iter().map(|(_, i)| i).collect(),
+                ))
+            }
+            (Some(individuals), None) => individuals
+                .into_iter()
+                .map(|(population, i)| {
+                    population.map(|population| (sample::Population::from(Some(population)), i))
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(site::reader::builder::Project::NamedIndividuals)
+                .ok_or_else(|| {
+                    anyhow!("cannot mix named and unnamed populations in projected individuals")
+                }),
+            (None, Some(shape)) => Ok(site::reader::builder::Project::Shape(Shape::from(shape))),
             _ => unreachable!("checked by clap"),
         }
     }
 }
 
+fn read_inputs_file(path: &Path) -> Result<Vec<PathBuf>, Error> {
+    let inputs = fs::read_to_string(path)
+        .map_err(|e| anyhow!("failed to read inputs file '{}': {e}", path.display()))?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(PathBuf::from)
+        .collect::<Vec<_>>();
+
+    if inputs.is_empty() {
+        Err(anyhow!("no inputs in inputs file '{}'", path.display()))
+    } else {
+        Ok(inputs)
+    }
+}
+
+fn time_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+/// Writes labelled spectra with their metadata to a container on stdout.
+fn write_container_to_stdout<I>(
+    writer: spectrum::io::write::Builder,
+    records: I,
+) -> Result<(), Error>
+where
+    I: IntoIterator<Item = Record>,
+{
+    let mut container = writer.build_container(io::stdout())?;
+    for Labelled {
+        label,
+        value: (scs, metadata),
+    } in records
+    {
+        container.write_record(&label, &scs, &metadata)?;
+    }
+    container.finish()?;
+
+    Ok(())
+}
+
 fn parse_sample_population(s: &str) -> Result<(Sample, sample::Population), clap::Error> {
     Ok(s.split_once('=')
         .map(|(key, val)| (Sample::from(key), sample::Population::from(Some(val))))
@@ -143,23 +626,204 @@ fn parse_sample_population(s: &str) -> Result<(Sample, sample::Population), clap
 
 impl Create {
     pub fn run(self) -> Result<(), Error> {
-        let precision = self.project.as_ref().map_or(0, |_| self.precision);
-
+        let precision = if (self.project.is_some() && !self.downsample) || self.likelihoods {
+            self.precision
+        } else {
+            0
+        };
+
+        let site_filters = self
+            .filter_pass
+            .then_some(SiteFilter::Pass)
+            .into_iter()
+            .chain(self.filter_qual.map(SiteFilter::MinQuality))
+            .chain(self.filter_info.into_iter().map(SiteFilter::Info))
+            .collect();
+        let genotype_filters = self.filter_format.into_iter().map(GenotypeFilter).collect();
+
+        let polarized = self.polarize.is_some();
+        let stratify = match (self.mutation_classes, self.annotation_categories) {
+            (Some(classes), _) => Some(site::reader::builder::Stratify::MutationClasses(
+                classes.into(),
+            )),
+            (None, Some(categories)) => {
+                Some(site::reader::builder::Stratify::Annotation(categories))
+            }
+            (None, None) => None,
+        };
+        let stratified = stratify.is_some();
+
+        let paths = match self.inputs_file {
+            Some(path) => read_inputs_file(&path)?,
+            None => self.input,
+        };
+        if paths.len() > 1 {
+            log::debug!("Reading {n} inputs one after the other.", n = paths.len());
+        }
+        let inputs = if paths.is_empty() {
+            vec![Input::new(None)?]
+        } else {
+            paths
+                .into_iter()
+                .map(|path| Input::new(Some(path)))
+                .collect::<Result<_, _>>()?
+        };
+
+        let seed = (self.bootstrap.is_some() || self.downsample).then(|| {
+            self.seed.unwrap_or_else(|| {
+                let seed = time_seed();
+                log::info!("Using random seed {seed}.");
+                seed
+            })
+        });
+
+        let project = self
+            .project
+            .map(site::reader::builder::Project::try_from)
+            .transpose()?
+            .map(|project| match seed {
+                Some(seed) if self.downsample => site::reader::builder::Project::Downsample {
+                    project: Box::new(project),
+                    seed,
+                },
+                _ => project,
+            });
+
+        let regions = self.regions.map(TryInto::try_into).transpose()?;
         let reader = site::reader::Builder::default()
             .set_samples(self.samples.map(Into::into))
-            .set_project(self.project.map(Into::into))
+            .set_exclude_samples(
+                self.exclude_samples
+                    .into_iter()
+                    .flatten()
+                    .map(Sample::from)
+                    .collect(),
+            )
+            .set_population_order(self.population_order.map(|order| {
+                order
+                    .into_iter()
+                    .map(|population| sample::Population::from(Some(population)))
+                    .collect()
+            }))
+            .set_project(project)
+            .set_polarize(self.polarize.map(Into::into))
+            .set_stratify(stratify)
+            .set_ploidy(self.ploidy.get())
+            .set_ploidy_path(self.ploidy_file)
+            .set_site_filters(site_filters)
+            .set_genotype_filters(genotype_filters)
+            .set_callable_path(self.callable)
+            .set_regions(regions.clone())
             .build(
                 genotype::reader::Builder::default()
-                    .set_input(Input::new(self.input)?)
+                    .set_inputs(inputs)
+                    .set_regions(regions)
                     .set_threads(self.threads)
                     .build()?,
             )?;
 
-        let sfs = Runner::new(reader, self.strict)?.run()?;
-
-        spectrum::io::write::Builder::default()
-            .set_precision(precision)
-            .write_to_stdout(&sfs)?;
+        let em = Em::default()
+            .set_max_iterations(self.max_iterations)
+            .set_tolerance(self.tolerance);
+        let metadata = Metadata {
+            populations: reader
+                .populations()
+                .any(|population| matches!(population, sample::Population::Named(_)))
+                .then(|| reader.populations().map(ToString::to_string).collect()),
+            folded: false,
+            polarized,
+            mask: None,
+            region: None,
+        };
+        let counts_writer = self
+            .counts_out
+            .map(|path| {
+                let file = File::create(&path).map_err(|e| {
+                    anyhow!("failed to create counts file '{}': {e}", path.display())
+                })?;
+
+                counts::Writer::new(
+                    Box::new(BufWriter::new(file)) as Box<dyn Write>,
+                    reader.unprojected_shape(),
+                    &metadata,
+                )
+                .map_err(Error::from)
+            })
+            .transpose()?;
+
+        let writer = spectrum::io::write::Builder::default().set_precision(precision);
+
+        let mut runner = Runner::new(reader, self.strict)?.set_counts_writer(counts_writer);
+        match (
+            self.window.map(|window| window.with_step(self.window_step)),
+            self.bootstrap,
+        ) {
+            (Some(window), _) => {
+                let windows = if self.likelihoods {
+                    runner.run_likelihoods_windows(em, window)?
+                } else {
+                    runner.run_windows(window)?
+                };
+
+                write_container_to_stdout(
+                    writer,
+                    windows
+                        .into_iter()
+                        .map(|Windowed { region, value }| Labelled {
+                            label: region.to_string(),
+                            value: (
+                                value,
+                                Metadata {
+                                    region: Some(region),
+                                    ..metadata.clone()
+                                },
+                            ),
+                        }),
+                )?;
+            }
+            (None, Some(replicates)) => {
+                let blocks = match self.bootstrap_block_size {
+                    Some(size) => input::Window::Bases { size, step: size },
+                    None => input::Window::Contigs,
+                };
+                let seed = seed.expect("seed set when bootstrapping");
+
+                let replicates = runner.run_bootstrap(blocks, replicates.get(), seed)?;
+
+                write_container_to_stdout(
+                    writer,
+                    replicates
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, replicate)| Labelled {
+                            label: format!("replicate_{}", i + 1),
+                            value: (replicate, metadata.clone()),
+                        }),
+                )?;
+            }
+            (None, None) if stratified => {
+                let spectra = runner.run_strata()?;
+
+                write_container_to_stdout(
+                    writer,
+                    spectra
+                        .into_iter()
+                        .map(|Labelled { label, value }| Labelled {
+                            label,
+                            value: (value, metadata.clone()),
+                        }),
+                )?;
+            }
+            (None, None) => {
+                let sfs = if self.likelihoods {
+                    runner.run_likelihoods(em)?
+                } else {
+                    runner.run()?
+                };
+
+                writer.set_metadata(metadata).write_to_stdout(&sfs)?;
+            }
+        }
 
         Ok(())
     }
@@ -173,6 +837,18 @@ mod tests {
 
     use crate::tests::{parse_subcmd, try_parse_subcmd};
 
+    #[test]
+    fn test_parse_multiple_inputs() {
+        let args = parse_subcmd::<Create>("sfs create a.bcf b.bcf");
+        assert_eq!(
+            args.input,
+            vec![PathBuf::from("a.bcf"), PathBuf::from("b.bcf")]
+        );
+
+        let result = try_parse_subcmd::<Create>("sfs create --inputs-file inputs.txt a.bcf");
+        assert_eq!(result.unwrap_err().kind(), ClapErrorKind::ArgumentConflict);
+    }
+
     #[test]
     fn test_samples_and_samples_file_conflict() {
         let result = try_parse_subcmd::<Create>("sfs create -s sample0 -S samples.file input.bcf");
@@ -201,6 +877,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_samples_pattern_conflict() {
+        let result =
+            try_parse_subcmd::<Create>("sfs create -s sample0 --samples-pattern ^[^_]+ input.bcf");
+
+        assert_eq!(result.unwrap_err().kind(), ClapErrorKind::ArgumentConflict)
+    }
+
+    #[test]
+    fn test_parse_project_named_individuals() {
+        let args = parse_subcmd::<Create>("sfs create -p pop1=3,pop0=2 input.bcf");
+
+        assert_eq!(
+            args.project.and_then(|project| project.individuals),
+            Some(vec![
+                (Some(String::from("pop1")), 3),
+                (Some(String::from("pop0")), 2),
+            ])
+        );
+    }
+
     #[test]
     fn test_project() {
         let args = parse_subcmd::<Create>("sfs create --project-shape 6,3,9 input.bcf");
@@ -220,6 +917,100 @@ mod tests {
         assert_eq!(result.unwrap_err().kind(), ClapErrorKind::ArgumentConflict)
     }
 
+    #[test]
+    fn test_likelihoods_project_conflict() {
+        let result = try_parse_subcmd::<Create>("sfs create --likelihoods -p 2 input.bcf");
+
+        assert_eq!(result.unwrap_err().kind(), ClapErrorKind::ArgumentConflict)
+    }
+
+    #[test]
+    fn test_em_args_require_likelihoods() {
+        let result = try_parse_subcmd::<Create>("sfs create --max-iterations 10 input.bcf");
+
+        assert_eq!(
+            result.unwrap_err().kind(),
+            ClapErrorKind::MissingRequiredArgument
+        )
+    }
+
+    #[test]
+    fn test_parse_polarize() {
+        let args = parse_subcmd::<Create>("sfs create --ancestral-fasta anc.fa input.bcf");
+
+        assert_eq!(
+            args.polarize,
+            Some(Polarize {
+                info: false,
+                fasta: Some(PathBuf::from("anc.fa"))
+            })
+        );
+
+        let args = parse_subcmd::<Create>("sfs create input.bcf");
+        assert_eq!(args.polarize, None);
+    }
+
+    #[test]
+    fn test_polarize_args_conflict() {
+        let result = try_parse_subcmd::<Create>(
+            "sfs create --ancestral-info --ancestral-fasta anc.fa input.bcf",
+        );
+
+        assert_eq!(result.unwrap_err().kind(), ClapErrorKind::ArgumentConflict)
+    }
+
+    #[test]
+    fn test_parse_regions() {
+        let args = parse_subcmd::<Create>("sfs create -r chr1,chr2:10-20 input.bcf");
+
+        assert_eq!(
+            args.regions.and_then(|regions| regions.list),
+            Some(vec![
+                Region::new("chr1", 1, None),
+                Region::new("chr2", 10, Some(20))
+            ])
+        );
+    }
+
+    #[test]
+    fn test_regions_and_regions_file_conflict() {
+        let result = try_parse_subcmd::<Create>("sfs create -r chr1 -R regions.bed input.bcf");
+
+        assert_eq!(result.unwrap_err().kind(), ClapErrorKind::ArgumentConflict)
+    }
+
+    #[test]
+    fn test_window_step_defaults_to_size() {
+        let args = parse_subcmd::<Create>("sfs create --window-sites 100 input.bcf");
+
+        assert_eq!(
+            args.window.map(|window| window.with_step(args.window_step)),
+            Some(input::Window::Sites {
+                size: NonZeroUsize::new(100).unwrap(),
+                step: NonZeroUsize::new(100).unwrap(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_window_args_conflict() {
+        let result = try_parse_subcmd::<Create>(
+            "sfs create --window-size 1000 --window-sites 100 input.bcf",
+        );
+
+        assert_eq!(result.unwrap_err().kind(), ClapErrorKind::ArgumentConflict)
+    }
+
+    #[test]
+    fn test_window_step_requires_window() {
+        let result = try_parse_subcmd::<Create>("sfs create --window-step 10 input.bcf");
+
+        assert_eq!(
+            result.unwrap_err().kind(),
+            ClapErrorKind::MissingRequiredArgument
+        )
+    }
+
     #[test]
     fn test_project_strict_conflict() {
         let result = try_parse_subcmd::<Create>("sfs create -p 2 --strict input.bcf");
@@ -1,10 +1,16 @@
+use std::io::Write;
+
 use anyhow::{anyhow, Error};
 
 use sfs_core::{
     input::{
-        site::{self, Site},
-        ReadStatus,
+        counts,
+        site::{self, Located, SafSite, Site},
+        window::{Windowed, Windows},
+        ReadStatus, Window,
     },
+    saf::Em,
+    spectrum::{bootstrap::block_bootstrap, io::Labelled},
     Scs,
 };
 
@@ -13,31 +19,33 @@ pub struct Runner {
     strict: bool,
     sites: usize,
     skipped: usize,
+    unpolarized: usize,
+    uncallable: usize,
+    filtered: Vec<(String, usize)>,
+    filtered_genotypes: Vec<(String, usize)>,
+    counts_writer: Option<counts::Writer<Box<dyn Write>>>,
 }
 
 impl Runner {
-    fn handle_skipped_site(&mut self) -> Result<(), Error> {
+    fn handle_skipped_site(&mut self, reason: &str) -> Result<(), Error> {
         let contig = self.reader.current_contig();
         let position = self.reader.current_position();
 
         if self.strict {
             return Err(anyhow!(
-                "Missing or multiallelic genotype at site '{contig}:{position}' in strict mode. \
+                "Missing, multiallelic, or filtered genotype at site '{contig}:{position}' in strict mode. \
                 Filter BCF or disable strict mode and try again. \
                 Increase verbosity for more information."
             ));
         } else {
             if self.skipped == 0 {
                 log::info!(
-                    "Skipping site '{contig}:{position}' due to too many missing and/or \
-                    multiallelic genotypes. By default, this message will be shown only once, \
+                    "Skipping site '{contig}:{position}' due to {reason}. \
+                    By default, this message will be shown only once, \
                     with a summary at the end. Increase verbosity for more information."
                 );
             } else {
-                log::debug!(
-                    "Skipping site '{contig}:{position}' \
-                    due to too many missing and/or multiallelic genotypes."
-                );
+                log::debug!("Skipping site '{contig}:{position}' due to {reason}.");
             }
 
             self.skipped += 1;
@@ -45,10 +53,65 @@ impl Runner {
         Ok(())
     }
 
-    fn handle_skipped_samples(&self) {
+    fn handle_filtered_site(&mut self, filter: String) {
+        let contig = self.reader.current_contig();
+        let position = self.reader.current_position();
+
+        if self.filtered.is_empty() {
+            log::info!(
+                "Skipping site '{contig}:{position}' failing filter '{filter}'. \
+                By default, this message will be shown only once, with a summary at the end. \
+                Increase verbosity for more information."
+            );
+        } else {
+            log::debug!("Skipping site '{contig}:{position}' failing filter '{filter}'.");
+        }
+
+        increment(&mut self.filtered, filter);
+    }
+
+    fn handle_uncallable_site(&mut self) {
+        let contig = self.reader.current_contig();
+        let position = self.reader.current_position();
+
+        if self.uncallable == 0 {
+            log::info!(
+                "Skipping site '{contig}:{position}' outside callable regions. \
+                By default, this message will be shown only once, with a summary at the end. \
+                Increase verbosity for more information."
+            );
+        } else {
+            log::debug!("Skipping site '{contig}:{position}' outside callable regions.");
+        }
+
+        self.uncallable += 1;
+    }
+
+    fn handle_unpolarized_site(&mut self) {
         let contig = self.reader.current_contig();
         let position = self.reader.current_position();
 
+        if self.unpolarized == 0 {
+            log::info!(
+                "Skipping site '{contig}:{position}' due to unknown ancestral allele. \
+                By default, this message will be shown only once, with a summary at the end. \
+                Increase verbosity for more information."
+            );
+        } else {
+            log::debug!("Skipping site '{contig}:{position}' due to unknown ancestral allele.");
+        }
+
+        self.unpolarized += 1;
+    }
+
+    fn handle_skipped_samples(&mut self) {
+        let contig = self.reader.current_contig();
+        let position = self.reader.current_position();
+
+        for (_, filter) in self.reader.current_filtered_samples() {
+            increment(&mut self.filtered_genotypes, filter.to_string());
+        }
+
         for (sample, reason) in self
             .reader
             .current_skipped_samples()
@@ -66,22 +129,93 @@ impl Runner {
             strict,
             sites: 0,
             skipped: 0,
+            unpolarized: 0,
+            uncallable: 0,
+            filtered: Vec::new(),
+            filtered_genotypes: Vec::new(),
+            counts_writer: None,
         })
     }
 
-    pub fn run(&mut self) -> Result<Scs, Error> {
-        let mut scs = self.reader.create_zero_scs();
+    pub fn set_counts_writer(mut self, writer: Option<counts::Writer<Box<dyn Write>>>) -> Self {
+        self.counts_writer = writer;
+        self
+    }
 
+    fn write_counts(&mut self) -> Result<(), Error> {
+        if let Some(writer) = self.counts_writer.as_mut() {
+            writer.write_site(
+                self.reader.current_contig(),
+                self.reader.current_position(),
+                self.reader.current_counts(),
+                self.reader.current_totals(),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn for_each_site<F>(&mut self, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(&str, usize, Site<'_>),
+    {
+        self.for_each_stratified_site(|contig, position, site, _| f(contig, position, site))
+    }
+
+    fn for_each_stratified_site<F>(&mut self, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(&str, usize, Site<'_>, Option<usize>),
+    {
         loop {
-            match self.reader.read_site() {
-                ReadStatus::Read(Site::Standard(counts)) => {
-                    scs[&counts] += 1.0;
+            match self.reader.read_stratified_site() {
+                ReadStatus::Read((
+                    Located {
+                        contig,
+                        position,
+                        site: site @ (Site::Standard(_) | Site::Projected(_)),
+                    },
+                    stratum,
+                )) => {
+                    f(contig, position, site, stratum);
+                    self.write_counts()?;
                 }
-                ReadStatus::Read(Site::Projected(projected)) => {
-                    projected.add_unchecked(&mut scs);
+                ReadStatus::Read((
+                    Located {
+                        site: Site::InsufficientData,
+                        ..
+                    },
+                    _,
+                )) => {
+                    self.write_counts()?;
+                    self.handle_skipped_site("too many missing and/or multiallelic genotypes")?;
                 }
-                ReadStatus::Read(Site::InsufficientData) => {
-                    self.handle_skipped_site()?;
+                ReadStatus::Read((
+                    Located {
+                        site: Site::UnknownAncestral,
+                        ..
+                    },
+                    _,
+                )) => {
+                    self.handle_unpolarized_site();
+                }
+                ReadStatus::Read((
+                    Located {
+                        site: Site::Uncallable,
+                        ..
+                    },
+                    _,
+                )) => {
+                    self.handle_uncallable_site();
+                }
+                ReadStatus::Read((
+                    Located {
+                        site: Site::Filtered(filter),
+                        ..
+                    },
+                    _,
+                )) => {
+                    let filter = filter.to_string();
+                    self.handle_filtered_site(filter);
                 }
                 ReadStatus::Error(e) => {
                     return Err(anyhow!(
@@ -100,10 +234,286 @@ impl Runner {
 
         self.summarize_skipped();
 
+        if let Some(writer) = self.counts_writer.as_mut() {
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn for_each_saf_site<F>(&mut self, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(&str, usize, &[f64]) -> Result<(), Error>,
+    {
+        loop {
+            match self.reader.read_located_saf_site() {
+                ReadStatus::Read(Located {
+                    contig,
+                    position,
+                    site: SafSite::Likelihoods(site),
+                }) => {
+                    f(contig, position, site)?;
+                }
+                ReadStatus::Read(Located {
+                    site: SafSite::InsufficientData,
+                    ..
+                }) => {
+                    self.handle_skipped_site("multiallelic genotype likelihoods")?;
+                }
+                ReadStatus::Read(Located {
+                    site: SafSite::UnknownAncestral,
+                    ..
+                }) => {
+                    self.handle_unpolarized_site();
+                }
+                ReadStatus::Read(Located {
+                    site: SafSite::Uncallable,
+                    ..
+                }) => {
+                    self.handle_uncallable_site();
+                }
+                ReadStatus::Read(Located {
+                    site: SafSite::Filtered(filter),
+                    ..
+                }) => {
+                    let filter = filter.to_string();
+                    self.handle_filtered_site(filter);
+                }
+                ReadStatus::Error(e) => {
+                    return Err(anyhow!(
+                        "encountered genotype likelihood error at site '{}:{}': {e}",
+                        self.reader.current_contig(),
+                        self.reader.current_position()
+                    ))
+                }
+                ReadStatus::Done => break,
+            }
+
+            self.handle_skipped_samples();
+
+            self.sites += 1;
+        }
+
+        self.summarize_filtered();
+        if self.skipped > 0 {
+            log::info!(
+                "Skipped {skipped}/{total} sites due to multiallelic genotype likelihoods.",
+                skipped = self.skipped,
+                total = self.sites,
+            );
+        }
+        self.summarize_unpolarized();
+
+        Ok(())
+    }
+
+    pub fn run(&mut self) -> Result<Scs, Error> {
+        let mut scs = self.reader.create_zero_scs();
+
+        self.for_each_site(|_, _, site| match site {
+            Site::Standard(counts) => scs[counts] += 1.0,
+            Site::Projected(projected) => projected.add_unchecked(&mut scs),
+            _ => unreachable!("only called on included sites"),
+        })?;
+
+        if let Some(bases) = self.reader.callable_bases_without_records() {
+            log::info!("Adding {bases} callable bases without records as monomorphic sites.");
+
+            let monomorphic = vec![0; scs.dimensions()];
+            scs[monomorphic] += bases as f64;
+        }
+
         Ok(scs)
     }
 
+    pub fn run_bootstrap(
+        &mut self,
+        blocks: Window,
+        replicates: usize,
+        seed: u64,
+    ) -> Result<Vec<Scs>, Error> {
+        let blocks = self
+            .run_windows(blocks)?
+            .into_iter()
+            .map(|block| block.value)
+            .collect::<Vec<_>>();
+
+        if blocks.is_empty() {
+            return Err(anyhow!("cannot bootstrap without any sites"));
+        }
+
+        log::debug!(
+            "Creating {replicates} bootstrap replicates from {n} blocks.",
+            n = blocks.len()
+        );
+
+        Ok(block_bootstrap(&blocks, replicates, seed))
+    }
+
+    pub fn run_likelihoods(&mut self, em: Em) -> Result<Scs, Error> {
+        let mut saf = self.reader.create_empty_saf();
+
+        self.for_each_saf_site(|_, _, site| saf.push(site).map_err(Error::from))?;
+
+        log::debug!(
+            "Estimating SFS from {sites} sites using EM.",
+            sites = saf.sites()
+        );
+        let estimate = em.estimate(&saf);
+
+        if estimate.converged {
+            log::info!(
+                "EM converged after {iterations} iterations with log-likelihood {log_likelihood:.6}.",
+                iterations = estimate.iterations,
+                log_likelihood = estimate.log_likelihood,
+            );
+        } else if !saf.is_empty() {
+            log::warn!(
+                "EM did not converge within {iterations} iterations. \
+                Consider increasing the maximum number of iterations.",
+                iterations = estimate.iterations,
+            );
+        }
+
+        Ok(estimate.scs)
+    }
+
+    pub fn run_likelihoods_windows(
+        &mut self,
+        em: Em,
+        window: Window,
+    ) -> Result<Vec<Windowed<Scs>>, Error> {
+        let mut windows = Windows::new(window, self.reader.create_empty_saf());
+        let mut finished = Vec::new();
+
+        self.for_each_saf_site(|contig, position, site| {
+            finished.extend(windows.push(contig, position));
+
+            windows
+                .iter_mut()
+                .try_for_each(|saf| saf.push(site))
+                .map_err(Error::from)
+        })?;
+        finished.extend(windows.finish());
+
+        log::debug!(
+            "Estimating SFS in {windows} windows using EM.",
+            windows = finished.len()
+        );
+        let mut unconverged = 0;
+        let estimated = finished
+            .into_iter()
+            .map(|Windowed { region, value }| {
+                let estimate = em.estimate(&value);
+
+                log::debug!(
+                    "EM {status} after {iterations} iterations in window '{region}'.",
+                    status = if estimate.converged {
+                        "converged"
+                    } else {
+                        "did not converge"
+                    },
+                    iterations = estimate.iterations,
+                );
+                if !estimate.converged && !value.is_empty() {
+                    unconverged += 1;
+                }
+
+                Windowed {
+                    region,
+                    value: estimate.scs,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if unconverged > 0 {
+            log::warn!(
+                "EM did not converge in {unconverged}/{total} windows. \
+                Consider increasing the maximum number of iterations.",
+                total = estimated.len(),
+            );
+        }
+
+        Ok(estimated)
+    }
+
+    pub fn run_strata(&mut self) -> Result<Vec<Labelled<Scs>>, Error> {
+        let strata = self
+            .reader
+            .strata()
+            .ok_or_else(|| anyhow!("no stratification set"))?;
+        let mut spectra = vec![self.reader.create_zero_scs(); strata.len()];
+
+        let mut unstratified = 0;
+        self.for_each_stratified_site(|_, _, site, stratum| {
+            let Some(scs) = stratum.map(|i| &mut spectra[i]) else {
+                unstratified += 1;
+                return;
+            };
+
+            match site {
+                Site::Standard(counts) => scs[counts] += 1.0,
+                Site::Projected(projected) => projected.add_unchecked(scs),
+                _ => unreachable!("only called on included sites"),
+            }
+        })?;
+
+        if unstratified > 0 {
+            log::info!(
+                "Skipped {unstratified}/{total} sites not belonging to any stratum.",
+                total = self.sites,
+            );
+        }
+
+        Ok(strata
+            .into_iter()
+            .zip(spectra)
+            .map(|(label, value)| Labelled { label, value })
+            .collect())
+    }
+
+    pub fn run_windows(&mut self, window: Window) -> Result<Vec<Windowed<Scs>>, Error> {
+        let mut windows = Windows::new(window, self.reader.create_zero_scs());
+        let mut finished = Vec::new();
+
+        self.for_each_site(|contig, position, site| {
+            finished.extend(windows.push(contig, position));
+
+            match site {
+                Site::Standard(counts) => windows.iter_mut().for_each(|scs| scs[counts] += 1.0),
+                Site::Projected(projected) => projected.add_unchecked_to_each(windows.iter_mut()),
+                _ => unreachable!("only called on included sites"),
+            }
+        })?;
+        finished.extend(windows.finish());
+
+        Ok(finished)
+    }
+
+    fn summarize_filtered(&self) {
+        if self.uncallable > 0 {
+            log::info!(
+                "Skipped {uncallable}/{total} sites outside callable regions.",
+                uncallable = self.uncallable,
+                total = self.sites,
+            );
+        }
+
+        for (filter, filtered) in self.filtered.iter() {
+            log::info!(
+                "Skipped {filtered}/{total} sites failing filter '{filter}'.",
+                total = self.sites,
+            );
+        }
+
+        for (filter, filtered) in self.filtered_genotypes.iter() {
+            log::info!("Skipped {filtered} genotypes failing filter '{filter}'.");
+        }
+    }
+
     fn summarize_skipped(&self) {
+        self.summarize_filtered();
+
         if self.skipped > 0 {
             log::info!(
                 "Skipped {skipped}/{total} sites due to missing and/or multiallelic genotypes. \
@@ -112,5 +522,25 @@ impl Runner {
                 total = self.sites,
             );
         }
+
+        self.summarize_unpolarized();
+    }
+
+    fn summarize_unpolarized(&self) {
+        if self.unpolarized > 0 {
+            log::info!(
+                "Skipped {unpolarized}/{total} sites due to unknown ancestral allele.",
+                unpolarized = self.unpolarized,
+                total = self.sites,
+            );
+        }
+    }
+}
+
+/// Increments the count associated with the key, adding the key if not present.
+fn increment(counts: &mut Vec<(String, usize)>, key: String) {
+    match counts.iter_mut().find(|(k, _)| *k == key) {
+        Some((_, count)) => *count += 1,
+        None => counts.push((key, 1)),
     }
 }
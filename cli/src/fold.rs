@@ -61,14 +61,24 @@ impl From<Fill> for f64 {
 
 impl Fold {
     pub fn run(self) -> Result<(), Error> {
-        let mut scs = spectrum::io::read::Builder::default()
+        let (mut scs, mut metadata) = spectrum::io::read::Builder::default()
             .set_input(Input::new(self.input)?)
-            .read()?;
+            .read_with_metadata()?;
 
-        scs = scs.fold().into_spectrum(f64::from(self.fill));
+        let folded = scs.fold();
+        scs = folded.into_spectrum(f64::from(self.fill));
+        metadata.folded = true;
+
+        // Elements folded away are added to any existing mask
+        if let Some(mask) = metadata.mask.as_mut() {
+            mask.iter_mut()
+                .zip(folded.mask())
+                .for_each(|(masked, folded)| *masked |= folded);
+        }
 
         spectrum::io::write::Builder::default()
             .set_precision(self.precision)
+            .set_metadata(metadata)
             .write_to_path_or_stdout(self.output, &scs)?;
 
         Ok(())
@@ -0,0 +1,198 @@
+use std::{
+    io::{self, Write},
+    num::NonZeroUsize,
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, Error};
+
+use clap::Parser;
+
+use sfs_core::{
+    input::{genotype, pairwise::Pairwise, ReadStatus},
+    spectrum::{self, io::Metadata},
+    Input,
+};
+
+/// Calculate kinship statistics for all pairs of samples.
+///
+/// For each pair of diploid samples, a 3x3 two-sample SFS is created from all sites where both
+/// samples have a genotype, and the King, R0, and R1 kinship statistics are calculated from it.
+/// See Waples et al. (2019). All pairs are handled in a single pass over the input.
+#[derive(Debug, Parser)]
+#[clap(name = crate::NAME, about)]
+pub struct Kinship {
+    /// Input VCF/BCF or PLINK .bed.
+    ///
+    /// If no file is provided, stdin will be used. See the `create` subcommand for details on
+    /// supported input. Multiple files may be provided, in which case they are read one after the
+    /// other. All files must have the same samples in the same order.
+    #[arg(value_name = "FILE")]
+    input: Vec<PathBuf>,
+
+    /// Samples.
+    ///
+    /// By default, all samples in the input are used. Using this argument, only pairs of the
+    /// provided samples are used, in the provided order. Multiple, comma-separated values may be
+    /// provided.
+    #[arg(
+        short = 's',
+        long,
+        use_value_delimiter = true,
+        value_name = "SAMPLE,..."
+    )]
+    samples: Option<Vec<String>>,
+
+    /// Output pairwise SFS.
+    ///
+    /// By default, a table of kinship statistics is output with one row per pair of samples.
+    /// Using this flag, the pairwise 3x3 SFS are instead output in plain text format, one after
+    /// the other, with the names of the samples as population names.
+    #[arg(long, conflicts_with_all = ["delimiter", "header", "precision"])]
+    spectra: bool,
+
+    /// Delimiter between columns.
+    #[arg(short = 'd', long, default_value_t = ',', value_name = "CHAR")]
+    delimiter: char,
+
+    /// Include a header with the names of columns.
+    #[arg(short = 'H', long)]
+    header: bool,
+
+    /// Precision to use when printing statistics.
+    #[arg(short = 'p', long, default_value_t = 6, value_name = "INT")]
+    precision: usize,
+
+    /// Number of threads.
+    ///
+    /// Multi-threading currently only affects reading and parsing BGZF compressed input.
+    #[arg(short = 't', long, default_value_t = NonZeroUsize::new(4).unwrap(), value_name = "INT")]
+    threads: NonZeroUsize,
+}
+
+impl Kinship {
+    pub fn run(self) -> Result<(), Error> {
+        let inputs = if self.input.is_empty() {
+            vec![Input::new(None)?]
+        } else {
+            self.input
+                .into_iter()
+                .map(|path| Input::new(Some(path)))
+                .collect::<Result<_, _>>()?
+        };
+
+        let mut reader = genotype::reader::Builder::default()
+            .set_inputs(inputs)
+            .set_threads(self.threads)
+            .build()?;
+
+        let indices = match self.samples {
+            Some(samples) => samples
+                .into_iter()
+                .map(|sample| {
+                    reader
+                        .samples()
+                        .iter()
+                        .position(|s| s.as_ref() == sample)
+                        .ok_or_else(|| anyhow!("unknown sample {sample}"))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => (0..reader.samples().len()).collect(),
+        };
+
+        if indices.len() < 2 {
+            return Err(anyhow!("at least two samples are required"));
+        }
+
+        let mut pairwise = Pairwise::new(
+            indices
+                .iter()
+                .map(|&i| reader.samples()[i].clone())
+                .collect(),
+        );
+        log::debug!(
+            "Creating SFS for {pairs} pairs of samples.",
+            pairs = indices.len() * (indices.len() - 1) / 2
+        );
+
+        let mut sites = 0;
+        loop {
+            match reader.read_genotypes() {
+                ReadStatus::Read(genotypes) => {
+                    pairwise
+                        .add_genotypes(indices.iter().map(|&i| &genotypes[i]))
+                        .map_err(|e| {
+                            anyhow!(
+                                "encountered genotype error at site '{}:{}': {e}",
+                                reader.current_contig(),
+                                reader.current_position()
+                            )
+                        })?;
+
+                    sites += 1;
+                }
+                ReadStatus::Error(e) => return Err(e.into()),
+                ReadStatus::Done => break,
+            }
+        }
+        log::info!("Read {sites} sites.");
+
+        let mut writer = io::stdout().lock();
+        if self.spectra {
+            for (first, second, scs) in pairwise.iter() {
+                spectrum::io::write::Builder::default()
+                    .set_precision(0)
+                    .set_metadata(Metadata {
+                        populations: Some(vec![
+                            first.as_ref().to_string(),
+                            second.as_ref().to_string(),
+                        ]),
+                        ..Default::default()
+                    })
+                    .write(&mut writer, &scs)?;
+            }
+        } else {
+            let delimiter = self.delimiter;
+            let precision = self.precision;
+
+            if self.header {
+                writeln!(
+                    writer,
+                    "sample_1{delimiter}sample_2{delimiter}king{delimiter}r0{delimiter}r1"
+                )?;
+            }
+
+            for (first, second, scs) in pairwise.iter() {
+                let king = scs.king()?;
+                let r0 = scs.r0()?;
+                let r1 = scs.r1()?;
+
+                writeln!(
+                    writer,
+                    "{first}{delimiter}{second}{delimiter}{king:.precision$}{delimiter}\
+                    {r0:.precision$}{delimiter}{r1:.precision$}",
+                    first = first.as_ref(),
+                    second = second.as_ref(),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use clap::error::ErrorKind as ClapErrorKind;
+
+    use crate::tests::try_parse_subcmd;
+
+    #[test]
+    fn test_spectra_and_header_conflict() {
+        let result = try_parse_subcmd::<Kinship>("sfs kinship --spectra -H input.vcf");
+
+        assert_eq!(result.unwrap_err().kind(), ClapErrorKind::ArgumentConflict)
+    }
+}
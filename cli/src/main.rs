@@ -1,17 +1,29 @@
 #![deny(unsafe_code)]
 
-use std::io::Write;
+use std::{io::Write, str::FromStr};
 
 use anyhow::Error;
 
 use clap::{ArgAction, Parser, Subcommand};
 
+mod arithmetic;
+use arithmetic::{Add, Scale, Sub};
+
+mod counts;
+use counts::Counts;
+
 mod create;
 use create::Create;
 
 mod fold;
 use fold::Fold;
 
+mod kinship;
+use kinship::Kinship;
+
+mod saf;
+use saf::Saf;
+
 mod stat;
 use stat::Stat;
 
@@ -22,6 +34,17 @@ pub(crate) const NAME: &str = env!("CARGO_BIN_NAME");
 pub(crate) const VERSION: &str = env!("CARGO_PKG_VERSION");
 pub(crate) const AUTHOR: &str = env!("CARGO_PKG_AUTHORS");
 
+/// Parses a value optionally prefixed by a population name, as in `[POPULATION=]VALUE`.
+pub(crate) fn parse_population_value<T>(s: &str) -> Result<(Option<String>, T), T::Err>
+where
+    T: FromStr,
+{
+    match s.split_once('=') {
+        Some((population, value)) => value.parse().map(|v| (Some(population.to_string()), v)),
+        None => s.parse().map(|v| (None, v)),
+    }
+}
+
 /// Tools for working with site frequency spectra.
 #[derive(Debug, Parser)]
 #[clap(name = NAME, author = AUTHOR, version = VERSION, about)]
@@ -92,25 +115,61 @@ impl Cli {
     }
 }
 
+// Command is only constructed once when parsing arguments, so size difference is irrelevant
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Subcommand)]
 pub enum Command {
+    Add(Add),
+    Counts(Counts),
     Create(Create),
     Fold(Fold),
+    Kinship(Kinship),
+    Saf(Saf),
+    Scale(Scale),
     Stat(Stat),
+    Sub(Sub),
     View(View),
 }
 
 impl Command {
     fn run(self) -> Result<(), Error> {
         match self {
+            Command::Add(add) => add.run(),
+            Command::Counts(counts) => counts.run(),
             Command::Create(create) => create.run(),
             Command::Fold(fold) => fold.run(),
+            Command::Kinship(kinship) => kinship.run(),
+            Command::Saf(saf) => saf.run(),
+            Command::Scale(scale) => scale.run(),
             Command::Stat(stat) => stat.run(),
+            Command::Sub(sub) => sub.run(),
             Command::View(view) => view.run(),
         }
     }
 }
 
+impl TryFrom<Command> for Add {
+    type Error = Command;
+
+    fn try_from(command: Command) -> Result<Self, Self::Error> {
+        match command {
+            Command::Add(add) => Ok(add),
+            _ => Err(command),
+        }
+    }
+}
+
+impl TryFrom<Command> for Counts {
+    type Error = Command;
+
+    fn try_from(command: Command) -> Result<Self, Self::Error> {
+        match command {
+            Command::Counts(counts) => Ok(counts),
+            _ => Err(command),
+        }
+    }
+}
+
 impl TryFrom<Command> for Create {
     type Error = Command;
 
@@ -133,6 +192,39 @@ impl TryFrom<Command> for Fold {
     }
 }
 
+impl TryFrom<Command> for Kinship {
+    type Error = Command;
+
+    fn try_from(command: Command) -> Result<Self, Self::Error> {
+        match command {
+            Command::Kinship(kinship) => Ok(kinship),
+            _ => Err(command),
+        }
+    }
+}
+
+impl TryFrom<Command> for Saf {
+    type Error = Command;
+
+    fn try_from(command: Command) -> Result<Self, Self::Error> {
+        match command {
+            Command::Saf(saf) => Ok(saf),
+            _ => Err(command),
+        }
+    }
+}
+
+impl TryFrom<Command> for Scale {
+    type Error = Command;
+
+    fn try_from(command: Command) -> Result<Self, Self::Error> {
+        match command {
+            Command::Scale(scale) => Ok(scale),
+            _ => Err(command),
+        }
+    }
+}
+
 impl TryFrom<Command> for Stat {
     type Error = Command;
 
@@ -144,6 +236,17 @@ impl TryFrom<Command> for Stat {
     }
 }
 
+impl TryFrom<Command> for Sub {
+    type Error = Command;
+
+    fn try_from(command: Command) -> Result<Self, Self::Error> {
+        match command {
+            Command::Sub(sub) => Ok(sub),
+            _ => Err(command),
+        }
+    }
+}
+
 impl TryFrom<Command> for View {
     type Error = Command;
 
@@ -0,0 +1,149 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Error;
+
+use clap::Parser;
+
+use sfs_core::{
+    saf::{angsd, Em},
+    spectrum::{self, io::Metadata},
+};
+
+/// Estimate SFS from ANGSD SAF files.
+#[derive(Debug, Parser)]
+#[clap(name = crate::NAME, about)]
+pub struct Saf {
+    /// Input SAF index files.
+    ///
+    /// One `.saf.idx` file should be provided for each population, with the corresponding
+    /// `.saf.pos.gz` and `.saf.gz` files next to it, as written by `angsd -doSaf`. A 1D SFS is
+    /// estimated from a single population, and a joint SFS from multiple populations, using only
+    /// the sites shared by all populations. Only version 3 of the SAF format is supported.
+    #[arg(required = true, value_name = "FILE")]
+    inputs: Vec<PathBuf>,
+
+    /// Maximum number of EM iterations.
+    #[arg(long, default_value_t = 100, value_name = "INT")]
+    max_iterations: usize,
+
+    /// EM tolerance.
+    ///
+    /// The EM algorithm stops when the difference in log-likelihood between two successive
+    /// iterations falls below this value.
+    #[arg(long, default_value_t = 1e-6, value_name = "FLOAT")]
+    tolerance: f64,
+
+    /// Output SFS path.
+    ///
+    /// If no path is given, SFS will be output to stdout.
+    #[arg(short = 'o', long, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Output precision.
+    #[arg(long, default_value_t = 6, value_name = "INT")]
+    precision: usize,
+}
+
+impl Saf {
+    pub fn run(self) -> Result<(), Error> {
+        let mut readers = self
+            .inputs
+            .iter()
+            .map(angsd::Reader::from_index_path)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (path, reader) in self.inputs.iter().zip(readers.iter()) {
+            log::debug!(
+                "Found {sites} sites for {alleles} alleles in '{path}'.",
+                sites = reader.index().sites(),
+                alleles = reader.index().alleles(),
+                path = path.display(),
+            );
+        }
+
+        let saf = angsd::read_intersect(&mut readers)?;
+        if readers.len() > 1 {
+            log::info!(
+                "Found {sites} sites shared by all populations.",
+                sites = saf.sites()
+            );
+        }
+
+        log::debug!(
+            "Estimating SFS from {sites} sites using EM.",
+            sites = saf.sites()
+        );
+        let estimate = Em::default()
+            .set_max_iterations(self.max_iterations)
+            .set_tolerance(self.tolerance)
+            .estimate(&saf);
+
+        if estimate.converged {
+            log::info!(
+                "EM converged after {iterations} iterations with log-likelihood {log_likelihood:.6}.",
+                iterations = estimate.iterations,
+                log_likelihood = estimate.log_likelihood,
+            );
+        } else if !saf.is_empty() {
+            log::warn!(
+                "EM did not converge within {iterations} iterations. \
+                Consider increasing the maximum number of iterations.",
+                iterations = estimate.iterations,
+            );
+        }
+
+        let metadata = Metadata {
+            populations: self
+                .inputs
+                .iter()
+                .map(|path| population_name(path))
+                .collect(),
+            ..Default::default()
+        };
+
+        spectrum::io::write::Builder::default()
+            .set_precision(self.precision)
+            .set_metadata(metadata)
+            .write_to_path_or_stdout(self.output, &estimate.scs)?;
+
+        Ok(())
+    }
+}
+
+/// Returns the population name given by the file name of an index path, e.g. `pop` for
+/// `dir/pop.saf.idx`.
+fn population_name(path: &Path) -> Option<String> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_suffix(".saf.idx"))
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use clap::error::ErrorKind as ClapErrorKind;
+
+    use crate::tests::try_parse_subcmd;
+
+    #[test]
+    fn test_inputs_required() {
+        let result = try_parse_subcmd::<Saf>("sfs saf");
+
+        assert_eq!(
+            result.unwrap_err().kind(),
+            ClapErrorKind::MissingRequiredArgument
+        );
+    }
+
+    #[test]
+    fn test_population_name() {
+        assert_eq!(
+            population_name(Path::new("dir/pop.saf.idx")),
+            Some(String::from("pop"))
+        );
+        assert_eq!(population_name(Path::new("pop.idx")), None);
+    }
+}
@@ -4,12 +4,14 @@ use anyhow::Error;
 
 use clap::{CommandFactory, Parser, ValueEnum};
 use sfs_core::{
-    spectrum::{self, Scs},
+    spectrum::{self, io::read, Scs},
     Input,
 };
 
+use crate::view::select_label;
+
 mod runner;
-use runner::{Runner, StatisticWithOptions};
+use runner::{Runner, Spectra, StatisticWithOptions};
 
 /// Calculate statistics from SFS.
 #[derive(Debug, Parser)]
@@ -19,17 +21,41 @@ pub struct Stat {
     ///
     /// The input SFS can be provided here or read from stdin. The SFS will be normalised as
     /// required for particular statistics, so the input SFS does not need to be normalised.
+    /// The input may also contain several spectra in plain text or npz format, such as windows,
+    /// bootstrap replicates, or stratified spectra created by `sfs create`, in which case
+    /// statistics are calculated for each spectrum, with one row per spectrum. Each row starts
+    /// with the window coordinates where all spectra have a region, and the label otherwise.
     #[clap(value_parser, value_name = "PATH")]
     pub input: Option<PathBuf>,
 
+    /// Input label.
+    ///
+    /// Where the input contains several spectra, calculate statistics only for the spectrum with
+    /// the provided label, such as `C>T` for spectra stratified by mutation class.
+    #[clap(short = 'l', long, value_name = "LABEL")]
+    pub label: Option<String>,
+
     /// Delimiter between statistics.
     #[clap(short = 'd', long, default_value_t = ',', value_name = "CHAR")]
     pub delimiter: char,
 
     /// Include a header with the names of statistics.
+    ///
+    /// For several spectra, the header also includes the names of the window or label columns.
     #[clap(short = 'H', long)]
     pub header: bool,
 
+    /// Estimate standard errors using a block jackknife.
+    ///
+    /// Using this flag, the input must contain windowed spectra, as created by `sfs create` using
+    /// tiled windows, where each window is used as a block. Windows must not overlap, so sliding
+    /// windows created using a window step smaller than the window size cannot be used. Statistics
+    /// are then calculated from the sum of all blocks, and each is followed by its weighted block
+    /// jackknife standard error and Z-score, with blocks weighted by their number of sites. See
+    /// Busing et al. (1999).
+    #[clap(short = 'j', long)]
+    pub jackknife: bool,
+
     /// Precision to use when printing statistics.
     ///
     /// If a single value is provided, this will be used for all statistics. If more than one
@@ -144,9 +170,14 @@ impl fmt::Display for Statistic {
 
 impl Stat {
     pub fn run(self) -> Result<(), Error> {
-        let scs = spectrum::io::read::Builder::default()
+        let spectra = spectrum::io::read::Builder::default()
             .set_input(Input::new(self.input)?)
-            .read()?;
+            .read_spectra()?;
+
+        let spectra = match select_label(spectra, self.label.as_deref())? {
+            read::Spectra::Single(scs, _) => Spectra::Single(scs),
+            read::Spectra::Container(mut reader) => Spectra::Labelled(reader.read_records()?),
+        };
 
         let statistics = match (&self.precision[..], &self.statistics[..]) {
             (&[precision], statistics) => statistics
@@ -174,7 +205,13 @@ impl Stat {
             }
         };
 
-        let mut runner = Runner::new(scs, statistics, self.header, self.delimiter);
+        let mut runner = Runner::new(
+            spectra,
+            statistics,
+            self.header,
+            self.jackknife,
+            self.delimiter,
+        );
         runner.run()
     }
 }
@@ -2,10 +2,23 @@ use std::{fmt, io};
 
 use anyhow::{anyhow, Error};
 
-use sfs_core::Scs;
+use sfs_core::{
+    input::{window::Windowed, Region},
+    spectrum::{io::container::Record, jackknife::windowed_block_jackknife},
+    Scs,
+};
 
 use super::Statistic;
 
+/// The names of the columns giving the window coordinates for windowed input.
+const WINDOW_HEADER_NAMES: [&str; 3] = ["contig", "start", "end"];
+
+/// The name of the column giving the label for labelled input without regions.
+const LABEL_HEADER_NAME: &str = "label";
+
+/// The suffixes of the columns following each statistic when using a block jackknife.
+const JACKKNIFE_HEADER_SUFFIXES: [&str; 2] = ["se", "z"];
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct StatisticWithOptions {
     statistic: Statistic,
@@ -21,27 +34,52 @@ impl StatisticWithOptions {
     }
 }
 
+/// The spectra to calculate statistics for.
+#[derive(Debug)]
+pub enum Spectra {
+    /// A single spectrum.
+    Single(Scs),
+    /// Several labelled spectra, as read from a container.
+    Labelled(Vec<Record>),
+}
+
+impl Spectra {
+    /// Returns the regions of all spectra if labelled and all have a region, otherwise `None`.
+    fn regions(&self) -> Option<Vec<&Region>> {
+        match self {
+            Spectra::Single(_) => None,
+            Spectra::Labelled(records) => records
+                .iter()
+                .map(|record| record.value.1.region.as_ref())
+                .collect(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Runner<W> {
     writer: W,
-    scs: Scs,
+    spectra: Spectra,
     statistics: Vec<StatisticWithOptions>,
     header: bool,
+    jackknife: bool,
     delimiter: char,
 }
 
 impl Runner<io::StdoutLock<'static>> {
     pub fn new(
-        scs: Scs,
+        spectra: Spectra,
         statistics: Vec<StatisticWithOptions>,
         header: bool,
+        jackknife: bool,
         delimiter: char,
     ) -> Self {
         Self {
             writer: io::stdout().lock(),
-            scs,
+            spectra,
             statistics,
             header,
+            jackknife,
             delimiter,
         }
     }
@@ -60,26 +98,86 @@ where
     }
 
     fn write_header(&mut self) -> Result<(), Error> {
-        let header_names = self
-            .statistics
+        if self.jackknife {
+            let header_names = self
+                .statistics
+                .iter()
+                .flat_map(|s| {
+                    let name = s.statistic.header_name();
+
+                    std::iter::once(name.to_string()).chain(
+                        JACKKNIFE_HEADER_SUFFIXES
+                            .iter()
+                            .map(move |suffix| format!("{name}_{suffix}")),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            return self.write_with_delimiter(header_names);
+        }
+
+        let label_names = match (&self.spectra, self.spectra.regions()) {
+            (Spectra::Single(_), _) => &[][..],
+            (Spectra::Labelled(_), Some(_)) => &WINDOW_HEADER_NAMES[..],
+            (Spectra::Labelled(_), None) => &[LABEL_HEADER_NAME][..],
+        };
+
+        let header_names = label_names
             .iter()
-            .map(|s| s.statistic.header_name())
+            .copied()
+            .chain(self.statistics.iter().map(|s| s.statistic.header_name()))
             .collect::<Vec<_>>();
 
         self.write_with_delimiter(header_names)
     }
 
     fn write_statistics(&mut self) -> Result<(), Error> {
-        let statistics = self
-            .statistics
-            .iter()
-            .map(|s| match s.statistic.calculate(&self.scs) {
-                Ok(stat) => Ok(format!("{stat:.precision$}", precision = s.precision)),
-                Err(e) => Err(anyhow!(e)),
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+        let rows = match (&self.spectra, self.spectra.regions()) {
+            (Spectra::Labelled(records), Some(regions)) if self.jackknife => {
+                let blocks = records
+                    .iter()
+                    .zip(regions)
+                    .map(|(record, region)| Windowed {
+                        region: region.clone(),
+                        value: record.value.0.clone(),
+                    })
+                    .collect::<Vec<_>>();
 
-        self.write_with_delimiter(statistics)
+                vec![jackknife_statistics(&self.statistics, &blocks)?]
+            }
+            _ if self.jackknife => {
+                return Err(anyhow!(
+                    "block jackknife requires windowed input with one spectrum per block"
+                ))
+            }
+            (Spectra::Single(scs), _) => vec![calculate_statistics(&self.statistics, scs)?],
+            (Spectra::Labelled(records), regions) => records
+                .iter()
+                .map(|record| {
+                    let (scs, metadata) = &record.value;
+                    let columns = match (&regions, &metadata.region) {
+                        (Some(_), Some(region)) => {
+                            vec![
+                                region.contig().to_string(),
+                                region.start().to_string(),
+                                region.end().map(|end| end.to_string()).unwrap_or_default(),
+                            ]
+                        }
+                        _ => vec![record.label.clone()],
+                    };
+
+                    calculate_statistics(&self.statistics, scs)
+                        .map(|statistics| columns.into_iter().chain(statistics).collect())
+                        .map_err(|e| anyhow!("{e} in spectrum '{}'", record.label))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+
+        for row in rows {
+            self.write_with_delimiter(row)?;
+        }
+
+        Ok(())
     }
 
     fn write_with_delimiter<I>(&mut self, items: I) -> Result<(), Error>
@@ -98,3 +196,39 @@ where
         Ok(())
     }
 }
+
+fn calculate_statistics(
+    statistics: &[StatisticWithOptions],
+    scs: &Scs,
+) -> Result<Vec<String>, Error> {
+    statistics
+        .iter()
+        .map(|s| match s.statistic.calculate(scs) {
+            Ok(stat) => Ok(format!("{stat:.precision$}", precision = s.precision)),
+            Err(e) => Err(anyhow!(e)),
+        })
+        .collect()
+}
+
+fn jackknife_statistics(
+    statistics: &[StatisticWithOptions],
+    blocks: &[Windowed<Scs>],
+) -> Result<Vec<String>, Error> {
+    let mut row = Vec::with_capacity(statistics.len() * 3);
+
+    for s in statistics {
+        let jackknife = windowed_block_jackknife(blocks, |scs| s.statistic.calculate(scs))
+            .map_err(|e| anyhow!("{e} for statistic '{}'", s.statistic))?;
+
+        row.extend(
+            [
+                jackknife.estimate,
+                jackknife.standard_error,
+                jackknife.z_score(),
+            ]
+            .map(|x| format!("{x:.precision$}", precision = s.precision)),
+        );
+    }
+
+    Ok(row)
+}
@@ -1,14 +1,27 @@
-use std::{fmt, path::PathBuf};
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+};
 
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 
 use clap::{Args, Parser, ValueEnum};
 
 use sfs_core::{
     array::{Axis, Shape},
-    spectrum, Input,
+    input::ReadStatus,
+    spectrum::{
+        self,
+        io::{read::Spectra, Labelled, Metadata},
+        Scs,
+    },
+    Input,
 };
 
+use crate::parse_population_value;
+
 /// Format, marginalize, project, and convert SFS.
 ///
 /// Note that the order of operations matter, and the order is: marginalization > projection >
@@ -20,9 +33,41 @@ pub struct View {
     /// Input SFS.
     ///
     /// The input SFS can be provided here or read from stdin in any of the supported formats.
+    /// The input may also contain several spectra in plain text or npz format, such as windows,
+    /// bootstrap replicates, or stratified spectra created by `sfs create`, in which case each
+    /// spectrum is processed in turn and written with its label.
     #[clap(value_parser, value_name = "PATH")]
     pub input: Option<PathBuf>,
 
+    /// Input format.
+    ///
+    /// If unset, the input format will be detected automatically. The ANGSD format, as output by
+    /// `realSFS`, cannot be detected and must be provided explicitly together with
+    /// `--input-shape`.
+    #[clap(short = 'I', long, value_name = "FORMAT")]
+    pub input_format: Option<Format>,
+
+    /// Input label.
+    ///
+    /// Where the input contains several spectra, select the spectrum with the provided label,
+    /// such as `C>T` for spectra stratified by mutation class, or `chr1:1-100` for windows. The
+    /// selected spectrum is output on its own, and may be output in any format.
+    #[clap(short = 'l', long, value_name = "LABEL")]
+    pub label: Option<String>,
+
+    /// Input shape.
+    ///
+    /// The shape of the input SFS as a comma-separated list of values, which is required when
+    /// reading the ANGSD format, since it does not store the shape. For example, a
+    /// two-dimensional SFS from `realSFS` for 5 and 3 diploid individuals has shape `11,7`.
+    #[clap(
+        long,
+        use_value_delimiter = true,
+        requires = "input_format",
+        value_name = "INT,..."
+    )]
+    pub input_shape: Option<Vec<usize>>,
+
     /// Output path.
     ///
     /// If no path is given, SFS will be output to stdout.
@@ -30,6 +75,12 @@ pub struct View {
     pub output: Option<PathBuf>,
 
     /// Output format.
+    ///
+    /// fastsimcoal2 expects the file name to end with a suffix depending on the number of
+    /// dimensions and folding, namely `_DAFpop0.obs` for 1-dimensional, `_jointDAFpop1_0.obs` for
+    /// 2-dimensional, and `_MSFS.obs` for multi-dimensional SFS, where `DAF` is replaced by `MAF`
+    /// when folded. A warning is given if the output path does not match. Several spectra can only
+    /// be output in plain text or npz format.
     #[clap(short = 'O', long, default_value_t = Format::Text, value_name = "FORMAT")]
     pub output_format: Format,
 
@@ -49,7 +100,8 @@ pub struct View {
 
     /// Print precision.
     ///
-    /// This is only used for printing SFS to plain text format, and will be ignored otherwise.
+    /// This is only used for printing SFS to plain text, dadi, fastsimcoal2, or ANGSD format,
+    /// and will be ignored otherwise.
     #[clap(long, default_value_t = 6, value_name = "INT")]
     pub precision: usize,
 }
@@ -61,15 +113,15 @@ struct Marginalize {
     ///
     /// Marginalize out provided populations. Marginalization corresponds to an
     /// array sum over the SFS seen as an array. Use a comma-separated list of 0-based dimensions
-    /// to  keep, using the same ordering of the dimensions of the SFS as specified e.g. in the
-    /// header.
+    /// to remove, using the same ordering of the dimensions of the SFS as specified e.g. in the
+    /// header. Where the input has population names, these may be used instead of dimensions.
     #[clap(
         short = 'm',
         long = "marginalize-remove",
         use_value_delimiter = true,
-        value_name = "INT,..."
+        value_name = "POPULATION,..."
     )]
-    pub remove: Option<Vec<usize>>,
+    pub remove: Option<Vec<String>>,
 
     /// Marginalize remaining populations.
     ///
@@ -80,9 +132,9 @@ struct Marginalize {
         short = 'M',
         long = "marginalize-keep",
         use_value_delimiter = true,
-        value_name = "INT,..."
+        value_name = "POPULATION,..."
     )]
-    pub keep: Option<Vec<usize>>,
+    pub keep: Option<Vec<String>>,
 }
 
 #[derive(Args, Debug, Eq, PartialEq)]
@@ -93,7 +145,9 @@ struct Project {
     /// Using this argument, it is possible to project the SFS down to a lower number of
     /// individuals.  Use a comma-separated list of values giving the new shape of the SFS.
     /// For example, `--project-individuals 3,2` would project a two-dimensional SFS down to three
-    /// individuals in the first dimension and two in the second.
+    /// individuals in the first dimension and two in the second. Where the input has population
+    /// names, populations may also be addressed by name using `population=INT` pairs, in which
+    /// case each population must be named once, in any order.
     ///
     /// Note that it is also possible to project during creation of the SFS using the `create`
     /// subcommand, and projection after creation is not in equivalent. Where applicable,
@@ -102,9 +156,10 @@ struct Project {
         short = 'p',
         long = "project-individuals",
         use_value_delimiter = true,
-        value_name = "INT,..."
+        value_parser = parse_population_value::<usize>,
+        value_name = "[POPULATION=]INT,..."
     )]
-    individuals: Option<Vec<usize>>,
+    individuals: Option<Vec<(Option<String>, usize)>>,
 
     /// Projected shape.
     ///
@@ -122,8 +177,16 @@ struct Project {
 
 #[derive(ValueEnum, Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Format {
+    // ANGSD realSFS plain format.
+    Angsd,
+    // dadi and moments .fs format.
+    Dadi,
+    // fastsimcoal2 .obs format.
+    Fsc,
     // Binary numpy npy format.
     Npy,
+    // Numpy npz archive of several spectra.
+    Npz,
     // Plain text format.
     Text,
 }
@@ -131,7 +194,11 @@ pub enum Format {
 impl Format {
     pub fn name(&self) -> &'static str {
         match self {
+            Format::Angsd => "angsd",
+            Format::Dadi => "dadi",
+            Format::Fsc => "fsc",
             Format::Npy => "npy",
+            Format::Npz => "npz",
             Format::Text => "text",
         }
     }
@@ -146,43 +213,190 @@ impl fmt::Display for Format {
 impl From<Format> for sfs_core::spectrum::io::Format {
     fn from(value: Format) -> Self {
         match value {
+            Format::Angsd => sfs_core::spectrum::io::Format::Angsd,
+            Format::Dadi => sfs_core::spectrum::io::Format::Dadi,
+            Format::Fsc => sfs_core::spectrum::io::Format::Fsc,
             Format::Npy => sfs_core::spectrum::io::Format::Npy,
+            Format::Npz => sfs_core::spectrum::io::Format::Npz,
             Format::Text => sfs_core::spectrum::io::Format::Text,
         }
     }
 }
 
+/// Returns the spectrum with the provided label from spectra, if any label is provided.
+pub(crate) fn select_label(spectra: Spectra, label: Option<&str>) -> Result<Spectra, Error> {
+    match (spectra, label) {
+        (Spectra::Container(mut reader), Some(label)) => {
+            let (scs, metadata) = reader
+                .find_record(label)?
+                .ok_or_else(|| anyhow!("no spectrum with label '{label}' in input"))?
+                .value;
+
+            Ok(Spectra::Single(scs, metadata))
+        }
+        (Spectra::Single(..), Some(label)) => Err(anyhow!(
+            "cannot select label '{label}' from input with a single spectrum"
+        )),
+        (spectra, None) => Ok(spectra),
+    }
+}
+
+/// Returns the axes of populations given by name or by 0-based dimension.
+///
+/// Population names take precedence over dimensions.
+fn parse_axes(populations: &[String], metadata: &Metadata) -> Result<Vec<Axis>, Error> {
+    populations
+        .iter()
+        .map(|population| {
+            metadata
+                .population_axis(population)
+                .or_else(|| population.parse().ok().map(Axis))
+                .ok_or_else(|| anyhow!("unknown population '{population}'"))
+        })
+        .collect()
+}
+
+/// Returns the provided numbers of individuals in the order of the spectrum dimensions.
+///
+/// Either all or none of the individuals must be associated with a population name.
+pub(crate) fn order_individuals(
+    individuals: Vec<(Option<String>, usize)>,
+    metadata: &Metadata,
+) -> Result<Vec<usize>, Error> {
+    if individuals
+        .iter()
+        .all(|(population, _)| population.is_none())
+    {
+        return Ok(individuals.into_iter().map(|(_, i)| i).collect());
+    }
+
+    let populations = metadata
+        .populations
+        .as_ref()
+        .ok_or_else(|| anyhow!("cannot project by population name without population names"))?;
+
+    let mut ordered = vec![None; populations.len()];
+    for (population, i) in individuals {
+        let population = population.ok_or_else(|| {
+            anyhow!("cannot mix named and unnamed populations in projected individuals")
+        })?;
+        let axis = metadata
+            .population_axis(&population)
+            .ok_or_else(|| anyhow!("unknown population '{population}'"))?;
+
+        if ordered[axis.0].replace(i).is_some() {
+            return Err(anyhow!("population '{population}' provided more than once"));
+        }
+    }
+
+    ordered
+        .into_iter()
+        .zip(populations)
+        .map(|(i, population)| i.ok_or_else(|| anyhow!("population '{population}' not provided")))
+        .collect()
+}
+
 impl View {
     pub fn run(self) -> Result<(), Error> {
-        let mut scs = spectrum::io::read::Builder::default()
-            .set_input(Input::new(self.input)?)
-            .read()?;
+        let mut builder =
+            spectrum::io::read::Builder::default().set_input(Input::new(self.input.clone())?);
+        if let Some(format) = self.input_format {
+            builder = builder.set_format(sfs_core::spectrum::io::Format::from(format));
+        }
+        if let Some(shape) = self.input_shape.clone() {
+            builder = builder.set_shape(Shape(shape));
+        }
 
-        if let Some(marginalize) = self.marginalize {
-            // If marginalizing, normalize to indices to marginalize away (rather than keep)
-            let axes = match (marginalize.keep, marginalize.remove) {
-                (Some(keep), None) => (0..scs.dimensions())
-                    .filter(|i| !keep.contains(i))
-                    .collect(),
+        let writer = spectrum::io::write::Builder::default()
+            .set_precision(self.precision)
+            .set_format(sfs_core::spectrum::io::Format::from(self.output_format));
+
+        match select_label(builder.read_spectra()?, self.label.as_deref())? {
+            Spectra::Single(scs, metadata) => {
+                let (scs, metadata) = self.process(scs, metadata)?;
+
+                if let (Format::Fsc, Some(path)) = (self.output_format, self.output.as_ref()) {
+                    let suffix = spectrum::io::fsc::file_suffix(scs.dimensions(), metadata.folded);
+
+                    if !path.to_string_lossy().ends_with(&suffix) {
+                        log::warn!(
+                            "fastsimcoal2 expects the output file name to end with '{suffix}', \
+                            found '{}'.",
+                            path.display()
+                        );
+                    }
+                }
 
-                (None, Some(remove)) => remove,
+                writer
+                    .set_metadata(metadata)
+                    .write_to_path_or_stdout(self.output, &scs)?;
+            }
+            Spectra::Container(mut reader) => {
+                let output: Box<dyn Write> = match &self.output {
+                    Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+                    None => Box::new(io::stdout().lock()),
+                };
+                let mut container = writer.build_container(output)?;
+
+                loop {
+                    let Labelled {
+                        label,
+                        value: (scs, metadata),
+                    } = match reader.read_record() {
+                        ReadStatus::Read(record) => record,
+                        ReadStatus::Error(e) => return Err(e.into()),
+                        ReadStatus::Done => break,
+                    };
+
+                    let (scs, metadata) = self
+                        .process(scs, metadata)
+                        .map_err(|e| anyhow!("{e} in spectrum '{label}'"))?;
+
+                    container.write_record(&label, &scs, &metadata)?;
+                }
+
+                container.finish()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Applies the requested operations to a spectrum and its metadata.
+    fn process(&self, mut scs: Scs, mut metadata: Metadata) -> Result<(Scs, Metadata), Error> {
+        if let Some(marginalize) = &self.marginalize {
+            // If marginalizing, normalize to indices to marginalize away (rather than keep)
+            let axes = match (&marginalize.keep, &marginalize.remove) {
+                (Some(keep), None) => {
+                    let keep = parse_axes(keep, &metadata)?;
+
+                    (0..scs.dimensions())
+                        .map(Axis)
+                        .filter(|axis| !keep.contains(axis))
+                        .collect()
+                }
+                (None, Some(remove)) => parse_axes(remove, &metadata)?,
                 _ => unreachable!("checked by clap"),
             };
 
-            let axes = axes.into_iter().map(Axis).collect::<Vec<_>>();
             scs = scs.marginalize(&axes)?;
+            metadata = metadata.marginalize(&axes);
         }
 
-        if let Some(project) = self.project {
-            let shape = match (project.individuals, project.shape) {
+        if let Some(project) = &self.project {
+            let shape = match (&project.individuals, &project.shape) {
                 (Some(individuals), None) => {
+                    let individuals = order_individuals(individuals.clone(), &metadata)?;
+
                     Shape(individuals.into_iter().map(|i| 2 * i + 1).collect())
                 }
-                (None, Some(shape)) => Shape(shape),
+                (None, Some(shape)) => Shape(shape.clone()),
                 _ => unreachable!("checked by clap"),
             };
 
             scs = scs.project(shape)?;
+            // A mask does not carry over to the projected spectrum
+            metadata.mask = None;
         }
 
         if self.mask_monomorphic {
@@ -195,12 +409,7 @@ impl View {
             scs.normalize();
         }
 
-        spectrum::io::write::Builder::default()
-            .set_precision(self.precision)
-            .set_format(sfs_core::spectrum::io::Format::from(self.output_format))
-            .write_to_path_or_stdout(self.output, &scs)?;
-
-        Ok(())
+        Ok((scs, metadata))
     }
 }
 
@@ -1,3 +1,19 @@
+#[test]
+fn add() {
+    trycmd::TestCases::new()
+        .case("tests/add/*.toml")
+        .env("SFS_ALLOW_STDIN", "true")
+        .default_bin_name("sfs");
+}
+
+#[test]
+fn counts() {
+    trycmd::TestCases::new()
+        .case("tests/counts/*.toml")
+        .env("SFS_ALLOW_STDIN", "true")
+        .default_bin_name("sfs");
+}
+
 #[test]
 fn create() {
     trycmd::TestCases::new()
@@ -14,6 +30,30 @@ fn fold() {
         .default_bin_name("sfs");
 }
 
+#[test]
+fn kinship() {
+    trycmd::TestCases::new()
+        .case("tests/kinship/*.toml")
+        .env("SFS_ALLOW_STDIN", "true")
+        .default_bin_name("sfs");
+}
+
+#[test]
+fn saf() {
+    trycmd::TestCases::new()
+        .case("tests/saf/*.toml")
+        .env("SFS_ALLOW_STDIN", "true")
+        .default_bin_name("sfs");
+}
+
+#[test]
+fn scale() {
+    trycmd::TestCases::new()
+        .case("tests/scale/*.toml")
+        .env("SFS_ALLOW_STDIN", "true")
+        .default_bin_name("sfs");
+}
+
 #[test]
 fn stat() {
     trycmd::TestCases::new()
@@ -22,6 +62,14 @@ fn stat() {
         .default_bin_name("sfs");
 }
 
+#[test]
+fn sub() {
+    trycmd::TestCases::new()
+        .case("tests/sub/*.toml")
+        .env("SFS_ALLOW_STDIN", "true")
+        .default_bin_name("sfs");
+}
+
 #[test]
 fn view() {
     trycmd::TestCases::new()
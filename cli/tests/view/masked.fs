@@ -0,0 +1,4 @@
+# Converted from moments
+3 5 unfolded "a" "b"
+0 1 2 3 4 5 6 7 8 9 10 11 12 13 14
+1 0 0 0 0 0 0 1 0 0 0 0 0 0 1
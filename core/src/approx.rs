@@ -101,7 +101,7 @@ where
     }
 }
 
-impl<'a, T> ApproxEq for &'a T
+impl<T> ApproxEq for &T
 where
     T: ApproxEq + ?Sized,
 {
@@ -114,7 +114,7 @@ where
     }
 }
 
-impl<'a, T> ApproxEq for &'a mut T
+impl<T> ApproxEq for &mut T
 where
     T: ApproxEq + ?Sized,
 {
@@ -2,7 +2,7 @@
 
 use std::{
     fmt, io,
-    ops::{Index, IndexMut},
+    ops::{Add, Index, IndexMut, Mul, Sub},
 };
 
 pub mod iter;
@@ -167,7 +167,7 @@ impl<T> Array<T> {
         if data.len() == shape.elements() {
             Ok(Array::new_unchecked(data, shape))
         } else {
-            Err(ShapeError {
+            Err(ShapeError::Elements {
                 shape,
                 n: data.len(),
             })
@@ -230,6 +230,24 @@ impl Array<f64> {
             })
     }
 
+    fn zip_mut_with<F>(&mut self, other: &Self, f: F) -> Result<(), ShapeError>
+    where
+        F: Fn(&mut f64, f64),
+    {
+        if self.shape != other.shape {
+            return Err(ShapeError::Mismatch {
+                left: self.shape.clone(),
+                right: other.shape.clone(),
+            });
+        }
+
+        self.iter_mut()
+            .zip(other.iter())
+            .for_each(|(x, &y)| f(x, y));
+
+        Ok(())
+    }
+
     /// Writes the in the [`npy`] format.
     ///
     /// See the [format docs](https://numpy.org/devdocs/reference/generated/numpy.lib.format.html)
@@ -242,6 +260,50 @@ impl Array<f64> {
     }
 }
 
+/// Element-wise addition, returning an error if the shapes of the arrays differ.
+impl Add<&Array<f64>> for Array<f64> {
+    type Output = Result<Self, ShapeError>;
+
+    fn add(mut self, rhs: &Array<f64>) -> Self::Output {
+        self.zip_mut_with(rhs, |x, y| *x += y).map(|()| self)
+    }
+}
+
+impl Add for Array<f64> {
+    type Output = Result<Self, ShapeError>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self + &rhs
+    }
+}
+
+/// Element-wise subtraction, returning an error if the shapes of the arrays differ.
+impl Sub<&Array<f64>> for Array<f64> {
+    type Output = Result<Self, ShapeError>;
+
+    fn sub(mut self, rhs: &Array<f64>) -> Self::Output {
+        self.zip_mut_with(rhs, |x, y| *x -= y).map(|()| self)
+    }
+}
+
+impl Sub for Array<f64> {
+    type Output = Result<Self, ShapeError>;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self - &rhs
+    }
+}
+
+/// Element-wise multiplication by a scalar.
+impl Mul<f64> for Array<f64> {
+    type Output = Self;
+
+    fn mul(mut self, rhs: f64) -> Self::Output {
+        self.iter_mut().for_each(|x| *x *= rhs);
+        self
+    }
+}
+
 impl<T, I> Index<I> for Array<T>
 where
     I: AsRef<[usize]>,
@@ -264,20 +326,37 @@ where
     }
 }
 
-/// An error associated with a shape mismatch on construction of an [`Array`].
+/// An error associated with a shape mismatch of an [`Array`].
 #[derive(Debug)]
-pub struct ShapeError {
-    shape: Shape,
-    n: usize,
+pub enum ShapeError {
+    /// The number of elements does not fit the shape on construction.
+    Elements {
+        /// The shape of the array.
+        shape: Shape,
+        /// The number of elements.
+        n: usize,
+    },
+    /// The shapes of two arrays in an element-wise operation differ.
+    Mismatch {
+        /// The shape of the left-hand side array.
+        left: Shape,
+        /// The shape of the right-hand side array.
+        right: Shape,
+    },
 }
 
 impl fmt::Display for ShapeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let ShapeError { shape, n } = self;
-        write!(
-            f,
-            "cannot construct array with shape {shape} from {n} elements"
-        )
+        match self {
+            ShapeError::Elements { shape, n } => write!(
+                f,
+                "cannot construct array with shape {shape} from {n} elements"
+            ),
+            ShapeError::Mismatch { left, right } => write!(
+                f,
+                "cannot combine arrays with different shapes {left} and {right}"
+            ),
+        }
     }
 }
 
@@ -55,7 +55,7 @@ impl Shape {
         sum
     }
 
-    pub(crate) fn remove_axis(&self, axis: Axis) -> RemovedAxis<Self> {
+    pub(crate) fn remove_axis(&self, axis: Axis) -> RemovedAxis<'_, Self> {
         RemovedAxis::new(self, axis)
     }
 
@@ -39,7 +39,7 @@ impl Strides {
             .fold(0, |flat, (stride, idx)| flat + stride * idx)
     }
 
-    pub(crate) fn remove_axis(&self, axis: Axis) -> RemovedAxis<Self> {
+    pub(crate) fn remove_axis(&self, axis: Axis) -> RemovedAxis<'_, Self> {
         RemovedAxis::new(self, axis)
     }
 }
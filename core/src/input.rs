@@ -7,15 +7,25 @@ use std::{
     path::{Path, PathBuf},
 };
 
+pub mod counts;
+
 pub mod genotype;
 pub use genotype::Genotype;
 
+pub mod pairwise;
+
+pub mod region;
+pub use region::{Region, RegionSet};
+
 pub mod sample;
 pub use sample::Sample;
 
 pub mod site;
 pub use site::Site;
 
+pub mod window;
+pub use window::Window;
+
 /// A status when trying to read an element from a reader.
 #[derive(Debug)]
 pub enum ReadStatus<T> {
@@ -27,7 +37,31 @@ pub enum ReadStatus<T> {
     Done,
 }
 
+impl ReadStatus<()> {
+    fn from_bytes_read(result: io::Result<usize>) -> Self {
+        match result {
+            Ok(0) => ReadStatus::Done,
+            Ok(_) => ReadStatus::Read(()),
+            Err(e) => ReadStatus::Error(e),
+        }
+    }
+}
+
 impl<T> ReadStatus<T> {
+    fn and_then<U, F>(self, op: F) -> ReadStatus<U>
+    where
+        F: FnOnce(T) -> io::Result<U>,
+    {
+        match self {
+            ReadStatus::Read(t) => match op(t) {
+                Ok(u) => ReadStatus::Read(u),
+                Err(e) => ReadStatus::Error(e),
+            },
+            ReadStatus::Error(e) => ReadStatus::Error(e),
+            ReadStatus::Done => ReadStatus::Done,
+        }
+    }
+
     fn map<U, F>(self, op: F) -> ReadStatus<U>
     where
         F: FnOnce(T) -> U,
@@ -0,0 +1,415 @@
+//! Per-site allele counts.
+//!
+//! An allele counts table stores the derived and total number of alleles in each population at
+//! each site, so that spectra can be created with any projection without reading genotypes again.
+//!
+//! The table is plain text. It starts with header lines in the same form as the plain text
+//! spectrum format: a `#SHAPE=<[shape]>` line giving the shape of the unprojected spectrum,
+//! optionally followed by `#POPULATIONS=<[populations]>` and `#POLARIZED=<true>` lines. Each
+//! following line gives a site as the tab-separated contig, 1-based position, and a
+//! `[derived]/[total]` count for each population. Counts are of alternative alleles unless
+//! polarized.
+
+use std::{fmt, io, str::FromStr};
+
+use crate::{
+    array::Shape,
+    spectrum::{
+        io::{text::Header, Metadata},
+        project::PartialProjection,
+        Count,
+    },
+    Scs,
+};
+
+/// A site in an allele counts table.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Record {
+    /// The contig of the site.
+    pub contig: String,
+    /// The 1-based position of the site within its contig.
+    pub position: usize,
+    /// The number of derived alleles in each population.
+    pub counts: Count,
+    /// The total number of alleles in each population.
+    pub totals: Count,
+}
+
+impl Default for Record {
+    fn default() -> Self {
+        Self {
+            contig: String::new(),
+            position: 0,
+            counts: Count::from_zeros(0),
+            totals: Count::from_zeros(0),
+        }
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\t{}", self.contig, self.position)?;
+
+        for (count, total) in self.counts.iter().zip(self.totals.iter()) {
+            write!(f, "\t{count}/{total}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Record {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid allele counts line '{}'", s.trim_end()),
+            )
+        };
+
+        let mut fields = s.trim_end().split('\t');
+        let contig = fields
+            .next()
+            .filter(|s| !s.is_empty())
+            .ok_or_else(invalid)?;
+        let position = fields
+            .next()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(invalid)?;
+
+        let (counts, totals): (Vec<_>, Vec<_>) = fields
+            .map(|field| {
+                field
+                    .split_once('/')
+                    .and_then(|(count, total)| Some((count.parse().ok()?, total.parse().ok()?)))
+                    .filter(|(count, total)| count <= total)
+                    .ok_or_else(invalid)
+            })
+            .collect::<io::Result<Vec<(usize, usize)>>>()?
+            .into_iter()
+            .unzip();
+
+        Ok(Self {
+            contig: contig.to_string(),
+            position,
+            counts: Count(counts),
+            totals: Count(totals),
+        })
+    }
+}
+
+/// A reader of allele counts tables.
+pub struct Reader<R> {
+    inner: R,
+    header: Header,
+    buf: String,
+}
+
+impl<R> Reader<R>
+where
+    R: io::BufRead,
+{
+    /// Returns the metadata of the table.
+    pub fn metadata(&self) -> &Metadata {
+        &self.header.metadata
+    }
+
+    /// Creates a new reader, reading the header from the inner reader.
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let invalid_data = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+
+        let mut buf = String::new();
+        inner.read_line(&mut buf)?;
+        let mut header = Header::from_str(buf.trim_end()).map_err(invalid_data)?;
+
+        if header.shape.contains(&0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "found zero-sized dimension in allele counts with shape {}",
+                    header.shape
+                ),
+            ));
+        }
+
+        while inner.fill_buf()?.first() == Some(&b'#') {
+            buf.clear();
+            inner.read_line(&mut buf)?;
+            header.parse_field(&buf).map_err(invalid_data)?;
+        }
+
+        if let Some(populations) = &header.metadata.populations {
+            if populations.len() != header.shape.dimensions() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "found {} population names for allele counts with {} dimensions",
+                        populations.len(),
+                        header.shape.dimensions()
+                    ),
+                ));
+            }
+        }
+
+        Ok(Self { inner, header, buf })
+    }
+
+    /// Reads the next site into the provided record.
+    ///
+    /// Returns the number of bytes read, so that zero indicates that the reader is done.
+    pub fn read_record(&mut self, record: &mut Record) -> io::Result<usize> {
+        self.buf.clear();
+        let bytes_read = self.inner.read_line(&mut self.buf)?;
+
+        if bytes_read > 0 {
+            *record = self.buf.parse()?;
+
+            if record.counts.dimensions() != self.header.shape.dimensions() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "found {} populations at site '{}:{}' in allele counts with {} dimensions",
+                        record.counts.dimensions(),
+                        record.contig,
+                        record.position,
+                        self.header.shape.dimensions(),
+                    ),
+                ));
+            }
+
+            if let Some((&total, _)) = record
+                .totals
+                .iter()
+                .zip(self.header.shape.iter())
+                .find(|(&total, &n)| total >= n)
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "found {} alleles at site '{}:{}' in allele counts with shape {}",
+                        total, record.contig, record.position, self.header.shape,
+                    ),
+                ));
+            }
+        }
+
+        Ok(bytes_read)
+    }
+
+    /// Creates a spectrum from all remaining sites, optionally projecting to a shape.
+    ///
+    /// Without projection, sites where the total number of alleles in any population falls short
+    /// of the shape of the table are skipped. With projection, only sites where the total falls
+    /// short of the projected shape are skipped. Returns the spectrum together with the number of
+    /// skipped sites.
+    ///
+    /// # Errors
+    ///
+    /// If the projection is invalid for the shape of the table, or if a site cannot be read, for
+    /// instance because its total number of alleles exceeds the shape of the table.
+    pub fn read_scs(&mut self, project_to: Option<Shape>) -> io::Result<(Scs, usize)> {
+        let mut projection = project_to
+            .map(|project_to| PartialProjection::from_shapes(self.shape().clone(), project_to))
+            .transpose()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        let shape = projection.as_ref().map_or_else(
+            || self.shape().clone(),
+            |projection| projection.project_to().clone().into_shape(),
+        );
+        let full = Count::try_from_shape(shape.clone())
+            .expect("zero-sized dimensions are rejected when reading header");
+
+        let mut scs = Scs::from_zeros(shape);
+        let mut skipped = 0;
+
+        let mut record = Record::default();
+        while self.read_record(&mut record)? > 0 {
+            let (exact, projectable) = record.totals.iter().zip(full.iter()).fold(
+                (true, true),
+                |(exact, projectable), (&total, &to)| {
+                    (exact && total == to, projectable && total >= to)
+                },
+            );
+
+            match projection.as_mut() {
+                _ if exact => scs[&record.counts] += 1.0,
+                Some(projection) if projectable => projection
+                    .project_unchecked(&record.totals, &record.counts)
+                    .add_unchecked(&mut scs),
+                _ => skipped += 1,
+            }
+        }
+
+        Ok((scs, skipped))
+    }
+
+    /// Returns the shape of the unprojected spectrum of the table.
+    pub fn shape(&self) -> &Shape {
+        &self.header.shape
+    }
+}
+
+/// A writer of allele counts tables.
+pub struct Writer<W> {
+    inner: W,
+    record: Record,
+}
+
+impl<W> Writer<W>
+where
+    W: io::Write,
+{
+    /// Creates a new writer, writing the header to the inner writer.
+    ///
+    /// The shape should be the shape of the unprojected spectrum of the sites, and only the
+    /// population names and polarization status of the metadata are written.
+    pub fn new(mut inner: W, shape: Shape, metadata: &Metadata) -> io::Result<Self> {
+        let mut header = Header::new(shape);
+        header.metadata = Metadata {
+            folded: false,
+            mask: None,
+            ..metadata.clone()
+        };
+        header.write(&mut inner)?;
+
+        Ok(Self {
+            inner,
+            record: Record::default(),
+        })
+    }
+
+    /// Flushes the inner writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Writes a site.
+    pub fn write_site(
+        &mut self,
+        contig: &str,
+        position: usize,
+        counts: &Count,
+        totals: &Count,
+    ) -> io::Result<()> {
+        self.record.contig.clear();
+        self.record.contig.push_str(contig);
+        self.record.position = position;
+        self.record.counts.clone_from(counts);
+        self.record.totals.clone_from(totals);
+
+        writeln!(self.inner, "{}", self.record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE: &str = "#SHAPE=<5/3>\n\
+        #POPULATIONS=<a/b>\n\
+        chr1\t1\t1/4\t2/2\n\
+        chr1\t2\t0/2\t1/2\n\
+        chr2\t5\t3/4\t0/0\n";
+
+    #[test]
+    fn test_parse_record() {
+        assert_eq!(
+            "chr1\t12\t1/4\t0/2\n".parse::<Record>().unwrap(),
+            Record {
+                contig: String::from("chr1"),
+                position: 12,
+                counts: Count(vec![1, 0]),
+                totals: Count(vec![4, 2]),
+            }
+        );
+
+        assert!("chr1\t12\t5/4".parse::<Record>().is_err());
+        assert!("chr1\t12\t1".parse::<Record>().is_err());
+        assert!("chr1\tx\t1/4".parse::<Record>().is_err());
+    }
+
+    #[test]
+    fn test_read_header_zero_sized_dimension() {
+        assert!(Reader::new(&b"#SHAPE=<0/3>\n"[..]).is_err());
+        assert!(Reader::new(&b"#SHAPE=<5/0>\n#POPULATIONS=<a/b>\n"[..]).is_err());
+    }
+
+    #[test]
+    fn test_read_record_total_exceeds_shape() {
+        let mut reader = Reader::new(&b"#SHAPE=<5/3>\nchr1\t1\t1/5\t2/2\n"[..]).unwrap();
+
+        assert!(reader.read_record(&mut Record::default()).is_err());
+    }
+
+    #[test]
+    fn test_read_header() {
+        let reader = Reader::new(TABLE.as_bytes()).unwrap();
+
+        assert_eq!(reader.shape(), &Shape(vec![5, 3]));
+        assert_eq!(
+            reader.metadata().populations,
+            Some(vec![String::from("a"), String::from("b")])
+        );
+    }
+
+    #[test]
+    fn test_read_scs() {
+        let (scs, skipped) = Reader::new(TABLE.as_bytes())
+            .unwrap()
+            .read_scs(None)
+            .unwrap();
+
+        let mut expected = Scs::from_zeros([5, 3]);
+        expected[[1, 2]] = 1.0;
+        assert_eq!(scs, expected);
+        assert_eq!(skipped, 2);
+    }
+
+    #[test]
+    fn test_read_scs_projected() {
+        let (scs, skipped) = Reader::new(TABLE.as_bytes())
+            .unwrap()
+            .read_scs(Some(Shape(vec![3, 3])))
+            .unwrap();
+
+        // The first site is projected from 1/4 to 2, so that 0, 1 and 2 derived alleles
+        // have probabilities 1/2, 1/2 and 0
+        let mut expected = Scs::from_zeros([3, 3]);
+        expected[[0, 2]] = 0.5;
+        expected[[1, 2]] = 0.5;
+        expected[[0, 1]] = 1.0;
+        assert_eq!(scs, expected);
+        assert_eq!(skipped, 1);
+    }
+
+    #[test]
+    fn test_read_scs_invalid_projection() {
+        let mut reader = Reader::new(TABLE.as_bytes()).unwrap();
+
+        assert!(reader.read_scs(Some(Shape(vec![7, 3]))).is_err());
+    }
+
+    #[test]
+    fn test_write_read_roundtrip() {
+        let metadata = Metadata {
+            populations: Some(vec![String::from("a"), String::from("b")]),
+            ..Default::default()
+        };
+
+        let mut writer = Writer::new(Vec::new(), Shape(vec![5, 3]), &metadata).unwrap();
+        writer
+            .write_site("chr1", 1, &Count(vec![1, 2]), &Count(vec![4, 2]))
+            .unwrap();
+        writer
+            .write_site("chr1", 2, &Count(vec![0, 1]), &Count(vec![2, 2]))
+            .unwrap();
+        writer
+            .write_site("chr2", 5, &Count(vec![3, 0]), &Count(vec![4, 0]))
+            .unwrap();
+
+        assert_eq!(String::from_utf8(writer.inner).unwrap(), TABLE);
+    }
+}
@@ -1,31 +1,37 @@
-//! Diploid, diallelic genotype.
+//! Diallelic genotype.
 
 use std::fmt;
 
+pub mod likelihoods;
+pub use likelihoods::Likelihoods;
+
 pub mod reader;
 pub use reader::Reader;
 
-/// A diploid, diallelic genotype, coded as the number of minor/alternative/derived alleles.
+/// A diallelic genotype of any ploidy, coded as the number of minor/alternative/derived alleles.
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-#[repr(u8)]
-pub enum Genotype {
-    /// Zero alleles.
-    Zero = 0,
-    /// One alleles.
-    One = 1,
-    /// Two alleles.
-    Two = 2,
+pub struct Genotype {
+    alleles: usize,
+    ploidy: usize,
 }
 
 impl Genotype {
-    /// Returns a genotype its raw representation if possible, otherwise `None`.
-    pub fn try_from_raw(raw: usize) -> Option<Self> {
-        match raw {
-            0 => Some(Self::Zero),
-            1 => Some(Self::One),
-            2 => Some(Self::Two),
-            _ => None,
-        }
+    /// Returns the number of minor/alternative/derived alleles in the genotype.
+    pub fn alleles(&self) -> usize {
+        self.alleles
+    }
+
+    /// Returns a new genotype with the provided number of alleles and ploidy if possible,
+    /// otherwise `None`.
+    ///
+    /// The ploidy must be non-zero, and the number of alleles cannot exceed the ploidy.
+    pub fn new(alleles: usize, ploidy: usize) -> Option<Self> {
+        (ploidy > 0 && alleles <= ploidy).then_some(Self { alleles, ploidy })
+    }
+
+    /// Returns the ploidy of the genotype.
+    pub fn ploidy(&self) -> usize {
+        self.ploidy
     }
 }
 
@@ -43,6 +49,8 @@ pub enum Result {
 /// A reason for skipping a genotype.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Skipped {
+    /// Genotype failed a genotype filter.
+    Filtered,
     /// Genotype was missing.
     Missing,
     /// Genotype was multiallelic.
@@ -53,6 +61,7 @@ impl Skipped {
     /// Returns a string representation for having skipped the genotype.
     pub fn reason(&self) -> &'static str {
         match self {
+            Self::Filtered => "filtered",
             Self::Missing => "missing",
             Self::Multiallelic => "multiallelic",
         }
@@ -62,14 +71,22 @@ impl Skipped {
 /// An error associated with parsing a genotype.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Error {
-    /// Genotype not diploid.
-    PloidyError,
+    /// Genotype ploidy does not match the ploidy of the sample.
+    PloidyError {
+        /// The expected ploidy of the sample.
+        expected: usize,
+        /// The ploidy of the genotype.
+        actual: usize,
+    },
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Error::PloidyError => f.write_str("genotype not diploid"),
+            Error::PloidyError { expected, actual } => write!(
+                f,
+                "genotype ploidy {actual} does not match expected sample ploidy {expected}"
+            ),
         }
     }
 }
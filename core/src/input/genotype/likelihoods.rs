@@ -0,0 +1,135 @@
+//! Genotype likelihoods.
+
+use super::{Error, Skipped};
+
+/// Genotype likelihoods for a diallelic site.
+///
+/// The likelihoods are stored on natural log scale, and ordered by the number of
+/// minor/alternative/derived alleles in the genotype.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Likelihoods(Vec<f64>);
+
+impl Likelihoods {
+    /// Returns the likelihoods as a slice of natural log-likelihoods.
+    pub fn as_slice(&self) -> &[f64] {
+        &self.0
+    }
+
+    /// Creates new genotype likelihoods from log10-scaled likelihoods, as in the VCF GL field.
+    pub fn from_log10<I>(log10_likelihoods: I) -> Self
+    where
+        I: IntoIterator<Item = f64>,
+    {
+        Self(
+            log10_likelihoods
+                .into_iter()
+                .map(|v| v * std::f64::consts::LN_10)
+                .collect(),
+        )
+    }
+
+    /// Creates new genotype likelihoods from phred-scaled likelihoods, as in the VCF PL field.
+    pub fn from_phred<I>(phred_likelihoods: I) -> Self
+    where
+        I: IntoIterator<Item = f64>,
+    {
+        Self::from_log10(phred_likelihoods.into_iter().map(|v| -v / 10.))
+    }
+
+    /// Creates new genotype likelihoods from natural log-likelihoods.
+    pub fn new(log_likelihoods: Vec<f64>) -> Self {
+        Self(log_likelihoods)
+    }
+
+    /// Returns the ploidy of the genotype corresponding to the likelihoods.
+    pub fn ploidy(&self) -> usize {
+        self.0.len() - 1
+    }
+}
+
+impl Result {
+    /// Checks the likelihoods against the expected ploidy of the sample.
+    ///
+    /// Biallelic likelihoods have one more value than the ploidy. Likelihoods with a single value
+    /// come from sites without alternative alleles, where the homozygous reference genotype is
+    /// the only possible genotype. Likelihoods with fewer values than expected are taken to
+    /// have the wrong ploidy, while likelihoods with more values are taken to be multiallelic.
+    pub(crate) fn check_ploidy(self, ploidy: usize) -> Self {
+        let Result::Likelihoods(likelihoods) = self else {
+            return self;
+        };
+
+        match likelihoods.0.len() {
+            1 => Result::Likelihoods(Likelihoods(
+                std::iter::once(0.0)
+                    .chain(std::iter::repeat(f64::NEG_INFINITY).take(ploidy))
+                    .collect(),
+            )),
+            n if n == ploidy + 1 => Result::Likelihoods(likelihoods),
+            n if n < ploidy + 1 => Result::Error(Error::PloidyError {
+                expected: ploidy,
+                actual: n - 1,
+            }),
+            _ => Result::Skipped(Skipped::Multiallelic),
+        }
+    }
+}
+
+/// The result of trying to read genotype likelihoods.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Result {
+    /// Genotype likelihoods that were succesfully read and parsed.
+    Likelihoods(Likelihoods),
+    /// Genotype likelihoods that were read, but skipped (e.g. multiallelic, missing)
+    Skipped(Skipped),
+    /// An error.
+    Error(Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::f64::consts::LN_10;
+
+    #[test]
+    fn test_from_phred() {
+        assert_approx_eq!(
+            Likelihoods::from_phred([0., 10., 20.]).0,
+            vec![0., -LN_10, -2. * LN_10]
+        );
+    }
+
+    #[test]
+    fn test_check_ploidy() {
+        let check = |v: Vec<f64>, ploidy| Result::Likelihoods(Likelihoods(v)).check_ploidy(ploidy);
+
+        assert_eq!(
+            check(vec![-1., 0.], 1),
+            Result::Likelihoods(Likelihoods(vec![-1., 0.]))
+        );
+        assert_eq!(
+            check(vec![-1.], 2),
+            Result::Likelihoods(Likelihoods(vec![0., f64::NEG_INFINITY, f64::NEG_INFINITY]))
+        );
+        assert_eq!(
+            check(vec![-1., 0.], 2),
+            Result::Error(Error::PloidyError {
+                expected: 2,
+                actual: 1
+            })
+        );
+        assert_eq!(
+            check(vec![0.; 6], 2),
+            Result::Skipped(Skipped::Multiallelic)
+        );
+    }
+
+    #[test]
+    fn test_from_log10() {
+        assert_approx_eq!(
+            Likelihoods::from_log10([-1., 0., -0.5]).0,
+            vec![-LN_10, 0., -0.5 * LN_10]
+        );
+    }
+}
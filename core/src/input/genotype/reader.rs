@@ -1,29 +1,68 @@
 //! Genotype reading.
 
+use std::io;
+
 use crate::input::{ReadStatus, Sample};
 
 pub mod builder;
 pub use builder::Builder;
 
 mod bcf;
+mod chain;
+mod indexed;
+mod plink;
 mod vcf;
 
-use super::Result;
+use super::{likelihoods, Result};
 
 /// An alias for a trait-object [`Reader`].
 pub type DynReader = Box<dyn Reader>;
 
 /// A type capable of reading genotypes for creating spectrum.
 pub trait Reader {
+    /// Returns the alleles of the current record in the reader.
+    fn current_alleles(&self) -> io::Result<Alleles>;
+
     /// Returns the current contig of the reader.
     fn current_contig(&self) -> &str;
 
+    /// Returns whether the FILTER field of the current record is `PASS`.
+    fn current_filter_is_pass(&self) -> io::Result<bool>;
+
+    /// Returns the numeric value of the FORMAT field with the provided key for each sample in the
+    /// current record.
+    ///
+    /// Where the field has multiple values, only the first is returned. Samples where the field
+    /// is missing have no value.
+    fn current_format_field(&self, key: &str) -> io::Result<Vec<Option<f64>>>;
+
+    /// Returns the value of the INFO field with the provided key in the current record, if
+    /// present.
+    ///
+    /// The value is returned in its VCF string representation, and flags have an empty value.
+    fn current_info_field(&self, key: &str) -> io::Result<Option<String>>;
+
     /// Returns the current position of the reader within its current contig.
     fn current_position(&self) -> usize;
 
+    /// Returns the QUAL field of the current record, if present.
+    fn current_quality(&self) -> Option<f64>;
+
     /// Returns the genotypes at the next position in the reader.
     fn read_genotypes(&mut self) -> ReadStatus<Vec<Result>>;
 
+    /// Returns the genotype likelihoods at the next position in the reader.
+    fn read_likelihoods(&mut self) -> ReadStatus<Vec<likelihoods::Result>>;
+
     /// Returns the samples defined by the reader.
     fn samples(&self) -> &[Sample];
 }
+
+/// The alleles of a record.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Alleles {
+    /// The reference allele.
+    pub reference: String,
+    /// The alternative alleles, if any.
+    pub alternative: Vec<String>,
+}
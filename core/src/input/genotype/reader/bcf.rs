@@ -2,11 +2,24 @@ use std::io;
 
 use bcf::lazy::Record as BcfRecord;
 use noodles_bcf as bcf;
+use noodles_bgzf as bgzf;
 
 use noodles_vcf as vcf;
-use vcf::record::genotypes::sample::value::genotype::Genotype as VcfGenotype;
+use vcf::record::Genotypes as VcfGenotypes;
 
-use crate::input::{genotype, ReadStatus, Sample};
+use crate::input::{
+    genotype::{self, likelihoods},
+    ReadStatus, Sample,
+};
+
+use super::{
+    indexed::{RecordReader, VirtualSeek},
+    vcf::{
+        alleles_from_vcf, format_field_from_vcf, genotypes_from_vcf, info_field_from_vcf,
+        likelihoods_from_vcf,
+    },
+    Alleles,
+};
 
 pub struct Reader<R> {
     pub inner: bcf::Reader<R>,
@@ -43,27 +56,44 @@ where
         })
     }
 
-    fn read_genotypes(&mut self) -> ReadStatus<Vec<Option<VcfGenotype>>> {
-        match self.inner.read_lazy_record(&mut self.buf) {
-            Ok(0) => ReadStatus::Done,
-            Ok(_) => {
-                let result = self
-                    .buf
-                    .genotypes()
-                    .try_into_vcf_record_genotypes(&self.header, self.string_maps.strings())
-                    .and_then(|genotypes| {
-                        genotypes
-                            .genotypes()
-                            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
-                    });
-
-                match result {
-                    Ok(genotypes) => ReadStatus::Read(genotypes),
-                    Err(e) => ReadStatus::Error(e),
-                }
-            }
-            Err(e) => ReadStatus::Error(e),
-        }
+    fn vcf_genotypes(&self) -> io::Result<VcfGenotypes> {
+        self.buf
+            .genotypes()
+            .try_into_vcf_record_genotypes(&self.header, self.string_maps.strings())
+    }
+}
+
+impl<R> RecordReader for Reader<R>
+where
+    R: io::Read,
+{
+    fn read_record(&mut self) -> io::Result<usize> {
+        self.inner.read_lazy_record(&mut self.buf)
+    }
+
+    fn current_genotypes(&self) -> io::Result<Vec<genotype::Result>> {
+        genotypes_from_vcf(&self.vcf_genotypes()?)
+    }
+
+    fn current_likelihoods(&self) -> io::Result<Vec<likelihoods::Result>> {
+        Ok(likelihoods_from_vcf(&self.vcf_genotypes()?))
+    }
+}
+
+impl<R> VirtualSeek for Reader<bgzf::Reader<R>>
+where
+    R: io::Read + io::Seek,
+{
+    fn reference_sequence_id(&self, contig: &str) -> Option<usize> {
+        self.string_maps.contigs().get_index_of(contig)
+    }
+
+    fn seek(&mut self, pos: bgzf::VirtualPosition) -> io::Result<bgzf::VirtualPosition> {
+        self.inner.seek(pos)
+    }
+
+    fn virtual_position(&self) -> bgzf::VirtualPosition {
+        self.inner.virtual_position()
     }
 }
 
@@ -71,6 +101,13 @@ impl<R> super::Reader for Reader<R>
 where
     R: io::Read,
 {
+    fn current_alleles(&self) -> io::Result<Alleles> {
+        // Alleles are not exposed on the lazy record, so the full record must be converted
+        self.buf
+            .try_into_vcf_record(&self.header, &self.string_maps)
+            .map(|record| alleles_from_vcf(&record))
+    }
+
     fn current_contig(&self) -> &str {
         self.string_maps
             .contigs()
@@ -78,17 +115,44 @@ where
             .unwrap_or("[unknown]")
     }
 
+    fn current_filter_is_pass(&self) -> io::Result<bool> {
+        let filters = self
+            .buf
+            .filters()
+            .try_into_vcf_record_filters(self.string_maps.strings())?;
+
+        Ok(matches!(filters, Some(vcf::record::Filters::Pass)))
+    }
+
+    fn current_format_field(&self, key: &str) -> io::Result<Vec<Option<f64>>> {
+        format_field_from_vcf(&self.vcf_genotypes()?, key)
+    }
+
+    fn current_info_field(&self, key: &str) -> io::Result<Option<String>> {
+        let info = self
+            .buf
+            .info()
+            .try_into_vcf_record_info(&self.header, self.string_maps.strings())?;
+
+        info_field_from_vcf(&info, key)
+    }
+
     fn current_position(&self) -> usize {
         self.buf.position().into()
     }
 
+    fn current_quality(&self) -> Option<f64> {
+        self.buf
+            .quality_score()
+            .map(|score| f64::from(f32::from(score)))
+    }
+
     fn read_genotypes(&mut self) -> ReadStatus<Vec<genotype::Result>> {
-        self.read_genotypes().map(|vcf_genotypes| {
-            vcf_genotypes
-                .into_iter()
-                .map(genotype::Result::from)
-                .collect()
-        })
+        ReadStatus::from_bytes_read(self.read_record()).and_then(|()| self.current_genotypes())
+    }
+
+    fn read_likelihoods(&mut self) -> ReadStatus<Vec<likelihoods::Result>> {
+        ReadStatus::from_bytes_read(self.read_record()).and_then(|()| self.current_likelihoods())
     }
 
     fn samples(&self) -> &[Sample] {
@@ -1,31 +1,40 @@
 //! Genotype reader builder.
 
 use std::{
+    fs::File,
     io::{self, Read as _},
     num::NonZeroUsize,
+    path::Path,
 };
 
 use flate2::bufread::MultiGzDecoder;
 
 use noodles_bgzf as bgzf;
 
-use crate::{input, Input};
+use crate::{
+    input::{self, Region},
+    Input,
+};
+
+use super::indexed::{read_index, IndexedReader};
 
 /// A genotype reader builder.
 #[derive(Debug)]
 pub struct Builder {
-    input: Option<Input>,
+    inputs: Option<Vec<Input>>,
     format: Option<Format>,
     compression_method: Option<Option<CompressionMethod>>,
+    regions: Option<Vec<Region>>,
     threads: NonZeroUsize,
 }
 
 impl Default for Builder {
     fn default() -> Self {
         Self {
-            input: None,
+            inputs: None,
             format: None,
             compression_method: None,
+            regions: None,
             threads: NonZeroUsize::try_from(4).unwrap(),
         }
     }
@@ -34,18 +43,112 @@ impl Default for Builder {
 impl Builder {
     /// Returns a new reader.
     ///
+    /// If multiple inputs are set, the returned reader reads the inputs one after the other.
+    ///
     /// # Errors
     ///
     /// If no input is set or available via stdin, or if an I/O error is encountered during format
-    /// detection and reader creation.
-    pub fn build(self) -> io::Result<super::DynReader> {
-        match self.input.as_ref().unwrap_or(&Input::Stdin).open()? {
-            input::Reader::File(reader) => self.build_from_reader(reader),
-            input::Reader::Stdin(reader) => self.build_from_reader(reader),
+    /// detection and reader creation. If regions are set, the input must furthermore be a
+    /// BGZF-compressed file with an index. If multiple inputs are set, they must all have the
+    /// same samples in the same order.
+    pub fn build(mut self) -> io::Result<super::DynReader> {
+        let mut inputs = self
+            .inputs
+            .take()
+            .filter(|inputs| !inputs.is_empty())
+            .unwrap_or_else(|| vec![Input::Stdin]);
+
+        if inputs.len() == 1 {
+            return self.build_single(&inputs.remove(0));
+        }
+
+        let readers = inputs
+            .iter()
+            .map(|input| self.build_single(input))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        match super::chain::Chain::new(readers) {
+            Ok(reader) => Ok(Box::new(reader)),
+            Err(i) => {
+                let name = |input: &Input| {
+                    input
+                        .as_path()
+                        .map_or_else(|| String::from("stdin"), |path| path.display().to_string())
+                };
+
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "samples in input '{}' differ from samples in input '{}', \
+                        all inputs must have the same samples in the same order",
+                        name(&inputs[i]),
+                        name(&inputs[0]),
+                    ),
+                ))
+            }
+        }
+    }
+
+    fn build_single(&self, input: &Input) -> io::Result<super::DynReader> {
+        if let Some(regions) = self.regions.clone() {
+            let path = input.as_path().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "reading regions requires input from an indexed file, not stdin",
+                )
+            })?;
+
+            return self.build_indexed(path, regions);
         }
+
+        match input.open()? {
+            input::Reader::File(reader) => self.build_from_reader(reader, input.as_path()),
+            input::Reader::Stdin(reader) => self.build_from_reader(reader, None),
+        }
+    }
+
+    fn build_indexed(&self, path: &Path, regions: Vec<Region>) -> io::Result<super::DynReader> {
+        let mut reader = File::open(path).map(io::BufReader::new)?;
+
+        let compression_method = match self.compression_method {
+            Some(compression_method) => compression_method,
+            None => CompressionMethod::detect(&mut reader)?,
+        };
+
+        if compression_method != Some(CompressionMethod::Bgzf) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "reading regions requires BGZF-compressed input",
+            ));
+        }
+
+        let format = match self.format {
+            Some(format) => format,
+            None => Format::detect(&mut reader, compression_method)?,
+        };
+
+        let index = read_index(path)?;
+
+        let bgzf_reader = bgzf::reader::Builder::default()
+            .set_worker_count(self.threads)
+            .build_from_reader(reader);
+
+        let reader: super::DynReader = match format {
+            Format::Bcf => super::bcf::Reader::new(bgzf_reader)
+                .map(|reader| Box::new(IndexedReader::new(reader, index, regions)))?,
+            Format::Plink => return Err(plink_compressed_error()),
+            Format::Vcf => super::vcf::Reader::new(bgzf_reader)
+                .map(|reader| Box::new(IndexedReader::new(reader, index, regions)))?,
+        };
+
+        Ok(reader)
     }
 
-    fn build_from_reader<R>(self, mut reader: R) -> io::Result<super::DynReader>
+    fn build_from_reader<R>(
+        &self,
+        mut reader: R,
+        path: Option<&Path>,
+    ) -> io::Result<super::DynReader>
     where
         R: 'static + io::BufRead,
     {
@@ -67,11 +170,23 @@ impl Builder {
 
                 match format {
                     Format::Bcf => super::bcf::Reader::new(bgzf_reader).map(Box::new)?,
+                    Format::Plink => return Err(plink_compressed_error()),
                     Format::Vcf => super::vcf::Reader::new(bgzf_reader).map(Box::new)?,
                 }
             }
             None => match format {
                 Format::Bcf => super::bcf::Reader::new(reader).map(Box::new)?,
+                Format::Plink => {
+                    // The .bim and .fam files are found next to the .bed file
+                    let path = path.ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "reading PLINK input requires a .bed file, not stdin",
+                        )
+                    })?;
+
+                    super::plink::Reader::from_bed_path(reader, path).map(Box::new)?
+                }
                 Format::Vcf => super::vcf::Reader::new(reader).map(Box::new)?,
             },
         };
@@ -99,7 +214,30 @@ impl Builder {
     ///
     /// By default, it will be assumed that input is coming from stdin.
     pub fn set_input(mut self, input: Input) -> Self {
-        self.input = Some(input);
+        self.inputs = Some(vec![input]);
+        self
+    }
+
+    /// Sets multiple inputs for the reader.
+    ///
+    /// The inputs are read one after the other, e.g. to read a callset split by contig, and must
+    /// all have the same samples in the same order. Any regions are read from each input.
+    ///
+    /// By default, it will be assumed that input is coming from stdin.
+    pub fn set_inputs(mut self, inputs: Vec<Input>) -> Self {
+        self.inputs = Some(inputs);
+        self
+    }
+
+    /// Sets the regions for the reader.
+    ///
+    /// Only records with positions inside the regions will be read. Overlapping regions are
+    /// merged, so that each record is read at most once. Reading regions requires the input to be
+    /// a BGZF-compressed file with a CSI index (`<input>.csi`) or tabix index (`<input>.tbi`).
+    ///
+    /// By default, the entire input will be read.
+    pub fn set_regions(mut self, regions: Option<Vec<Region>>) -> Self {
+        self.regions = regions;
         self
     }
 
@@ -112,11 +250,20 @@ impl Builder {
     }
 }
 
+fn plink_compressed_error() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "PLINK input cannot be BGZF-compressed",
+    )
+}
+
 /// A reader input format.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Format {
     /// BCF.
     Bcf,
+    /// PLINK 1 binary `.bed`, with accompanying `.bim` and `.fam` files.
+    Plink,
     /// VCF.
     Vcf,
 }
@@ -146,6 +293,8 @@ impl Format {
         } else if let Some(buf) = src.get(..BCF_MAGIC_NUMBER.len()) {
             if buf == BCF_MAGIC_NUMBER {
                 return Ok(Format::Bcf);
+            } else if buf == super::plink::BED_MAGIC_NUMBER {
+                return Ok(Format::Plink);
             }
         }
 
@@ -0,0 +1,150 @@
+use std::io;
+
+use crate::input::{
+    genotype::{self, likelihoods},
+    ReadStatus, Sample,
+};
+
+use super::{Alleles, DynReader, Reader};
+
+/// A reader of multiple inputs with the same samples, read one after the other.
+pub struct Chain {
+    readers: Vec<DynReader>,
+    current: usize,
+}
+
+impl Chain {
+    /// Creates a new reader from readers with the same samples in the same order.
+    ///
+    /// # Errors
+    ///
+    /// If any reader has samples differing from the first reader, with the index of the first
+    /// such reader.
+    pub fn new(readers: Vec<DynReader>) -> Result<Self, usize> {
+        if let Some((first, rest)) = readers.split_first() {
+            if let Some(i) = rest
+                .iter()
+                .position(|reader| reader.samples() != first.samples())
+            {
+                return Err(i + 1);
+            }
+        }
+
+        Ok(Self {
+            readers,
+            current: 0,
+        })
+    }
+
+    fn current(&self) -> &dyn Reader {
+        self.readers[self.current].as_ref()
+    }
+
+    fn read_next<T, F>(&mut self, mut read: F) -> ReadStatus<T>
+    where
+        F: FnMut(&mut DynReader) -> ReadStatus<T>,
+    {
+        loop {
+            match read(&mut self.readers[self.current]) {
+                ReadStatus::Done if self.current + 1 < self.readers.len() => self.current += 1,
+                status => return status,
+            }
+        }
+    }
+}
+
+impl Reader for Chain {
+    fn current_alleles(&self) -> io::Result<Alleles> {
+        self.current().current_alleles()
+    }
+
+    fn current_contig(&self) -> &str {
+        self.current().current_contig()
+    }
+
+    fn current_filter_is_pass(&self) -> io::Result<bool> {
+        self.current().current_filter_is_pass()
+    }
+
+    fn current_format_field(&self, key: &str) -> io::Result<Vec<Option<f64>>> {
+        self.current().current_format_field(key)
+    }
+
+    fn current_info_field(&self, key: &str) -> io::Result<Option<String>> {
+        self.current().current_info_field(key)
+    }
+
+    fn current_position(&self) -> usize {
+        self.current().current_position()
+    }
+
+    fn current_quality(&self) -> Option<f64> {
+        self.current().current_quality()
+    }
+
+    fn read_genotypes(&mut self) -> ReadStatus<Vec<genotype::Result>> {
+        self.read_next(|reader| reader.read_genotypes())
+    }
+
+    fn read_likelihoods(&mut self) -> ReadStatus<Vec<likelihoods::Result>> {
+        self.read_next(|reader| reader.read_likelihoods())
+    }
+
+    fn samples(&self) -> &[Sample] {
+        self.current().samples()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::input::Genotype;
+
+    fn vcf_reader(samples: &str, positions: &[usize]) -> DynReader {
+        let mut vcf = format!(
+            "##fileformat=VCFv4.3\n\
+            ##contig=<ID=chr1>\n\
+            ##FORMAT=<ID=GT,Number=1,Type=String,Description=\"Genotype\">\n\
+            #CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\t{samples}\n"
+        );
+        for position in positions {
+            vcf.push_str(&format!("chr1\t{position}\t.\tA\tC\t.\t.\t.\tGT\t0/1\n"));
+        }
+
+        Box::new(super::super::vcf::Reader::new(io::Cursor::new(vcf.into_bytes())).unwrap())
+    }
+
+    #[test]
+    fn test_read_chained() {
+        let mut reader = Chain::new(vec![
+            vcf_reader("sample0", &[1, 2]),
+            vcf_reader("sample0", &[]),
+            vcf_reader("sample0", &[3]),
+        ])
+        .unwrap();
+
+        let mut positions = Vec::new();
+        while let ReadStatus::Read(genotypes) = reader.read_genotypes() {
+            assert_eq!(
+                genotypes,
+                vec![genotype::Result::Genotype(Genotype::new(1, 2).unwrap())]
+            );
+            positions.push(reader.current_position());
+        }
+
+        assert_eq!(positions, vec![1, 2, 3]);
+        assert!(matches!(reader.read_genotypes(), ReadStatus::Done));
+    }
+
+    #[test]
+    fn test_different_samples() {
+        let result = Chain::new(vec![
+            vcf_reader("sample0\tsample1", &[]),
+            vcf_reader("sample0\tsample1", &[]),
+            vcf_reader("sample1\tsample0", &[]),
+        ]);
+
+        assert_eq!(result.err(), Some(2));
+    }
+}
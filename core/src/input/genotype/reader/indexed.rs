@@ -0,0 +1,308 @@
+//! Indexed genotype reading.
+
+use std::{
+    collections::VecDeque,
+    ffi::OsString,
+    io,
+    path::{Path, PathBuf},
+};
+
+use noodles_bgzf as bgzf;
+use noodles_core::{region::Interval, Position};
+use noodles_csi::{self as csi, index::reference_sequence::bin::Chunk};
+use noodles_tabix as tabix;
+
+use crate::input::{genotype, ReadStatus, Region, Sample};
+
+use super::{likelihoods, Alleles, Reader};
+
+/// A reader that reads records one at a time, with separate parsing of the current record.
+pub(super) trait RecordReader: Reader {
+    /// Reads the next record, returning the number of bytes read.
+    fn read_record(&mut self) -> io::Result<usize>;
+
+    /// Returns the genotypes of the current record.
+    fn current_genotypes(&self) -> io::Result<Vec<genotype::Result>>;
+
+    /// Returns the genotype likelihoods of the current record.
+    fn current_likelihoods(&self) -> io::Result<Vec<likelihoods::Result>>;
+}
+
+/// A reader of BGZF-compressed input that can seek to virtual positions.
+pub(super) trait VirtualSeek {
+    /// Returns the reference sequence ID of the contig defined in the header, if any.
+    fn reference_sequence_id(&self, contig: &str) -> Option<usize>;
+
+    /// Seeks to the virtual position.
+    fn seek(&mut self, pos: bgzf::VirtualPosition) -> io::Result<bgzf::VirtualPosition>;
+
+    /// Returns the current virtual position.
+    fn virtual_position(&self) -> bgzf::VirtualPosition;
+}
+
+/// A reader restricted to a set of regions via an index.
+pub(super) struct IndexedReader<T> {
+    inner: T,
+    index: csi::Index,
+    regions: VecDeque<Region>,
+    region: Option<Region>,
+    chunks: VecDeque<Chunk>,
+    chunk_end: Option<bgzf::VirtualPosition>,
+}
+
+impl<T> IndexedReader<T>
+where
+    T: RecordReader + VirtualSeek,
+{
+    pub fn new(inner: T, index: csi::Index, regions: Vec<Region>) -> Self {
+        Self {
+            inner,
+            index,
+            regions: merge_regions(regions).into(),
+            region: None,
+            chunks: VecDeque::new(),
+            chunk_end: None,
+        }
+    }
+
+    fn in_chunk(&self) -> bool {
+        self.chunk_end
+            .is_some_and(|end| self.inner.virtual_position() < end)
+    }
+
+    fn next_chunk(&mut self) -> io::Result<Option<Chunk>> {
+        loop {
+            if let Some(chunk) = self.chunks.pop_front() {
+                return Ok(Some(chunk));
+            }
+
+            let Some(region) = self.regions.pop_front() else {
+                return Ok(None);
+            };
+
+            // Contigs without records in the index have no chunks
+            if let Some(id) = self
+                .reference_sequence_id(region.contig())
+                .filter(|&id| id < self.index.reference_sequences().len())
+            {
+                self.chunks = self.index.query(id, interval(&region)?)?.into();
+            }
+
+            self.region = Some(region);
+        }
+    }
+
+    fn read_record(&mut self) -> io::Result<usize> {
+        loop {
+            if !self.in_chunk() {
+                match self.next_chunk()? {
+                    Some(chunk) => {
+                        self.inner.seek(chunk.start())?;
+                        self.chunk_end = Some(chunk.end());
+                    }
+                    None => return Ok(0),
+                }
+            }
+
+            let bytes = self.inner.read_record()?;
+            if bytes == 0 {
+                self.chunk_end = None;
+                continue;
+            }
+
+            let region = self.region.as_ref().expect("region set when reading chunk");
+            let contig = self.inner.current_contig();
+            let position = self.inner.current_position();
+
+            if region.contains(contig, position) {
+                return Ok(bytes);
+            } else if contig != region.contig() || region.end().is_some_and(|end| position > end) {
+                // Remaining records in chunk are past the region
+                self.chunk_end = None;
+            }
+        }
+    }
+
+    fn reference_sequence_id(&self, contig: &str) -> Option<usize> {
+        // Tabix indices define their own reference sequences, otherwise use input header
+        match self.index.header() {
+            Some(header) => header.reference_sequence_names().get_index_of(contig),
+            None => self.inner.reference_sequence_id(contig),
+        }
+    }
+}
+
+impl<T> Reader for IndexedReader<T>
+where
+    T: RecordReader + VirtualSeek,
+{
+    fn current_alleles(&self) -> io::Result<Alleles> {
+        self.inner.current_alleles()
+    }
+
+    fn current_contig(&self) -> &str {
+        self.inner.current_contig()
+    }
+
+    fn current_filter_is_pass(&self) -> io::Result<bool> {
+        self.inner.current_filter_is_pass()
+    }
+
+    fn current_format_field(&self, key: &str) -> io::Result<Vec<Option<f64>>> {
+        self.inner.current_format_field(key)
+    }
+
+    fn current_info_field(&self, key: &str) -> io::Result<Option<String>> {
+        self.inner.current_info_field(key)
+    }
+
+    fn current_position(&self) -> usize {
+        self.inner.current_position()
+    }
+
+    fn current_quality(&self) -> Option<f64> {
+        self.inner.current_quality()
+    }
+
+    fn read_genotypes(&mut self) -> ReadStatus<Vec<genotype::Result>> {
+        ReadStatus::from_bytes_read(self.read_record())
+            .and_then(|()| self.inner.current_genotypes())
+    }
+
+    fn read_likelihoods(&mut self) -> ReadStatus<Vec<likelihoods::Result>> {
+        ReadStatus::from_bytes_read(self.read_record())
+            .and_then(|()| self.inner.current_likelihoods())
+    }
+
+    fn samples(&self) -> &[Sample] {
+        self.inner.samples()
+    }
+}
+
+/// Reads the index of the input at the provided path.
+///
+/// A CSI index at `<path>.csi` is preferred, falling back to a tabix index at `<path>.tbi`.
+pub(super) fn read_index(path: &Path) -> io::Result<csi::Index> {
+    let csi_path = with_appended_extension(path, "csi");
+    let tbi_path = with_appended_extension(path, "tbi");
+
+    if csi_path.exists() {
+        csi::read(csi_path)
+    } else if tbi_path.exists() {
+        tabix::read(tbi_path)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "no CSI or tabix index found for '{}', expected '{}' or '{}'",
+                path.display(),
+                csi_path.display(),
+                tbi_path.display()
+            ),
+        ))
+    }
+}
+
+fn with_appended_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut s = OsString::from(path.as_os_str());
+    s.push(".");
+    s.push(extension);
+    PathBuf::from(s)
+}
+
+fn interval(region: &Region) -> io::Result<Interval> {
+    let position = |n| {
+        Position::new(n).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid region {region}"),
+            )
+        })
+    };
+
+    let start = position(region.start())?;
+
+    Ok(match region.end() {
+        Some(end) => Interval::from(start..=position(end)?),
+        None => Interval::from(start..),
+    })
+}
+
+/// Merges overlapping or adjacent regions on the same contig.
+///
+/// Contigs are kept in order of first appearance, and regions within contigs are sorted by start.
+fn merge_regions(regions: Vec<Region>) -> Vec<Region> {
+    let mut contigs: Vec<(String, Vec<Region>)> = Vec::new();
+
+    for region in regions {
+        match contigs
+            .iter_mut()
+            .find(|(contig, _)| contig == region.contig())
+        {
+            Some((_, contig_regions)) => contig_regions.push(region),
+            None => contigs.push((region.contig().to_string(), vec![region])),
+        }
+    }
+
+    let mut merged = Vec::new();
+
+    for (_, mut contig_regions) in contigs {
+        contig_regions.sort_by_key(Region::start);
+
+        let mut iter = contig_regions.into_iter();
+        let mut current = iter.next().expect("contig has at least one region");
+
+        for region in iter {
+            match current.end() {
+                Some(end) if region.start() > end + 1 => {
+                    merged.push(std::mem::replace(&mut current, region));
+                }
+                Some(end) => {
+                    let new_end = region.end().map(|region_end| region_end.max(end));
+                    current = Region::new(current.contig(), current.start(), new_end);
+                }
+                None => (),
+            }
+        }
+
+        merged.push(current);
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_regions() {
+        let regions = vec![
+            Region::new("chr2", 10, Some(20)),
+            Region::new("chr1", 30, Some(40)),
+            Region::new("chr2", 1, Some(5)),
+            Region::new("chr2", 15, Some(25)),
+            Region::new("chr2", 26, Some(30)),
+            Region::new("chr1", 1, Some(10)),
+            Region::new("chr1", 35, None),
+        ];
+
+        assert_eq!(
+            merge_regions(regions),
+            vec![
+                Region::new("chr2", 1, Some(5)),
+                Region::new("chr2", 10, Some(30)),
+                Region::new("chr1", 1, Some(10)),
+                Region::new("chr1", 30, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_with_appended_extension() {
+        assert_eq!(
+            with_appended_extension(Path::new("dir/file.vcf.gz"), "tbi"),
+            PathBuf::from("dir/file.vcf.gz.tbi")
+        );
+    }
+}
@@ -0,0 +1,330 @@
+use std::{
+    fs::File,
+    io::{self, BufRead},
+    path::Path,
+};
+
+use crate::input::{
+    genotype::{self, likelihoods, Genotype},
+    ReadStatus, Sample,
+};
+
+use super::Alleles;
+
+/// The magic number of a PLINK `.bed` file in variant-major mode.
+pub(super) const BED_MAGIC_NUMBER: [u8; 3] = [0x6c, 0x1b, 0x01];
+
+/// A reader of PLINK 1 binary `.bed` files, with variants from a `.bim` file and samples from a
+/// `.fam` file.
+///
+/// Genotypes are taken to be diploid, and the second allele in the `.bim` file is taken to be the
+/// reference allele. PLINK files have no FILTER, QUAL, INFO, or FORMAT fields, and so these are
+/// treated as missing for all records.
+pub struct Reader<R> {
+    pub bed: R,
+    pub bim: Box<dyn BufRead>,
+    pub samples: Vec<Sample>,
+    pub buf: Vec<u8>,
+    pub record: Record,
+}
+
+impl<R> Reader<R>
+where
+    R: io::BufRead,
+{
+    /// Creates a new reader from a `.bed` reader, with `.bim` and `.fam` files next to the
+    /// provided `.bed` path.
+    pub fn from_bed_path(bed: R, path: &Path) -> io::Result<Self> {
+        let open = |extension| {
+            let path = path.with_extension(extension);
+
+            File::open(&path).map(io::BufReader::new).map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("failed to open PLINK file '{}': {e}", path.display()),
+                )
+            })
+        };
+
+        let bim = open("bim")?;
+        let fam = open("fam")?;
+
+        Self::new(bed, Box::new(bim), fam)
+    }
+
+    pub fn new<F>(mut bed: R, bim: Box<dyn BufRead>, fam: F) -> io::Result<Self>
+    where
+        F: BufRead,
+    {
+        let mut magic = [0; BED_MAGIC_NUMBER.len()];
+        bed.read_exact(&mut magic)?;
+
+        if magic != BED_MAGIC_NUMBER {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid PLINK .bed magic number, only variant-major .bed files are supported",
+            ));
+        }
+
+        let samples = samples_from_fam(fam)?;
+        let buf = vec![0; (samples.len() + 3) / 4];
+
+        Ok(Self {
+            bed,
+            bim,
+            samples,
+            buf,
+            record: Record::default(),
+        })
+    }
+
+    fn read_record(&mut self) -> io::Result<usize> {
+        let mut line = String::new();
+        let bytes_read = self.bim.read_line(&mut line)?;
+
+        if bytes_read == 0 {
+            return Ok(0);
+        }
+
+        self.record = line.parse()?;
+
+        self.bed
+            .read_exact(&mut self.buf)
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::UnexpectedEof => io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "PLINK .bed file has fewer variants than .bim file",
+                ),
+                _ => e,
+            })?;
+
+        Ok(bytes_read)
+    }
+
+    fn current_genotypes(&self) -> Vec<genotype::Result> {
+        genotypes_from_bed(&self.buf, self.samples.len())
+    }
+}
+
+impl<R> super::Reader for Reader<R>
+where
+    R: io::BufRead,
+{
+    fn current_alleles(&self) -> io::Result<Alleles> {
+        Ok(self.record.alleles.clone())
+    }
+
+    fn current_contig(&self) -> &str {
+        &self.record.contig
+    }
+
+    fn current_filter_is_pass(&self) -> io::Result<bool> {
+        Ok(false)
+    }
+
+    fn current_format_field(&self, _key: &str) -> io::Result<Vec<Option<f64>>> {
+        Ok(vec![None; self.samples.len()])
+    }
+
+    fn current_info_field(&self, _key: &str) -> io::Result<Option<String>> {
+        Ok(None)
+    }
+
+    fn current_position(&self) -> usize {
+        self.record.position
+    }
+
+    fn current_quality(&self) -> Option<f64> {
+        None
+    }
+
+    fn read_genotypes(&mut self) -> ReadStatus<Vec<genotype::Result>> {
+        ReadStatus::from_bytes_read(self.read_record()).map(|()| self.current_genotypes())
+    }
+
+    fn read_likelihoods(&mut self) -> ReadStatus<Vec<likelihoods::Result>> {
+        ReadStatus::Error(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "genotype likelihoods cannot be read from PLINK input",
+        ))
+    }
+
+    fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+}
+
+/// A variant record from a `.bim` file.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Record {
+    contig: String,
+    position: usize,
+    alleles: Alleles,
+}
+
+impl std::str::FromStr for Record {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid PLINK .bim line '{}'", s.trim_end()),
+            )
+        };
+
+        let fields = s.split_whitespace().collect::<Vec<_>>();
+        let [contig, _id, _cm, position, allele1, allele2] = fields.as_slice() else {
+            return Err(invalid());
+        };
+
+        // PLINK uses the allele code `0` for a missing allele, e.g. at monomorphic sites
+        let alternative = match *allele1 {
+            "0" => Vec::new(),
+            allele => vec![allele.to_string()],
+        };
+
+        Ok(Self {
+            contig: contig.to_string(),
+            position: position.parse().map_err(|_| invalid())?,
+            alleles: Alleles {
+                reference: allele2.to_string(),
+                alternative,
+            },
+        })
+    }
+}
+
+/// Decodes a block of packed genotypes for a single variant.
+///
+/// Each byte holds four genotypes of two bits, starting from the least significant bits, where
+/// `00` is homozygous for the first allele, `01` is missing, `10` is heterozygous, and `11` is
+/// homozygous for the second allele.
+fn genotypes_from_bed(buf: &[u8], samples: usize) -> Vec<genotype::Result> {
+    buf.iter()
+        .flat_map(|byte| (0..4).map(move |i| (byte >> (2 * i)) & 0b11))
+        .take(samples)
+        .map(|code| {
+            let alleles = match code {
+                0b00 => 2,
+                0b10 => 1,
+                0b11 => 0,
+                _ => return genotype::Result::Skipped(genotype::Skipped::Missing),
+            };
+
+            genotype::Result::Genotype(Genotype::new(alleles, 2).unwrap())
+        })
+        .collect()
+}
+
+/// Reads the samples from a `.fam` file, using the individual ID of each line.
+fn samples_from_fam<R>(reader: R) -> io::Result<Vec<Sample>>
+where
+    R: BufRead,
+{
+    reader
+        .lines()
+        .filter(|line| !line.as_ref().is_ok_and(|line| line.trim().is_empty()))
+        .map(|line| {
+            let line = line?;
+
+            line.split_whitespace()
+                .nth(1)
+                .map(Sample::from)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid PLINK .fam line '{line}'"),
+                    )
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::input::genotype::reader::Reader as _;
+
+    fn genotype(alleles: usize) -> genotype::Result {
+        genotype::Result::Genotype(Genotype::new(alleles, 2).unwrap())
+    }
+
+    #[test]
+    fn test_genotypes_from_bed() {
+        const MISSING: genotype::Result = genotype::Result::Skipped(genotype::Skipped::Missing);
+
+        assert_eq!(
+            genotypes_from_bed(&[0b11_10_01_00, 0b00_00_00_10], 5),
+            vec![genotype(2), MISSING, genotype(1), genotype(0), genotype(1)]
+        );
+    }
+
+    #[test]
+    fn test_parse_record() {
+        assert_eq!(
+            "1\trs1\t0\t12\tA\tG\n".parse::<Record>().unwrap(),
+            Record {
+                contig: String::from("1"),
+                position: 12,
+                alleles: Alleles {
+                    reference: String::from("G"),
+                    alternative: vec![String::from("A")],
+                },
+            }
+        );
+
+        assert!("1 rs1 0 12 0 G"
+            .parse::<Record>()
+            .unwrap()
+            .alleles
+            .alternative
+            .is_empty());
+        assert!("1 rs1 0 x A G".parse::<Record>().is_err());
+        assert!("1 rs1 12 A G".parse::<Record>().is_err());
+    }
+
+    #[test]
+    fn test_read_genotypes() {
+        let bed = [0x6c, 0x1b, 0x01, 0b00_11_10_00, 0b00_00_01_11];
+        let bim = "chr1 a 0 5 T C\nchr2 b 0 7 A G\n";
+        let fam = "fam0 sample0 0 0 0 -9\nfam1 sample1 0 0 0 -9\nfam1 sample2 0 0 0 -9\n";
+
+        let mut reader = Reader::new(&bed[..], Box::new(bim.as_bytes()), fam.as_bytes()).unwrap();
+        assert_eq!(
+            reader.samples(),
+            ["sample0", "sample1", "sample2"].map(Sample::from)
+        );
+
+        let ReadStatus::Read(genotypes) = reader.read_genotypes() else {
+            panic!("failed to read first record")
+        };
+        assert_eq!(genotypes, vec![genotype(2), genotype(1), genotype(0)]);
+        assert_eq!(
+            (reader.current_contig(), reader.current_position()),
+            ("chr1", 5)
+        );
+
+        let ReadStatus::Read(genotypes) = reader.read_genotypes() else {
+            panic!("failed to read second record")
+        };
+        assert_eq!(
+            genotypes,
+            vec![
+                genotype(0),
+                genotype::Result::Skipped(genotype::Skipped::Missing),
+                genotype(2)
+            ]
+        );
+
+        assert!(matches!(reader.read_genotypes(), ReadStatus::Done));
+    }
+
+    #[test]
+    fn test_invalid_magic_number() {
+        let bed = [0x6c, 0x1b, 0x00];
+
+        assert!(Reader::new(&bed[..], Box::new(&b""[..]), &b""[..]).is_err());
+    }
+}
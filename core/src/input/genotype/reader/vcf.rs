@@ -1,15 +1,30 @@
 use std::io;
 
+use noodles_bgzf as bgzf;
 use noodles_vcf as vcf;
 use vcf::record::{
-    genotypes::sample::value::genotype::Genotype as VcfGenotype, Record as VcfRecord,
+    genotypes::{
+        keys::{key, Key as VcfFormatKey},
+        sample::{
+            value::{genotype::Genotype as VcfGenotype, Array as VcfArray},
+            Value as VcfValue,
+        },
+        Sample as VcfSample,
+    },
+    info::field::{Key as VcfInfoKey, Value as VcfInfoValue},
+    Filters as VcfFilters, Genotypes as VcfGenotypes, Info as VcfInfo, Record as VcfRecord,
 };
 
 use crate::input::{
-    genotype::{self, Genotype},
+    genotype::{self, likelihoods, Genotype, Likelihoods},
     ReadStatus, Sample,
 };
 
+use super::{
+    indexed::{RecordReader, VirtualSeek},
+    Alleles,
+};
+
 pub struct Reader<R> {
     pub inner: vcf::Reader<R>,
     pub header: vcf::Header,
@@ -39,24 +54,39 @@ where
             buf: VcfRecord::default(),
         })
     }
+}
 
-    fn read_genotypes(&mut self) -> ReadStatus<Vec<Option<VcfGenotype>>> {
-        match self.inner.read_record(&self.header, &mut self.buf) {
-            Ok(0) => ReadStatus::Done,
-            Ok(_) => {
-                let result = self
-                    .buf
-                    .genotypes()
-                    .genotypes()
-                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
-
-                match result {
-                    Ok(genotypes) => ReadStatus::Read(genotypes),
-                    Err(e) => ReadStatus::Error(e),
-                }
-            }
-            Err(e) => ReadStatus::Error(e),
-        }
+impl<R> RecordReader for Reader<R>
+where
+    R: io::BufRead,
+{
+    fn read_record(&mut self) -> io::Result<usize> {
+        self.inner.read_record(&self.header, &mut self.buf)
+    }
+
+    fn current_genotypes(&self) -> io::Result<Vec<genotype::Result>> {
+        genotypes_from_vcf(self.buf.genotypes())
+    }
+
+    fn current_likelihoods(&self) -> io::Result<Vec<likelihoods::Result>> {
+        Ok(likelihoods_from_vcf(self.buf.genotypes()))
+    }
+}
+
+impl<R> VirtualSeek for Reader<bgzf::Reader<R>>
+where
+    R: io::Read + io::Seek,
+{
+    fn reference_sequence_id(&self, contig: &str) -> Option<usize> {
+        self.header.contigs().get_index_of(contig)
+    }
+
+    fn seek(&mut self, pos: bgzf::VirtualPosition) -> io::Result<bgzf::VirtualPosition> {
+        self.inner.seek(pos)
+    }
+
+    fn virtual_position(&self) -> bgzf::VirtualPosition {
+        self.inner.virtual_position()
     }
 }
 
@@ -64,48 +94,171 @@ impl<R> super::Reader for Reader<R>
 where
     R: io::BufRead,
 {
+    fn current_alleles(&self) -> io::Result<Alleles> {
+        Ok(alleles_from_vcf(&self.buf))
+    }
+
     fn current_contig(&self) -> &str {
         match self.buf.chromosome() {
             vcf::record::Chromosome::Name(s) | vcf::record::Chromosome::Symbol(s) => s,
         }
     }
 
+    fn current_filter_is_pass(&self) -> io::Result<bool> {
+        Ok(matches!(self.buf.filters(), Some(VcfFilters::Pass)))
+    }
+
+    fn current_format_field(&self, key: &str) -> io::Result<Vec<Option<f64>>> {
+        format_field_from_vcf(self.buf.genotypes(), key)
+    }
+
+    fn current_info_field(&self, key: &str) -> io::Result<Option<String>> {
+        info_field_from_vcf(self.buf.info(), key)
+    }
+
     fn current_position(&self) -> usize {
         self.buf.position().into()
     }
 
+    fn current_quality(&self) -> Option<f64> {
+        self.buf
+            .quality_score()
+            .map(|score| f64::from(f32::from(score)))
+    }
+
     fn read_genotypes(&mut self) -> ReadStatus<Vec<genotype::Result>> {
-        self.read_genotypes().map(|vcf_genotypes| {
+        ReadStatus::from_bytes_read(self.read_record()).and_then(|()| self.current_genotypes())
+    }
+
+    fn read_likelihoods(&mut self) -> ReadStatus<Vec<likelihoods::Result>> {
+        ReadStatus::from_bytes_read(self.read_record()).and_then(|()| self.current_likelihoods())
+    }
+
+    fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+}
+
+pub(super) fn alleles_from_vcf(record: &VcfRecord) -> Alleles {
+    Alleles {
+        reference: record.reference_bases().to_string(),
+        alternative: record
+            .alternate_bases()
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+    }
+}
+
+pub(super) fn format_field_from_vcf(
+    genotypes: &VcfGenotypes,
+    key: &str,
+) -> io::Result<Vec<Option<f64>>> {
+    let key: VcfFormatKey = key
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    genotypes
+        .values()
+        .map(|sample| match sample.get(&key) {
+            Some(Some(VcfValue::Integer(value))) => Ok(Some(f64::from(*value))),
+            Some(Some(VcfValue::Float(value))) => Ok(Some(f64::from(*value))),
+            // Only the first value of arrays is used, e.g. the depth of the reference allele
+            Some(Some(VcfValue::Array(VcfArray::Integer(values)))) => {
+                Ok(values.first().copied().flatten().map(f64::from))
+            }
+            Some(Some(VcfValue::Array(VcfArray::Float(values)))) => {
+                Ok(values.first().copied().flatten().map(f64::from))
+            }
+            Some(Some(_)) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("FORMAT field '{key}' is not numeric"),
+            )),
+            Some(None) | None => Ok(None),
+        })
+        .collect()
+}
+
+pub(super) fn info_field_from_vcf(info: &VcfInfo, key: &str) -> io::Result<Option<String>> {
+    let key: VcfInfoKey = key
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    Ok(info.get(&key).map(|value| match value {
+        Some(VcfInfoValue::Flag) | None => String::new(),
+        Some(value) => value.to_string(),
+    }))
+}
+
+pub(super) fn genotypes_from_vcf(genotypes: &VcfGenotypes) -> io::Result<Vec<genotype::Result>> {
+    genotypes
+        .genotypes()
+        .map(|vcf_genotypes| {
             vcf_genotypes
                 .into_iter()
                 .map(genotype::Result::from)
                 .collect()
         })
-    }
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
 
-    fn samples(&self) -> &[Sample] {
-        &self.samples
-    }
+pub(super) fn likelihoods_from_vcf(genotypes: &VcfGenotypes) -> Vec<likelihoods::Result> {
+    genotypes.values().map(likelihoods::Result::from).collect()
 }
 
 impl From<Option<VcfGenotype>> for genotype::Result {
     fn from(genotype: Option<VcfGenotype>) -> Self {
-        match genotype {
-            Some(genotype) => match &genotype[..] {
-                [a, b] => match (a.position(), b.position()) {
-                    (Some(a), Some(b)) => match Genotype::try_from_raw(a + b) {
-                        Some(genotype) => genotype::Result::Genotype(genotype),
-                        None => genotype::Result::Skipped(genotype::Skipped::Multiallelic),
-                    },
-                    _ => genotype::Result::Skipped(genotype::Skipped::Missing),
-                },
-                _ => genotype::Result::Error(genotype::Error::PloidyError),
+        let Some(genotype) = genotype else {
+            return genotype::Result::Skipped(genotype::Skipped::Missing);
+        };
+
+        let positions = genotype
+            .iter()
+            .map(|allele| allele.position())
+            .collect::<Option<Vec<_>>>();
+
+        match positions {
+            Some(positions) if positions.iter().any(|&position| position > 1) => {
+                genotype::Result::Skipped(genotype::Skipped::Multiallelic)
+            }
+            Some(positions) => match Genotype::new(positions.iter().sum(), positions.len()) {
+                Some(genotype) => genotype::Result::Genotype(genotype),
+                None => genotype::Result::Skipped(genotype::Skipped::Missing),
             },
             None => genotype::Result::Skipped(genotype::Skipped::Missing),
         }
     }
 }
 
+impl From<VcfSample<'_>> for likelihoods::Result {
+    fn from(sample: VcfSample<'_>) -> Self {
+        // Prefer the unrounded GL field where available, and fall back to PL otherwise
+        let likelihoods = match (
+            sample.get(&key::GENOTYPE_LIKELIHOODS),
+            sample.get(&key::ROUNDED_GENOTYPE_LIKELIHOODS),
+        ) {
+            (Some(Some(VcfValue::Array(VcfArray::Float(values)))), _) => values
+                .iter()
+                .map(|v| v.map(f64::from))
+                .collect::<Option<Vec<_>>>()
+                .map(Likelihoods::from_log10),
+            (_, Some(Some(VcfValue::Array(VcfArray::Integer(values))))) => values
+                .iter()
+                .map(|v| v.map(f64::from))
+                .collect::<Option<Vec<_>>>()
+                .map(Likelihoods::from_phred),
+            _ => None,
+        };
+
+        match likelihoods {
+            Some(likelihoods) if !likelihoods.as_slice().is_empty() => {
+                likelihoods::Result::Likelihoods(likelihoods)
+            }
+            _ => likelihoods::Result::Skipped(genotype::Skipped::Missing),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -116,24 +269,24 @@ mod tests {
     fn test_genotype_from_vcf_genotype() -> Result<(), Box<dyn std::error::Error>> {
         assert_eq!(
             genotype::Result::from(Some(VcfGenotype::from_str("0/0")?)),
-            genotype::Result::Genotype(Genotype::Zero)
+            genotype::Result::Genotype(Genotype::new(0, 2).unwrap())
         );
         assert_eq!(
             genotype::Result::from(Some(VcfGenotype::from_str("0/1")?)),
-            genotype::Result::Genotype(Genotype::One)
+            genotype::Result::Genotype(Genotype::new(1, 2).unwrap())
         );
         assert_eq!(
             genotype::Result::from(Some(VcfGenotype::from_str("1/1")?)),
-            genotype::Result::Genotype(Genotype::Two)
+            genotype::Result::Genotype(Genotype::new(2, 2).unwrap())
         );
 
         assert_eq!(
             genotype::Result::from(Some(VcfGenotype::from_str("0|1")?)),
-            genotype::Result::Genotype(Genotype::One)
+            genotype::Result::Genotype(Genotype::new(1, 2).unwrap())
         );
         assert_eq!(
             genotype::Result::from(Some(VcfGenotype::from_str("1|0")?)),
-            genotype::Result::Genotype(Genotype::One)
+            genotype::Result::Genotype(Genotype::new(1, 2).unwrap())
         );
 
         Ok(())
@@ -176,15 +329,31 @@ mod tests {
     }
 
     #[test]
-    fn test_genotype_from_vcf_genotype_not_diploid() -> Result<(), Box<dyn std::error::Error>> {
+    fn test_genotype_from_vcf_genotype_multiallelic_zero_sum(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(
+            genotype::Result::from(Some(VcfGenotype::from_str("0/2")?)),
+            genotype::Result::Skipped(genotype::Skipped::Multiallelic),
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_genotype_from_vcf_genotype_other_ploidy() -> Result<(), Box<dyn std::error::Error>> {
         assert_eq!(
-            genotype::Result::from(Some(VcfGenotype::from_str("0")?)),
-            genotype::Result::Error(genotype::Error::PloidyError),
+            genotype::Result::from(Some(VcfGenotype::from_str("1")?)),
+            genotype::Result::Genotype(Genotype::new(1, 1).unwrap()),
         );
 
         assert_eq!(
-            genotype::Result::from(Some(VcfGenotype::from_str("0/0/0")?)),
-            genotype::Result::Error(genotype::Error::PloidyError),
+            genotype::Result::from(Some(VcfGenotype::from_str("0/1/1/1")?)),
+            genotype::Result::Genotype(Genotype::new(3, 4).unwrap()),
+        );
+
+        assert_eq!(
+            genotype::Result::from(Some(VcfGenotype::from_str("0/./1")?)),
+            genotype::Result::Skipped(genotype::Skipped::Missing),
         );
 
         Ok(())
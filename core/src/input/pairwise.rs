@@ -0,0 +1,194 @@
+//! Pairwise two-sample spectra.
+
+use crate::{
+    input::{genotype, Sample},
+    Scs,
+};
+
+const PLOIDY: usize = 2;
+const ELEMENTS: usize = (PLOIDY + 1) * (PLOIDY + 1);
+
+/// Two-sample spectra for all pairs of diploid samples.
+///
+/// For each pair of samples, a 3x3 spectrum is accumulated, where entry `[i, j]` counts the sites
+/// where the first sample carries `i` and the second sample `j` alternative alleles. Such spectra
+/// are used for kinship statistics like [`Scs::king`]. Sites are counted for a pair whenever both
+/// samples have a genotype, regardless of missing genotypes in other samples.
+#[derive(Clone, Debug)]
+pub struct Pairwise {
+    samples: Vec<Sample>,
+    spectra: Vec<[u64; ELEMENTS]>,
+    genotypes: Vec<Option<usize>>,
+}
+
+impl Pairwise {
+    /// Adds a site to the spectra of all pairs of samples.
+    ///
+    /// The genotypes must be in the same order as the samples. Skipped genotypes are treated as
+    /// missing.
+    ///
+    /// # Errors
+    ///
+    /// If any genotype is not diploid, or is an error.
+    ///
+    /// # Panics
+    ///
+    /// If the number of genotypes does not match the number of samples.
+    pub fn add_genotypes<'a, I>(&mut self, genotypes: I) -> Result<(), genotype::Error>
+    where
+        I: IntoIterator<Item = &'a genotype::Result>,
+    {
+        let mut n = 0;
+        for (buf, genotype) in self.genotypes.iter_mut().zip(genotypes) {
+            *buf = match genotype {
+                genotype::Result::Genotype(genotype) if genotype.ploidy() == PLOIDY => {
+                    Some(genotype.alleles())
+                }
+                genotype::Result::Genotype(genotype) => {
+                    return Err(genotype::Error::PloidyError {
+                        expected: PLOIDY,
+                        actual: genotype.ploidy(),
+                    })
+                }
+                genotype::Result::Skipped(_) => None,
+                genotype::Result::Error(e) => return Err(*e),
+            };
+            n += 1;
+        }
+        assert_eq!(
+            n,
+            self.samples.len(),
+            "number of genotypes must match samples"
+        );
+
+        let mut spectra = self.spectra.iter_mut();
+        for (i, first) in self.genotypes.iter().enumerate() {
+            let rest = &self.genotypes[i + 1..];
+            let spectra = spectra.by_ref().take(rest.len());
+
+            if let Some(first) = first {
+                for (spectrum, second) in spectra.zip(rest) {
+                    if let Some(second) = second {
+                        spectrum[first * (PLOIDY + 1) + second] += 1;
+                    }
+                }
+            } else {
+                spectra.for_each(drop);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns an iterator over all pairs of samples and their spectra.
+    ///
+    /// Pairs are ordered by the first sample, and then by the second sample, using the order of
+    /// the samples provided when creating the spectra. Each pair occurs once.
+    pub fn iter(&self) -> impl Iterator<Item = (&Sample, &Sample, Scs)> {
+        self.samples
+            .iter()
+            .enumerate()
+            .flat_map(move |(i, first)| {
+                self.samples[i + 1..]
+                    .iter()
+                    .map(move |second| (first, second))
+            })
+            .zip(self.spectra.iter())
+            .map(|((first, second), spectrum)| {
+                let data = spectrum.iter().map(|&x| x as f64).collect::<Vec<_>>();
+
+                (
+                    first,
+                    second,
+                    Scs::new(data, [PLOIDY + 1, PLOIDY + 1]).expect("valid shape"),
+                )
+            })
+    }
+
+    /// Creates new, empty spectra for all pairs of the provided samples.
+    pub fn new(samples: Vec<Sample>) -> Self {
+        let n = samples.len();
+
+        Self {
+            spectra: vec![[0; ELEMENTS]; n * n.saturating_sub(1) / 2],
+            genotypes: vec![None; n],
+            samples,
+        }
+    }
+
+    /// Returns the samples.
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::input::{genotype::Skipped, Genotype};
+
+    fn genotypes(alleles: &[Option<usize>]) -> Vec<genotype::Result> {
+        alleles
+            .iter()
+            .map(|alleles| match alleles {
+                Some(alleles) => genotype::Result::Genotype(Genotype::new(*alleles, 2).unwrap()),
+                None => genotype::Result::Skipped(Skipped::Missing),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pairwise() {
+        let mut pairwise = Pairwise::new(vec![
+            Sample::from("a"),
+            Sample::from("b"),
+            Sample::from("c"),
+        ]);
+
+        pairwise
+            .add_genotypes(&genotypes(&[Some(0), Some(1), Some(2)]))
+            .unwrap();
+        pairwise
+            .add_genotypes(&genotypes(&[Some(1), None, Some(1)]))
+            .unwrap();
+
+        let pairs = pairwise
+            .iter()
+            .map(|(first, second, scs)| (first.as_ref(), second.as_ref(), scs))
+            .collect::<Vec<_>>();
+
+        let scs = |entries: &[[usize; 2]]| {
+            let mut scs = Scs::from_zeros([3, 3]);
+            entries.iter().for_each(|&entry| scs[entry] += 1.0);
+            scs
+        };
+
+        assert_eq!(
+            pairs,
+            vec![
+                ("a", "b", scs(&[[0, 1]])),
+                ("a", "c", scs(&[[0, 2], [1, 1]])),
+                ("b", "c", scs(&[[1, 2]])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pairwise_not_diploid() {
+        let mut pairwise = Pairwise::new(vec![Sample::from("a"), Sample::from("b")]);
+
+        let genotypes = vec![
+            genotype::Result::Genotype(Genotype::new(0, 2).unwrap()),
+            genotype::Result::Genotype(Genotype::new(1, 1).unwrap()),
+        ];
+
+        assert_eq!(
+            pairwise.add_genotypes(&genotypes),
+            Err(genotype::Error::PloidyError {
+                expected: 2,
+                actual: 1
+            })
+        );
+    }
+}
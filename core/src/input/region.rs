@@ -0,0 +1,387 @@
+//! Genomic regions.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::File,
+    io::{self, BufRead},
+    path::Path,
+    str::FromStr,
+};
+
+/// A genomic region.
+///
+/// Positions are 1-based and inclusive, as in VCF.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Region {
+    contig: String,
+    start: usize,
+    end: Option<usize>,
+}
+
+impl Region {
+    /// Returns true if the position on the contig is contained in the region, false otherwise.
+    pub fn contains(&self, contig: &str, position: usize) -> bool {
+        self.contig == contig
+            && self.start <= position
+            && self.end.map_or(true, |end| position <= end)
+    }
+
+    /// Returns the contig of the region.
+    pub fn contig(&self) -> &str {
+        &self.contig
+    }
+
+    /// Returns the end position of the region, if bounded.
+    pub fn end(&self) -> Option<usize> {
+        self.end
+    }
+
+    /// Reads regions from a BED file.
+    ///
+    /// Only the first three columns of the BED file are used, and header, track and comment lines
+    /// are ignored. Note that BED uses 0-based, half-open intervals, which are converted to
+    /// 1-based, inclusive regions.
+    pub fn from_bed_path<P>(path: P) -> io::Result<Vec<Self>>
+    where
+        P: AsRef<Path>,
+    {
+        File::open(path).and_then(|f| Self::from_bed_reader(io::BufReader::new(f)))
+    }
+
+    /// Reads regions from a reader in BED format.
+    ///
+    /// See [`Region::from_bed_path`] for details.
+    pub fn from_bed_reader<R>(reader: R) -> io::Result<Vec<Self>>
+    where
+        R: BufRead,
+    {
+        let mut regions = Vec::new();
+
+        for line in reader.lines() {
+            let line = line?;
+
+            if line.is_empty()
+                || ["#", "track", "browser"]
+                    .iter()
+                    .any(|prefix| line.starts_with(prefix))
+            {
+                continue;
+            }
+
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid BED line '{line}'"),
+                )
+            };
+
+            let mut fields = line.split('\t');
+            let contig = fields.next().ok_or_else(invalid)?;
+            let start = fields
+                .next()
+                .and_then(|s| s.parse::<usize>().ok())
+                .ok_or_else(invalid)?;
+            let end = fields
+                .next()
+                .and_then(|s| s.parse::<usize>().ok())
+                .ok_or_else(invalid)?;
+
+            if start < end {
+                regions.push(Self::new(contig, start + 1, Some(end)));
+            }
+        }
+
+        Ok(regions)
+    }
+
+    /// Creates a new region.
+    ///
+    /// The start position is 1-based, and both start and end are inclusive. If no end is
+    /// provided, the region extends to the end of the contig.
+    pub fn new<S>(contig: S, start: usize, end: Option<usize>) -> Self
+    where
+        S: ToString,
+    {
+        Self {
+            contig: contig.to_string(),
+            start: start.max(1),
+            end,
+        }
+    }
+
+    /// Returns the start position of the region.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.end {
+            Some(end) => write!(f, "{}:{}-{end}", self.contig, self.start),
+            None if self.start > 1 => write!(f, "{}:{}-", self.contig, self.start),
+            None => f.write_str(&self.contig),
+        }
+    }
+}
+
+impl FromStr for Region {
+    type Err = ParseRegionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseRegionError(s.to_string());
+
+        // Contig names may in principle contain ':', so only split if the suffix looks like an
+        // interval
+        let Some((contig, interval)) = s
+            .rsplit_once(':')
+            .filter(|(_, interval)| interval.starts_with(|c: char| c.is_ascii_digit()))
+        else {
+            return if s.is_empty() {
+                Err(invalid())
+            } else {
+                Ok(Self::new(s, 1, None))
+            };
+        };
+
+        let parse = |s: &str| s.replace(',', "").parse::<usize>().map_err(|_| invalid());
+
+        let (start, end) = match interval.split_once('-') {
+            Some((start, "")) => (parse(start)?, None),
+            Some((start, end)) => (parse(start)?, Some(parse(end)?)),
+            None => {
+                let position = parse(interval)?;
+                (position, Some(position))
+            }
+        };
+
+        if contig.is_empty() || start == 0 || end.is_some_and(|end| end < start) {
+            Err(invalid())
+        } else {
+            Ok(Self::new(contig, start, end))
+        }
+    }
+}
+
+/// An error associated with parsing a region.
+#[derive(Debug, Eq, PartialEq)]
+pub struct ParseRegionError(String);
+
+impl fmt::Display for ParseRegionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid region '{}', expected format 'contig[:start[-[end]]]'",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseRegionError {}
+
+/// A set of bounded genomic regions, supporting fast lookup of positions.
+///
+/// Overlapping and adjacent regions are merged, so that each base is only included once.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RegionSet {
+    contigs: HashMap<String, Vec<(usize, usize)>>,
+}
+
+impl RegionSet {
+    /// Returns the total number of bases in the set.
+    pub fn bases(&self) -> usize {
+        self.contigs
+            .values()
+            .flatten()
+            .map(|(start, end)| end - start + 1)
+            .sum()
+    }
+
+    /// Returns the intersection of the set with the provided regions.
+    pub fn intersect(&self, regions: &[Region]) -> Self {
+        let mut intersection = Vec::new();
+
+        for region in regions {
+            let Some(intervals) = self.contigs.get(&region.contig) else {
+                continue;
+            };
+            let region_end = region.end.unwrap_or(usize::MAX);
+
+            for &(start, end) in intervals {
+                let (start, end) = (start.max(region.start), end.min(region_end));
+
+                if start <= end {
+                    intersection.push(Region::new(region.contig.clone(), start, Some(end)));
+                }
+            }
+        }
+
+        Self::from_bounded(intersection)
+    }
+
+    /// Returns true if the position on the contig is contained in the set, false otherwise.
+    pub fn contains(&self, contig: &str, position: usize) -> bool {
+        let Some(intervals) = self.contigs.get(contig) else {
+            return false;
+        };
+
+        // Index of the first interval with start after position
+        let i = intervals.partition_point(|&(start, _)| start <= position);
+
+        i > 0 && position <= intervals[i - 1].1
+    }
+
+    /// Reads a set of regions from a BED file.
+    ///
+    /// See [`Region::from_bed_path`] for details.
+    pub fn from_bed_path<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Region::from_bed_path(path).map(Self::from_bounded)
+    }
+
+    /// Creates a new set from bounded regions.
+    ///
+    /// # Panics
+    ///
+    /// If any region is unbounded.
+    fn from_bounded(regions: Vec<Region>) -> Self {
+        let mut contigs: HashMap<String, Vec<(usize, usize)>> = HashMap::new();
+
+        for region in regions {
+            let end = region.end.expect("BED regions are bounded");
+            contigs
+                .entry(region.contig)
+                .or_default()
+                .push((region.start, end));
+        }
+
+        for intervals in contigs.values_mut() {
+            intervals.sort_unstable();
+
+            let mut merged: Vec<(usize, usize)> = Vec::with_capacity(intervals.len());
+            for &(start, end) in intervals.iter() {
+                match merged.last_mut() {
+                    Some((_, last_end)) if start <= *last_end + 1 => {
+                        *last_end = (*last_end).max(end)
+                    }
+                    _ => merged.push((start, end)),
+                }
+            }
+
+            *intervals = merged;
+        }
+
+        Self { contigs }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_region() {
+        assert_eq!("chr1".parse(), Ok(Region::new("chr1", 1, None)));
+        assert_eq!("chr1:10".parse(), Ok(Region::new("chr1", 10, Some(10))));
+        assert_eq!("chr1:10-".parse(), Ok(Region::new("chr1", 10, None)));
+        assert_eq!(
+            "chr1:1,000-2,000".parse(),
+            Ok(Region::new("chr1", 1000, Some(2000)))
+        );
+        assert_eq!(
+            "chrUn:KI270302".parse(),
+            Ok(Region::new("chrUn:KI270302", 1, None))
+        );
+        assert_eq!(
+            "HLA-A*01:01:5-7".parse(),
+            Ok(Region::new("HLA-A*01:01", 5, Some(7)))
+        );
+    }
+
+    #[test]
+    fn test_parse_region_invalid() {
+        assert!("".parse::<Region>().is_err());
+        assert!(":1-2".parse::<Region>().is_err());
+        assert!("chr1:0-2".parse::<Region>().is_err());
+        assert!("chr1:5-2".parse::<Region>().is_err());
+        assert!("chr1:1-x".parse::<Region>().is_err());
+    }
+
+    #[test]
+    fn test_region_display_roundtrip() {
+        for s in ["chr1", "chr1:10-", "chr1:10-20"] {
+            assert_eq!(s.parse::<Region>().unwrap().to_string(), s);
+        }
+    }
+
+    #[test]
+    fn test_region_from_bed_reader() {
+        let src = b"# comment\ntrack name=x\nchr1\t0\t10\tname\nchr2\t99\t100\nchr3\t5\t5\n";
+
+        assert_eq!(
+            Region::from_bed_reader(&src[..]).unwrap(),
+            vec![
+                Region::new("chr1", 1, Some(10)),
+                Region::new("chr2", 100, Some(100)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_region_set() {
+        let set = RegionSet::from_bounded(vec![
+            Region::new("chr1", 11, Some(20)),
+            Region::new("chr1", 1, Some(5)),
+            Region::new("chr1", 15, Some(25)),
+            Region::new("chr1", 26, Some(30)),
+            Region::new("chr2", 1, Some(1)),
+        ]);
+
+        assert_eq!(set.bases(), 5 + 20 + 1);
+
+        assert!(set.contains("chr1", 1));
+        assert!(set.contains("chr1", 5));
+        assert!(!set.contains("chr1", 6));
+        assert!(!set.contains("chr1", 10));
+        assert!(set.contains("chr1", 11));
+        assert!(set.contains("chr1", 30));
+        assert!(!set.contains("chr1", 31));
+        assert!(set.contains("chr2", 1));
+        assert!(!set.contains("chr2", 2));
+        assert!(!set.contains("chr3", 1));
+    }
+
+    #[test]
+    fn test_region_set_intersect() {
+        let set = RegionSet::from_bounded(vec![
+            Region::new("chr1", 1, Some(10)),
+            Region::new("chr1", 21, Some(30)),
+            Region::new("chr2", 1, Some(10)),
+        ]);
+
+        let intersection = set.intersect(&[
+            Region::new("chr1", 5, Some(25)),
+            Region::new("chr1", 8, Some(8)),
+            Region::new("chr2", 10, None),
+            Region::new("chr3", 1, None),
+        ]);
+
+        assert_eq!(
+            intersection,
+            RegionSet::from_bounded(vec![
+                Region::new("chr1", 5, Some(10)),
+                Region::new("chr1", 21, Some(25)),
+                Region::new("chr2", 10, Some(10)),
+            ])
+        );
+        assert_eq!(intersection.bases(), 6 + 5 + 1);
+    }
+
+    #[test]
+    fn test_region_from_bed_reader_invalid() {
+        assert!(Region::from_bed_reader(&b"chr1\t0\n"[..]).is_err());
+    }
+}
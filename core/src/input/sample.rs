@@ -1,8 +1,14 @@
 //! Input samples.
 
-use std::{collections::HashMap, fs::File, io, path::Path};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufRead},
+    path::Path,
+};
 
 use indexmap::IndexMap;
+use regex::Regex;
 
 use crate::array::Shape;
 
@@ -32,9 +38,69 @@ impl AsRef<str> for Sample {
     }
 }
 
+/// The default ploidy of samples.
+pub const DEFAULT_PLOIDY: usize = 2;
+
+/// The prefix of the optional samples file header line giving the population order.
+pub const POPULATIONS_HEADER: &str = "#populations=";
+
+/// The format of a samples file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    /// Sample names with optional, tab-separated populations.
+    Tsv,
+    /// Sample names with optional, comma-separated populations.
+    Csv,
+    /// PLINK `.fam` file, using the family ID as population and the individual ID as sample.
+    Fam,
+}
+
+impl Format {
+    /// Returns the format inferred from the extension of the provided path.
+    ///
+    /// Paths with a `.csv` extension are CSV, paths with a `.fam` extension are PLINK `.fam`,
+    /// and all other paths are taken to be tab-separated.
+    pub fn from_path<P>(path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => Self::Csv,
+            Some(ext) if ext.eq_ignore_ascii_case("fam") => Self::Fam,
+            _ => Self::Tsv,
+        }
+    }
+
+    fn parse_line<'a>(&self, line: &'a str) -> Option<(&'a str, Option<&'a str>)> {
+        match self {
+            Format::Tsv => Some(match line.split_once('\t') {
+                Some((sample, population)) => (sample, Some(population)),
+                None => (line, None),
+            }),
+            Format::Csv => Some(match line.split_once(',') {
+                Some((sample, population)) => (sample.trim(), Some(population.trim())),
+                None => (line.trim(), None),
+            }),
+            Format::Fam => {
+                let mut fields = line.split_whitespace();
+                let family = fields.next()?;
+                let individual = fields.next()?;
+                Some((individual, Some(family)))
+            }
+        }
+    }
+}
+
 /// A mapping from samples to populations.
+///
+/// The mapping also keeps track of the ploidy of each sample, which defaults to
+/// [`DEFAULT_PLOIDY`].
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
-pub struct Map(IndexMap<Sample, population::Id>);
+pub struct Map {
+    populations: IndexMap<Sample, population::Id>,
+    population_names: population::Map,
+    ploidies: Vec<usize>,
+}
 
 impl Map {
     /// Creates a new mapping by mapping all samples to the same, unnamed population.
@@ -50,80 +116,321 @@ impl Map {
     }
 
     /// Creates a new mapping by reading a samples file at the provided path.
+    ///
+    /// The format of the file is inferred from the path, see [`Format::from_path`].
     pub fn from_path<P>(path: P) -> io::Result<Self>
     where
         P: AsRef<Path>,
     {
-        File::open(path).and_then(Self::from_reader)
+        let format = Format::from_path(&path);
+
+        File::open(path).and_then(|f| Self::from_reader_with_format(f, format))
     }
 
-    /// Creates a new mapping by reading a samples file from the provided reader.
-    pub fn from_reader<R>(mut reader: R) -> io::Result<Self>
+    /// Creates a new mapping from the samples matching the provided pattern.
+    ///
+    /// The population of each sample is given by the first capture group of the pattern, or by
+    /// the whole match if the pattern has no capture groups. For example, the pattern `^[^_]+`
+    /// maps the sample `pop1_ind3` to the population `pop1`. Samples not matching the pattern
+    /// are left out of the mapping.
+    pub fn from_pattern<I>(samples: I, pattern: &Regex) -> Self
+    where
+        I: IntoIterator<Item = Sample>,
+    {
+        Self::from_iter(samples.into_iter().filter_map(|sample| {
+            let population = pattern
+                .captures(sample.as_ref())
+                .and_then(|captures| captures.get(1).or_else(|| captures.get(0)))
+                .map(|m| Population::from(Some(m.as_str())))?;
+
+            Some((sample, population))
+        }))
+    }
+
+    /// Creates a new mapping by reading a tab-separated samples file from the provided reader.
+    ///
+    /// See [`Map::from_reader_with_format`] for details.
+    pub fn from_reader<R>(reader: R) -> io::Result<Self>
+    where
+        R: io::Read,
+    {
+        Self::from_reader_with_format(reader, Format::Tsv)
+    }
+
+    /// Creates a new mapping by reading a samples file in the provided format from the provided
+    /// reader.
+    ///
+    /// Each line of the file should contain a sample and, optionally, its population. By
+    /// default, populations are ordered by first appearance. Alternatively, a header line such
+    /// as `#populations=pop2,pop1` may be used to give the population order explicitly. Empty
+    /// lines and other lines starting with `#` are ignored.
+    pub fn from_reader_with_format<R>(mut reader: R, format: Format) -> io::Result<Self>
     where
         R: io::Read,
     {
         let mut s = String::new();
         let _ = reader.read_to_string(&mut s)?;
 
-        Ok(Self::from_str(&s))
+        Self::from_str(&s, format)
     }
 
-    fn from_str(s: &str) -> Self {
-        s.lines()
-            .map(|line| match line.split_once('\t') {
-                Some((sample, population)) => (sample, Some(population)),
-                None => (line, None),
-            })
-            .collect()
+    fn from_str(s: &str, format: Format) -> io::Result<Self> {
+        let mut order = None;
+        let mut entries = Vec::new();
+
+        for line in s.lines() {
+            if let Some(populations) = line.strip_prefix(POPULATIONS_HEADER) {
+                order = Some(
+                    populations
+                        .split(',')
+                        .map(|population| Population::from(Some(population.trim())))
+                        .collect::<Vec<_>>(),
+                );
+            } else if !(line.trim().is_empty() || line.starts_with('#')) {
+                entries.push(format.parse_line(line).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid samples file line '{line}'"),
+                    )
+                })?);
+            }
+        }
+
+        let mut map = Self::from_iter(entries);
+
+        if let Some(order) = order {
+            map.set_population_order(&order)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        }
+
+        Ok(map)
+    }
+
+    /// Returns the ploidy of the sample with the provided id if defined, otherwise `None`.
+    pub fn get_ploidy(&self, id: Id) -> Option<usize> {
+        self.ploidies.get(id.0).copied()
+    }
+
+    /// Returns the population with the provided id if defined, otherwise `None`.
+    pub fn get_population(&self, id: population::Id) -> Option<&Population> {
+        self.population_names.get_name(id)
     }
 
     /// Returns the population id of a sample if defined, otherwise `None`.
     pub fn get_population_id(&self, sample: &Sample) -> Option<population::Id> {
-        self.0.get(sample).copied()
+        self.populations.get(sample).copied()
     }
 
     /// Returns the sample with the provided id if defined, otherwise `None`.
     pub fn get_sample(&self, id: Id) -> Option<&Sample> {
-        self.0.get_index(id.0).map(|opt| opt.0)
+        self.populations.get_index(id.0).map(|opt| opt.0)
     }
 
     /// Returns the id of the provided sample if defined, otherwise `None`.
     pub fn get_sample_id(&self, sample: &Sample) -> Option<Id> {
-        self.0.get_index_of(sample).map(Id)
+        self.populations.get_index_of(sample).map(Id)
     }
 
     /// Returns true if no samples are defined, false otherwise.
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.populations.is_empty()
     }
 
     /// Returns the number of populations in the mapping.
     pub fn number_of_populations(&self) -> usize {
-        self.population_sizes().len()
+        self.population_names.len()
+    }
+
+    /// Returns the provided values ordered by the populations they are associated with.
+    ///
+    /// This allows addressing populations by name, for instance when the value for each
+    /// population is provided by the user.
+    ///
+    /// # Errors
+    ///
+    /// Unless each population in the mapping is provided exactly once.
+    pub fn order_by_population<I, T>(&self, values: I) -> Result<Vec<T>, population::Error>
+    where
+        I: IntoIterator<Item = (Population, T)>,
+    {
+        let mut ordered = Vec::from_iter((0..self.number_of_populations()).map(|_| None));
+
+        for (population, value) in values {
+            let Some(id) = self.population_names.get(&population) else {
+                return Err(population::Error::Unknown { population });
+            };
+
+            if ordered[id.0].replace(value).is_some() {
+                return Err(population::Error::Duplicate { population });
+            }
+        }
+
+        ordered
+            .into_iter()
+            .zip(self.populations())
+            .map(|(value, population)| {
+                value.ok_or_else(|| population::Error::Missing {
+                    population: population.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the ploidy shared by the samples in each population, in order.
+    ///
+    /// The ploidy of a population is `None` where its samples have different ploidies.
+    pub fn population_ploidies(&self) -> Vec<Option<usize>> {
+        let mut ploidies = vec![None; self.number_of_populations()];
+
+        for (&population_id, &ploidy) in self.populations.values().zip(self.ploidies.iter()) {
+            let shared = &mut ploidies[usize::from(population_id)];
+
+            *shared = match *shared {
+                None => Some(Some(ploidy)),
+                Some(Some(current)) if current == ploidy => Some(Some(ploidy)),
+                Some(_) => Some(None),
+            };
+        }
+
+        ploidies.into_iter().map(Option::flatten).collect()
     }
 
     /// Returns the number of samples defined for each population id.
     pub fn population_sizes(&self) -> HashMap<population::Id, usize> {
         let mut sizes = HashMap::new();
-        for &population_id in self.0.values() {
+        for &population_id in self.populations.values() {
             *sizes.entry(population_id).or_insert(0) += 1;
         }
         sizes
     }
 
+    /// Returns an iterator over the populations in the mapping, in order.
+    pub fn populations(&self) -> impl Iterator<Item = &Population> {
+        self.population_names.iter()
+    }
+
+    /// Removes the provided samples from the mapping.
+    ///
+    /// Populations left without samples are removed, while the order of the remaining
+    /// populations is kept. Samples not in the mapping are ignored.
+    pub fn remove_samples(&mut self, samples: &[Sample]) {
+        let (entries, ploidies): (Vec<_>, Vec<_>) = self
+            .populations
+            .iter()
+            .zip(self.ploidies.iter())
+            .filter(|((sample, _), _)| !samples.contains(sample))
+            .map(|((sample, &id), &ploidy)| {
+                let population = self.population_names.get_name(id).unwrap().clone();
+                ((sample.clone(), population), ploidy)
+            })
+            .unzip();
+
+        let mut map = Self::from_iter(entries);
+        map.ploidies = ploidies;
+
+        let order = Vec::from_iter(
+            self.populations()
+                .filter(|population| map.population_names.get(population).is_some())
+                .cloned(),
+        );
+        map.set_population_order(&order)
+            .expect("order contains remaining populations");
+
+        *self = map;
+    }
+
     /// Returns an iterator over the samples in the mapping.
     pub fn samples(&self) -> impl Iterator<Item = &Sample> {
-        self.0.keys()
+        self.populations.keys()
+    }
+
+    /// Sets the ploidy of all samples in the mapping.
+    pub fn set_ploidy(&mut self, ploidy: usize) {
+        self.ploidies.iter_mut().for_each(|x| *x = ploidy);
+    }
+
+    /// Sets the order of the populations in the mapping, and so the order of the dimensions of
+    /// its shape.
+    ///
+    /// # Errors
+    ///
+    /// Unless each population in the mapping is provided exactly once.
+    pub fn set_population_order(&mut self, order: &[Population]) -> Result<(), population::Error> {
+        let new_ids =
+            self.order_by_population(order.iter().cloned().enumerate().map(|(i, p)| (p, i)))?;
+
+        self.populations
+            .values_mut()
+            .for_each(|id| *id = population::Id(new_ids[id.0]));
+
+        let mut population_names = population::Map::default();
+        for population in order {
+            population_names.insert(population.clone());
+        }
+        self.population_names = population_names;
+
+        Ok(())
+    }
+
+    /// Sets the ploidy of individual samples by reading a ploidy file at the provided path.
+    ///
+    /// See [`Map::set_sample_ploidies_from_reader`] for details.
+    pub fn set_sample_ploidies_from_path<P>(&mut self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        File::open(path).and_then(|f| self.set_sample_ploidies_from_reader(io::BufReader::new(f)))
+    }
+
+    /// Sets the ploidy of individual samples by reading a ploidy file from the provided reader.
+    ///
+    /// Each line of the ploidy file should contain the name of a sample and its ploidy,
+    /// separated by a tab. Samples not in the mapping are ignored, and samples not in the ploidy
+    /// file keep their current ploidy.
+    pub fn set_sample_ploidies_from_reader<R>(&mut self, reader: R) -> io::Result<()>
+    where
+        R: BufRead,
+    {
+        for line in reader.lines() {
+            let line = line?;
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let (sample, ploidy) = line
+                .split_once('\t')
+                .and_then(|(sample, ploidy)| {
+                    ploidy
+                        .trim()
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|&ploidy| ploidy > 0)
+                        .map(|ploidy| (Sample::from(sample), ploidy))
+                })
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid ploidy file line '{line}'"),
+                    )
+                })?;
+
+            if let Some(id) = self.get_sample_id(&sample) {
+                self.ploidies[id.0] = ploidy;
+            }
+        }
+
+        Ok(())
     }
 
     pub(crate) fn shape(&self) -> Shape {
-        let population_sizes = self.population_sizes();
+        let mut shape = vec![1; self.number_of_populations()];
 
-        Shape(
-            (0..population_sizes.len())
-                .map(|id| 1 + 2 * population_sizes.get(&population::Id(id)).unwrap())
-                .collect(),
-        )
+        for (&population_id, &ploidy) in self.populations.values().zip(self.ploidies.iter()) {
+            shape[usize::from(population_id)] += ploidy;
+        }
+
+        Shape(shape)
     }
 }
 
@@ -136,15 +443,169 @@ where
     where
         I: IntoIterator<Item = (S, P)>,
     {
-        let mut population_map = population::Map::default();
+        let mut population_names = population::Map::default();
 
-        Self(IndexMap::from_iter(iter.into_iter().map(
-            |(sample_name, population_name)| {
+        let populations =
+            IndexMap::from_iter(iter.into_iter().map(|(sample_name, population_name)| {
                 (
                     sample_name.into(),
-                    population_map.get_or_insert(population_name.into()),
+                    population_names.get_or_insert(population_name.into()),
                 )
-            },
-        )))
+            }));
+        let ploidies = vec![DEFAULT_PLOIDY; populations.len()];
+
+        Self {
+            populations,
+            population_names,
+            ploidies,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shape() {
+        let map = Map::from_iter([("a", Some("x")), ("b", Some("y")), ("c", Some("x"))]);
+
+        assert_eq!(map.shape(), Shape(vec![5, 3]));
+    }
+
+    #[test]
+    fn test_shape_with_ploidies() -> io::Result<()> {
+        let mut map = Map::from_iter([("a", Some("x")), ("b", Some("y")), ("c", Some("x"))]);
+        map.set_ploidy(4);
+        map.set_sample_ploidies_from_reader(&b"a\t1\nd\t3\n"[..])?;
+
+        assert_eq!(map.get_ploidy(Id(0)), Some(1));
+        assert_eq!(map.shape(), Shape(vec![6, 5]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_population_ploidies() -> io::Result<()> {
+        let mut map = Map::from_iter([("a", Some("x")), ("b", Some("y")), ("c", Some("x"))]);
+        assert_eq!(map.population_ploidies(), vec![Some(2), Some(2)]);
+
+        map.set_sample_ploidies_from_reader(&b"a\t1\nb\t1\n"[..])?;
+        assert_eq!(map.population_ploidies(), vec![None, Some(1)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_sample_ploidies_invalid() {
+        let mut map = Map::from_all([Sample::from("a")]);
+
+        assert!(map.set_sample_ploidies_from_reader(&b"a\t0\n"[..]).is_err());
+        assert!(map.set_sample_ploidies_from_reader(&b"a 2\n"[..]).is_err());
+    }
+
+    #[test]
+    fn test_from_reader_with_comments_and_order() -> io::Result<()> {
+        let map = Map::from_reader(&b"#populations=y,x\n# comment\na\tx\n\nb\ty\nc\tx\n"[..])?;
+
+        assert_eq!(
+            map.populations().collect::<Vec<_>>(),
+            [&Population::from(Some("y")), &Population::from(Some("x"))]
+        );
+        assert_eq!(map.shape(), Shape(vec![3, 5]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_reader_with_invalid_order() {
+        assert!(Map::from_reader(&b"#populations=x\na\tx\nb\ty\n"[..]).is_err());
+        assert!(Map::from_reader(&b"#populations=x,y,z\na\tx\nb\ty\n"[..]).is_err());
+    }
+
+    #[test]
+    fn test_from_reader_with_format() -> io::Result<()> {
+        let expected = Map::from_iter([("a", Some("x")), ("b", Some("y")), ("c", Some("x"))]);
+
+        let csv = Map::from_reader_with_format(&b"a,x\nb, y\nc,x\n"[..], Format::Csv)?;
+        assert_eq!(csv, expected);
+
+        let fam = Map::from_reader_with_format(
+            &b"x a 0 0 1 -9\ny b 0 0 2 -9\nx c 0 0 1 -9\n"[..],
+            Format::Fam,
+        )?;
+        assert_eq!(fam, expected);
+
+        assert!(Map::from_reader_with_format(&b"x\n"[..], Format::Fam).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(Format::from_path("samples.csv"), Format::Csv);
+        assert_eq!(Format::from_path("data/plink.fam"), Format::Fam);
+        assert_eq!(Format::from_path("samples.txt"), Format::Tsv);
+        assert_eq!(Format::from_path("samples"), Format::Tsv);
+    }
+
+    #[test]
+    fn test_from_pattern() {
+        let samples = ["x_1", "y_1", "x_2", "z"].map(Sample::from);
+
+        let map = Map::from_pattern(samples.clone(), &Regex::new("^([^_]+)_").unwrap());
+        assert_eq!(
+            map,
+            Map::from_iter([("x_1", Some("x")), ("y_1", Some("y")), ("x_2", Some("x"))])
+        );
+
+        let map = Map::from_pattern(samples, &Regex::new("^[^_]+").unwrap());
+        assert_eq!(map.number_of_populations(), 3);
+    }
+
+    #[test]
+    fn test_remove_samples() -> Result<(), population::Error> {
+        let mut map = Map::from_iter([("a", Some("x")), ("b", Some("y")), ("c", Some("z"))]);
+        map.set_population_order(&["z", "y", "x"].map(|p| Population::from(Some(p))))?;
+        map.remove_samples(&[Sample::from("b"), Sample::from("d")]);
+
+        assert_eq!(
+            map.populations().collect::<Vec<_>>(),
+            [&Population::from(Some("z")), &Population::from(Some("x"))]
+        );
+        assert_eq!(
+            map.get_population_id(&Sample::from("c")),
+            Some(population::Id(0))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_order_by_population() {
+        let map = Map::from_iter([("a", Some("x")), ("b", Some("y"))]);
+        let named = |v: &[(&str, usize)]| {
+            map.order_by_population(v.iter().map(|&(p, i)| (Population::from(Some(p)), i)))
+        };
+
+        assert_eq!(named(&[("y", 1), ("x", 2)]), Ok(vec![2, 1]));
+        assert_eq!(
+            named(&[("x", 1)]),
+            Err(population::Error::Missing {
+                population: Population::from(Some("y"))
+            })
+        );
+        assert_eq!(
+            named(&[("x", 1), ("y", 1), ("x", 1)]),
+            Err(population::Error::Duplicate {
+                population: Population::from(Some("x"))
+            })
+        );
+        assert_eq!(
+            named(&[("x", 1), ("w", 1)]),
+            Err(population::Error::Unknown {
+                population: Population::from(Some("w"))
+            })
+        );
     }
 }
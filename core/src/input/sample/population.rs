@@ -52,6 +52,10 @@ impl Map {
         self.0.get_index_of(name).map(Id)
     }
 
+    pub fn get_name(&self, id: Id) -> Option<&Population> {
+        self.0.get_index(id.0)
+    }
+
     pub fn get_or_insert(&mut self, name: Population) -> Id {
         self.get(&name).unwrap_or_else(|| self.insert(name))
     }
@@ -59,4 +63,46 @@ impl Map {
     pub fn insert(&mut self, name: Population) -> Id {
         Id(self.0.insert_full(name).0)
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Population> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// An error associated with addressing populations by name.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// A population was provided more than once.
+    Duplicate {
+        /// The duplicated population.
+        population: Population,
+    },
+    /// A population in the mapping was not provided.
+    Missing {
+        /// The missing population.
+        population: Population,
+    },
+    /// A provided population is not in the mapping.
+    Unknown {
+        /// The unknown population.
+        population: Population,
+    },
 }
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Duplicate { population } => {
+                write!(f, "population '{population}' provided more than once")
+            }
+            Error::Missing { population } => write!(f, "population '{population}' not provided"),
+            Error::Unknown { population } => write!(f, "unknown population '{population}'"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -5,15 +5,63 @@ pub use reader::Reader;
 
 use crate::spectrum::{project::Projected, Count};
 
+use reader::filter::SiteFilter;
+
 /// An input site.
 ///
 /// This type results from reader genotypes from a [`Reader`] with its particular configuration.
-/// See there for details.
+/// See there for details. Counts are of derived alleles if polarizing, and otherwise of
+/// alternative alleles.
 pub enum Site<'a> {
-    /// A standard count with no projection.
+    /// A standard count with no projection, or a count downsampled to the projected shape.
     Standard(&'a Count),
     /// A projected count.
     Projected(Projected<'a>),
     /// A site with insufficient data.
     InsufficientData,
+    /// A site where the ancestral allele is unknown, or matches none of the alleles.
+    ///
+    /// Only used when polarizing.
+    UnknownAncestral,
+    /// A site failing the site filter.
+    ///
+    /// Only used when filtering sites.
+    Filtered(&'a SiteFilter),
+    /// A site outside the callable regions.
+    ///
+    /// Only used when callable regions are provided.
+    Uncallable,
+}
+
+/// An input site together with its location.
+#[derive(Debug)]
+pub struct Located<'a, T> {
+    /// The contig of the site.
+    pub contig: &'a str,
+    /// The 1-based position of the site within its contig.
+    pub position: usize,
+    /// The site.
+    pub site: T,
+}
+
+/// An input site of sample allele frequency likelihoods.
+///
+/// This type results from reading genotype likelihoods from a [`Reader`]. See there for details.
+pub enum SafSite<'a> {
+    /// SAF likelihoods, concatenated across populations.
+    Likelihoods(&'a [f64]),
+    /// A site with insufficient data.
+    InsufficientData,
+    /// A site where the ancestral allele is unknown, or matches none of the alleles.
+    ///
+    /// Only used when polarizing.
+    UnknownAncestral,
+    /// A site failing the site filter.
+    ///
+    /// Only used when filtering sites.
+    Filtered(&'a SiteFilter),
+    /// A site outside the callable regions.
+    ///
+    /// Only used when callable regions are provided.
+    Uncallable,
 }
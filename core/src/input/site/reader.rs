@@ -5,13 +5,38 @@ use std::io;
 pub mod builder;
 pub use builder::Builder;
 
+mod annotation;
+use annotation::Annotator;
+
+mod callable;
+use callable::Callable;
+
+mod fasta;
+
+pub mod filter;
+use filter::{GenotypeFilter, SiteFilter};
+
+pub mod mutation;
+use mutation::Classifier;
+
+mod polarize;
+use polarize::{Polarity, Polarizer};
+
 use crate::{
-    input::{genotype, sample, ReadStatus, Sample},
-    spectrum::{project::PartialProjection, Count},
+    array::Shape,
+    input::{
+        genotype::{self, likelihoods, Likelihoods},
+        sample, ReadStatus, Sample,
+    },
+    saf::{self, Saf},
+    spectrum::{
+        project::{Downsampling, PartialProjection},
+        Count,
+    },
     Scs,
 };
 
-use super::Site;
+use super::{Located, SafSite, Site};
 
 /// A site reader.
 pub struct Reader {
@@ -19,17 +44,42 @@ pub struct Reader {
     sample_map: sample::Map,
     counts: Count,
     totals: Count,
-    projection: Option<PartialProjection>,
+    projection: Option<Projection>,
     skipped_samples: Vec<(sample::Id, genotype::Skipped)>,
+    filtered_samples: Vec<(sample::Id, usize)>,
+    polarizer: Option<Polarizer>,
+    stratifier: Option<Stratifier>,
+    stratum: Option<usize>,
+    site_filters: Vec<SiteFilter>,
+    genotype_filters: Vec<GenotypeFilter>,
+    callable: Option<Callable>,
+    likelihoods: Vec<Vec<Likelihoods>>,
+    saf: Vec<f64>,
 }
 
 impl Reader {
+    /// Returns the number of bases in the callable regions without any record read so far.
+    ///
+    /// Once all records have been read, these bases can be taken to be monomorphic. Returns
+    /// `None` if no callable regions are used.
+    pub fn callable_bases_without_records(&self) -> Option<usize> {
+        self.callable.as_ref().map(Callable::uncovered)
+    }
+
+    /// Returns empty SAF likelihoods corresponding to the shape defined by the reader
+    /// configuration.
+    ///
+    /// Note that any projection is ignored for SAF likelihoods.
+    pub fn create_empty_saf(&self) -> Saf {
+        Saf::from_shape(self.sample_map.shape())
+    }
+
     /// Returns a spectrum filled with zeros corresponding to the shape defined by the reader
     /// configuration.
     pub fn create_zero_scs(&self) -> Scs {
         let shape = self
             .projection
-            .clone()
+            .as_ref()
             .map(|projection| projection.project_to().clone().into_shape())
             .unwrap_or_else(|| self.sample_map.shape());
 
@@ -41,6 +91,45 @@ impl Reader {
         self.reader.current_contig()
     }
 
+    /// Returns the number of derived alleles in each population at the current site.
+    ///
+    /// Counts are of derived alleles if polarizing, and otherwise of alternative alleles. Only
+    /// samples with genotypes are counted, see [`Reader::current_totals`]. The counts are only
+    /// meaningful after reading a site that was not filtered, uncallable, or unpolarized.
+    pub fn current_counts(&self) -> &Count {
+        &self.counts
+    }
+
+    /// Returns the total number of alleles in each population at the current site.
+    ///
+    /// Only samples with genotypes are counted, see [`Reader::current_counts`].
+    pub fn current_totals(&self) -> &Count {
+        &self.totals
+    }
+
+    /// Returns an iterator over the genotypes in the reader currently skipped due to genotype
+    /// filters, with their associated samples and the first filter they failed.
+    ///
+    /// These genotypes are also included in [`Reader::current_skipped_samples`].
+    pub fn current_filtered_samples(&self) -> impl Iterator<Item = (&Sample, &GenotypeFilter)> {
+        self.filtered_samples.iter().map(|(i, filter)| {
+            (
+                self.sample_map.get_sample(*i).unwrap(),
+                &self.genotype_filters[*filter],
+            )
+        })
+    }
+
+    /// Returns the stratum of the current site as an index into [`Reader::strata`], if defined.
+    ///
+    /// Only defined when stratifying, see [`Builder::set_stratify`], and after reading a site
+    /// that was not filtered, uncallable, or unpolarized, and that belongs to a stratum. For
+    /// mutation classes, the class is from the ancestral to the derived allele when polarizing,
+    /// and otherwise from the reference to the alternative allele.
+    pub fn current_stratum(&self) -> Option<usize> {
+        self.stratum
+    }
+
     /// Returns the current position of the reader within its current contig.
     pub fn current_position(&self) -> usize {
         self.reader.current_position()
@@ -54,10 +143,16 @@ impl Reader {
             .map(|(i, s)| (self.sample_map.get_sample(*i).unwrap(), s))
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn new_unchecked(
         reader: Box<dyn genotype::Reader>,
         sample_map: sample::Map,
-        projection: Option<PartialProjection>,
+        projection: Option<Projection>,
+        polarizer: Option<Polarizer>,
+        stratifier: Option<Stratifier>,
+        site_filters: Vec<SiteFilter>,
+        genotype_filters: Vec<GenotypeFilter>,
+        callable: Option<Callable>,
     ) -> Self {
         let dimensions = sample_map.number_of_populations();
 
@@ -68,11 +163,34 @@ impl Reader {
             counts: Count::from_zeros(dimensions),
             totals: Count::from_zeros(dimensions),
             skipped_samples: Vec::new(),
+            filtered_samples: Vec::new(),
+            polarizer,
+            stratifier,
+            stratum: None,
+            site_filters,
+            genotype_filters,
+            callable,
+            likelihoods: vec![Vec::new(); dimensions],
+            saf: Vec::new(),
         }
     }
 
     /// Reads the next site in the reader.
     pub fn read_site(&mut self) -> ReadStatus<Site<'_>> {
+        self.read_located_site().map(|located| located.site)
+    }
+
+    /// Reads the next site in the reader, together with its location.
+    ///
+    /// See [`Reader::read_site`] for details.
+    pub fn read_located_site(&mut self) -> ReadStatus<Located<'_, Site<'_>>> {
+        self.read_stratified_site().map(|(located, _)| located)
+    }
+
+    /// Reads the next site in the reader, together with its location and stratum.
+    ///
+    /// See [`Reader::read_site`] and [`Reader::current_stratum`] for details.
+    pub fn read_stratified_site(&mut self) -> ReadStatus<(Located<'_, Site<'_>>, Option<usize>)> {
         self.reset();
 
         let genotypes = match self.reader.read_genotypes() {
@@ -81,27 +199,107 @@ impl Reader {
             ReadStatus::Done => return ReadStatus::Done,
         };
 
-        for (sample, genotype) in self.reader.samples().iter().zip(genotypes) {
+        if !self.is_callable() {
+            return ReadStatus::Read((
+                Located {
+                    contig: self.reader.current_contig(),
+                    position: self.reader.current_position(),
+                    site: Site::Uncallable,
+                },
+                None,
+            ));
+        }
+
+        match self.failed_site_filter() {
+            Ok(Some(i)) => {
+                return ReadStatus::Read((
+                    Located {
+                        contig: self.reader.current_contig(),
+                        position: self.reader.current_position(),
+                        site: Site::Filtered(&self.site_filters[i]),
+                    },
+                    None,
+                ))
+            }
+            Ok(None) => (),
+            Err(e) => return ReadStatus::Error(e),
+        }
+
+        let format_values = match self.genotype_filter_values() {
+            Ok(values) => values,
+            Err(e) => return ReadStatus::Error(e),
+        };
+
+        for (i, (sample, genotype)) in self.reader.samples().iter().zip(genotypes).enumerate() {
             let Some(population_id) = self.sample_map.get_population_id(sample).map(usize::from)
             else {
                 continue;
             };
+            let sample_id = self.sample_map.get_sample_id(sample).unwrap();
+            let ploidy = self.sample_map.get_ploidy(sample_id).unwrap();
+
+            let genotype = match genotype {
+                genotype::Result::Genotype(_) => {
+                    match failed_genotype_filter(&self.genotype_filters, &format_values, i) {
+                        Some(filter) => {
+                            self.filtered_samples.push((sample_id, filter));
+                            genotype::Result::Skipped(genotype::Skipped::Filtered)
+                        }
+                        None => genotype,
+                    }
+                }
+                genotype => genotype,
+            };
 
             match genotype {
+                genotype::Result::Genotype(genotype) if genotype.ploidy() == ploidy => {
+                    self.counts[population_id] += genotype.alleles();
+                    self.totals[population_id] += ploidy;
+                }
                 genotype::Result::Genotype(genotype) => {
-                    self.counts[population_id] += genotype as u8 as usize;
-                    self.totals[population_id] += 2;
+                    let e = genotype::Error::PloidyError {
+                        expected: ploidy,
+                        actual: genotype.ploidy(),
+                    };
+                    return ReadStatus::Error(sample_error(sample, e));
                 }
                 genotype::Result::Skipped(skip) => {
-                    self.skipped_samples
-                        .push((self.sample_map.get_sample_id(sample).unwrap(), skip));
+                    self.skipped_samples.push((sample_id, skip));
                 }
                 genotype::Result::Error(e) => {
-                    return ReadStatus::Error(io::Error::new(io::ErrorKind::InvalidData, e));
+                    return ReadStatus::Error(sample_error(sample, e));
                 }
             }
         }
 
+        let polarity = match self.polarity() {
+            Ok(Polarity::Unknown) => {
+                return ReadStatus::Read((
+                    Located {
+                        contig: self.reader.current_contig(),
+                        position: self.reader.current_position(),
+                        site: Site::UnknownAncestral,
+                    },
+                    None,
+                ))
+            }
+            Ok(polarity) => polarity,
+            Err(e) => return ReadStatus::Error(e),
+        };
+
+        if polarity == Polarity::Alternative {
+            for i in 0..self.counts.dimensions() {
+                self.counts[i] = self.totals[i] - self.counts[i];
+            }
+        }
+
+        if let Some(stratifier) = self.stratifier.as_mut() {
+            match stratifier.stratify(self.reader.as_ref(), polarity) {
+                Ok(stratum) => self.stratum = stratum,
+                Err(e) => return ReadStatus::Error(e),
+            }
+        }
+
         let site = if let Some(projection) = self.projection.as_mut() {
             let (exact, projectable) = self.totals.iter().zip(projection.project_to().iter()).fold(
                 (true, true),
@@ -110,12 +308,15 @@ impl Reader {
                 },
             );
 
-            if exact {
-                Site::Standard(&self.counts)
-            } else if projectable {
-                Site::Projected(projection.project_unchecked(&self.totals, &self.counts))
-            } else {
-                Site::InsufficientData
+            match projection {
+                _ if exact => Site::Standard(&self.counts),
+                Projection::Partial(projection) if projectable => {
+                    Site::Projected(projection.project_unchecked(&self.totals, &self.counts))
+                }
+                Projection::Downsample(downsampling) if projectable => {
+                    Site::Standard(downsampling.downsample_unchecked(&self.totals, &self.counts))
+                }
+                _ => Site::InsufficientData,
             }
         } else if self.skipped_samples.is_empty() {
             Site::Standard(&self.counts)
@@ -123,17 +324,273 @@ impl Reader {
             Site::InsufficientData
         };
 
-        ReadStatus::Read(site)
+        ReadStatus::Read((
+            Located {
+                contig: self.reader.current_contig(),
+                position: self.reader.current_position(),
+                site,
+            },
+            self.stratum,
+        ))
+    }
+
+    /// Reads the SAF likelihoods of the next site in the reader.
+    ///
+    /// Samples with missing genotype likelihoods are treated as uninformative, while the site is
+    /// considered to have insufficient data if any sample is multiallelic. Note that any
+    /// projection is ignored for SAF likelihoods.
+    pub fn read_saf_site(&mut self) -> ReadStatus<SafSite<'_>> {
+        self.read_located_saf_site().map(|located| located.site)
+    }
+
+    /// Reads the SAF likelihoods of the next site in the reader, together with its location.
+    ///
+    /// See [`Reader::read_saf_site`] for details.
+    pub fn read_located_saf_site(&mut self) -> ReadStatus<Located<'_, SafSite<'_>>> {
+        self.reset();
+
+        let likelihoods = match self.reader.read_likelihoods() {
+            ReadStatus::Read(likelihoods) => likelihoods,
+            ReadStatus::Error(e) => return ReadStatus::Error(e),
+            ReadStatus::Done => return ReadStatus::Done,
+        };
+
+        if !self.is_callable() {
+            return ReadStatus::Read(Located {
+                contig: self.reader.current_contig(),
+                position: self.reader.current_position(),
+                site: SafSite::Uncallable,
+            });
+        }
+
+        match self.failed_site_filter() {
+            Ok(Some(i)) => {
+                return ReadStatus::Read(Located {
+                    contig: self.reader.current_contig(),
+                    position: self.reader.current_position(),
+                    site: SafSite::Filtered(&self.site_filters[i]),
+                })
+            }
+            Ok(None) => (),
+            Err(e) => return ReadStatus::Error(e),
+        }
+
+        let format_values = match self.genotype_filter_values() {
+            Ok(values) => values,
+            Err(e) => return ReadStatus::Error(e),
+        };
+
+        for (i, (sample, likelihoods)) in self.reader.samples().iter().zip(likelihoods).enumerate()
+        {
+            let Some(population_id) = self.sample_map.get_population_id(sample).map(usize::from)
+            else {
+                continue;
+            };
+
+            let sample_id = self.sample_map.get_sample_id(sample).unwrap();
+            let ploidy = self.sample_map.get_ploidy(sample_id).unwrap();
+
+            let likelihoods = match likelihoods.check_ploidy(ploidy) {
+                likelihoods::Result::Likelihoods(likelihoods) => {
+                    match failed_genotype_filter(&self.genotype_filters, &format_values, i) {
+                        Some(filter) => {
+                            self.filtered_samples.push((sample_id, filter));
+                            likelihoods::Result::Skipped(genotype::Skipped::Filtered)
+                        }
+                        None => likelihoods::Result::Likelihoods(likelihoods),
+                    }
+                }
+                likelihoods => likelihoods,
+            };
+
+            match likelihoods {
+                likelihoods::Result::Likelihoods(likelihoods) => {
+                    self.likelihoods[population_id].push(likelihoods);
+                }
+                likelihoods::Result::Skipped(skip) => {
+                    self.likelihoods[population_id].push(Likelihoods::new(vec![0.0; ploidy + 1]));
+                    self.skipped_samples.push((sample_id, skip));
+                }
+                likelihoods::Result::Error(e) => {
+                    return ReadStatus::Error(sample_error(sample, e));
+                }
+            }
+        }
+
+        let polarity = match self.polarity() {
+            Ok(Polarity::Unknown) => {
+                return ReadStatus::Read(Located {
+                    contig: self.reader.current_contig(),
+                    position: self.reader.current_position(),
+                    site: SafSite::UnknownAncestral,
+                })
+            }
+            Ok(polarity) => polarity,
+            Err(e) => return ReadStatus::Error(e),
+        };
+
+        let site = if self
+            .skipped_samples
+            .iter()
+            .any(|(_, skip)| skip == &genotype::Skipped::Multiallelic)
+        {
+            SafSite::InsufficientData
+        } else {
+            for population_likelihoods in self.likelihoods.iter() {
+                let mut population_saf = saf::from_genotype_likelihoods(population_likelihoods);
+                if polarity == Polarity::Alternative {
+                    population_saf.reverse();
+                }
+
+                self.saf.extend(population_saf);
+            }
+
+            SafSite::Likelihoods(&self.saf)
+        };
+
+        ReadStatus::Read(Located {
+            contig: self.reader.current_contig(),
+            position: self.reader.current_position(),
+            site,
+        })
+    }
+
+    /// Returns the index of the first site filter failed by the current site, if any.
+    fn failed_site_filter(&self) -> io::Result<Option<usize>> {
+        for (i, filter) in self.site_filters.iter().enumerate() {
+            if !filter.passes(self.reader.as_ref())? {
+                return Ok(Some(i));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the values of the FORMAT field of each genotype filter for the current site.
+    fn genotype_filter_values(&self) -> io::Result<Vec<Vec<Option<f64>>>> {
+        self.genotype_filters
+            .iter()
+            .map(|filter| self.reader.current_format_field(filter.0.key()))
+            .collect()
+    }
+
+    fn is_callable(&mut self) -> bool {
+        match self.callable.as_mut() {
+            Some(callable) => {
+                callable.check(self.reader.current_contig(), self.reader.current_position())
+            }
+            None => true,
+        }
+    }
+
+    /// Returns the populations defined by the reader, in the order of the spectrum dimensions.
+    pub fn populations(&self) -> impl Iterator<Item = &sample::Population> {
+        self.sample_map.populations()
+    }
+
+    fn polarity(&mut self) -> io::Result<Polarity> {
+        match self.polarizer.as_mut() {
+            Some(polarizer) => polarizer.polarity(self.reader.as_ref()),
+            None => Ok(Polarity::Reference),
+        }
     }
 
     fn reset(&mut self) {
+        self.stratum = None;
         self.counts.set_zero();
         self.totals.set_zero();
         self.skipped_samples.clear();
+        self.filtered_samples.clear();
+        self.likelihoods.iter_mut().for_each(Vec::clear);
+        self.saf.clear();
     }
 
     /// Returns the samples defined by the reader.
     pub fn samples(&self) -> &[Sample] {
         self.reader.samples()
     }
+
+    /// Returns the labels of all strata in order, if stratifying.
+    pub fn strata(&self) -> Option<Vec<String>> {
+        self.stratifier.as_ref().map(Stratifier::labels)
+    }
+
+    /// Returns the shape defined by the samples and their ploidy, ignoring any projection.
+    pub fn unprojected_shape(&self) -> Shape {
+        self.sample_map.shape()
+    }
+}
+
+/// Returns the index of the first genotype filter failed by the sample with the provided index,
+/// if any.
+fn failed_genotype_filter(
+    filters: &[GenotypeFilter],
+    values: &[Vec<Option<f64>>],
+    sample: usize,
+) -> Option<usize> {
+    filters
+        .iter()
+        .zip(values)
+        .position(|(filter, values)| !filter.0.passes(values.get(sample).copied().flatten()))
+}
+
+fn sample_error(sample: &Sample, e: genotype::Error) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{e} for sample '{}'", sample.as_ref()),
+    )
+}
+
+/// A projection used for reading.
+#[derive(Debug)]
+pub(crate) enum Projection {
+    /// A projection using hypergeometric weights.
+    Partial(PartialProjection),
+    /// A random downsampling.
+    Downsample(Box<Downsampling>),
+}
+
+impl Projection {
+    fn project_to(&self) -> &Count {
+        match self {
+            Projection::Partial(projection) => projection.project_to(),
+            Projection::Downsample(downsampling) => downsampling.project_to(),
+        }
+    }
+}
+
+/// A stratification of sites used for reading.
+enum Stratifier {
+    /// Stratification by mutation class.
+    MutationClasses(Classifier),
+    /// Stratification by functional annotation category.
+    Annotation(Annotator),
+}
+
+impl Stratifier {
+    fn labels(&self) -> Vec<String> {
+        match self {
+            Stratifier::MutationClasses(classifier) => classifier
+                .classes()
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            Stratifier::Annotation(annotator) => annotator.categories().to_vec(),
+        }
+    }
+
+    fn stratify(
+        &mut self,
+        reader: &dyn genotype::Reader,
+        polarity: Polarity,
+    ) -> io::Result<Option<usize>> {
+        match self {
+            Stratifier::MutationClasses(classifier) => {
+                let class = classifier.classify(reader, polarity)?;
+
+                Ok(class.and_then(|class| classifier.classes().iter().position(|c| *c == class)))
+            }
+            Stratifier::Annotation(annotator) => annotator.annotate(reader),
+        }
+    }
 }
@@ -0,0 +1,93 @@
+//! Functional annotation categories.
+
+use std::io;
+
+use crate::input::genotype;
+
+/// The INFO fields containing functional annotations, in order of precedence.
+///
+/// These are the VEP `CSQ` field and the SnpEff `ANN` field, respectively.
+const KEYS: [&str; 2] = ["CSQ", "ANN"];
+
+/// The delimiter between annotations of different alleles or transcripts.
+const ANNOTATION_DELIMITER: char = ',';
+
+/// The delimiter between the fields of an annotation.
+const FIELD_DELIMITER: char = '|';
+
+/// The index of the consequence field within an annotation.
+///
+/// This is the second field in the default layout of both VEP and SnpEff annotations.
+const CONSEQUENCE_INDEX: usize = 1;
+
+/// The delimiter between multiple consequence terms within an annotation.
+const TERM_DELIMITER: char = '&';
+
+/// An annotator of sites into functional annotation categories.
+pub(super) struct Annotator {
+    categories: Vec<String>,
+}
+
+impl Annotator {
+    /// Returns the category of the current site in the reader, as an index into the categories.
+    ///
+    /// The category is the first category that matches any consequence term in any annotation
+    /// of the site, so that the order of categories gives their precedence. Returns `None` for
+    /// sites without annotations or without any matching consequence term.
+    pub fn annotate(&self, reader: &dyn genotype::Reader) -> io::Result<Option<usize>> {
+        for key in KEYS {
+            if let Some(value) = reader.current_info_field(key)? {
+                return Ok(self.category(&value));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the categories in order.
+    pub fn categories(&self) -> &[String] {
+        &self.categories
+    }
+
+    fn category(&self, value: &str) -> Option<usize> {
+        let terms = value
+            .split(ANNOTATION_DELIMITER)
+            .filter_map(|annotation| annotation.split(FIELD_DELIMITER).nth(CONSEQUENCE_INDEX))
+            .flat_map(|consequence| consequence.split(TERM_DELIMITER));
+
+        self.categories
+            .iter()
+            .position(|category| terms.clone().any(|term| term == category))
+    }
+
+    pub fn new(categories: Vec<String>) -> Self {
+        Self { categories }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_category() {
+        let annotator = Annotator::new(vec![
+            String::from("missense_variant"),
+            String::from("synonymous_variant"),
+        ]);
+
+        assert_eq!(
+            annotator.category("T|synonymous_variant|LOW|ABC1|ENSG01"),
+            Some(1)
+        );
+        assert_eq!(
+            annotator.category(
+                "T|synonymous_variant|LOW|ABC1|ENSG01,T|missense_variant&splice_region_variant|MODERATE"
+            ),
+            Some(0)
+        );
+        assert_eq!(annotator.category("T|intron_variant|MODIFIER"), None);
+        assert_eq!(annotator.category("missense_variant"), None);
+        assert_eq!(annotator.category(""), None);
+    }
+}
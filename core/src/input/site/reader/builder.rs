@@ -2,19 +2,38 @@
 
 use std::{collections::HashSet, fmt, io, path::PathBuf};
 
-use sample::Sample;
+use regex::Regex;
+use sample::{Population, Sample};
 
+use super::{
+    annotation::Annotator,
+    callable::Callable,
+    filter::{GenotypeFilter, SiteFilter},
+    mutation::Classifier,
+    polarize::Polarizer,
+    Projection, Stratifier,
+};
 use crate::{
     array::Shape,
-    input::{genotype, sample},
-    spectrum::project::{PartialProjection, ProjectionError},
+    input::{genotype, sample, Region, RegionSet},
+    spectrum::project::{Downsampling, PartialProjection, ProjectionError},
 };
 
 /// A site reader builder.
 #[derive(Debug, Default)]
 pub struct Builder {
     samples: Option<Option<Samples>>,
+    exclude_samples: Option<Vec<Sample>>,
+    population_order: Option<Option<Vec<Population>>>,
     project: Option<Option<Project>>,
+    polarize: Option<Option<Polarize>>,
+    stratify: Option<Option<Stratify>>,
+    ploidy: Option<usize>,
+    ploidy_path: Option<Option<PathBuf>>,
+    site_filters: Option<Vec<SiteFilter>>,
+    genotype_filters: Option<Vec<GenotypeFilter>>,
+    callable_path: Option<Option<PathBuf>>,
+    regions: Option<Option<Vec<Region>>>,
 }
 
 impl Builder {
@@ -24,20 +43,26 @@ impl Builder {
     ///
     /// For a variety of reasons, see [`Error`] for details.
     pub fn build(self, reader: genotype::reader::DynReader) -> Result<super::Reader, Error> {
-        let sample_map = match self.samples.unwrap_or(None) {
+        let mut sample_map = match self.samples.unwrap_or(None) {
             Some(Samples::List(list)) => sample::Map::from_iter(list),
             Some(Samples::Path(path)) => sample::Map::from_path(path)?,
+            Some(Samples::Pattern(pattern)) => {
+                let regex = Regex::new(&pattern).map_err(|e| Error::InvalidSamplePattern {
+                    pattern,
+                    reason: e.to_string(),
+                })?;
+
+                sample::Map::from_pattern(reader.samples().iter().cloned(), &regex)
+            }
             None => sample::Map::from_all(reader.samples().iter().cloned()),
         };
 
-        if sample_map.is_empty() {
-            return Err(Error::EmptySamplesMap);
-        }
-
-        // All samples in sample map should be in reader samples
+        // All samples in sample map and exclusions should be in reader samples
+        let exclude_samples = self.exclude_samples.unwrap_or_default();
         let reader_samples = HashSet::<_>::from_iter(reader.samples());
         if let Some(unknown_sample) = sample_map
             .samples()
+            .chain(exclude_samples.iter())
             .find(|sample| !reader_samples.contains(sample))
         {
             return Err(Error::UnknownSample {
@@ -45,36 +70,168 @@ impl Builder {
             });
         }
 
-        let projection = if let Some(project_to) = self.project.unwrap_or(None).map(Project::shape)
-        {
-            let project_from = sample_map.shape();
+        sample_map.remove_samples(&exclude_samples);
 
-            if project_from.dimensions() != project_to.dimensions() {
-                return Err(ProjectionError::UnequalDimensions {
-                    from: project_from.dimensions(),
-                    to: project_to.dimensions(),
-                }
-                .into());
-            } else if let Some((dimension, (&from, &to))) = project_from
-                .iter()
-                .zip(project_to.iter())
-                .enumerate()
-                .find(|(_, (from, to))| from < to)
-            {
-                return Err(ProjectionError::InvalidProjection {
-                    dimension,
-                    from,
-                    to,
+        if sample_map.is_empty() {
+            return Err(Error::EmptySamplesMap);
+        }
+
+        if let Some(order) = self.population_order.unwrap_or(None) {
+            sample_map.set_population_order(&order)?;
+        }
+
+        let ploidy = self.ploidy.unwrap_or(sample::DEFAULT_PLOIDY);
+        if ploidy == 0 {
+            return Err(Error::ZeroPloidy);
+        }
+        sample_map.set_ploidy(ploidy);
+
+        if let Some(path) = self.ploidy_path.unwrap_or(None) {
+            sample_map.set_sample_ploidies_from_path(path)?;
+        }
+
+        let projection = match self.project.unwrap_or(None) {
+            Some(Project::Downsample { project, seed }) => {
+                let project_to = project.shape(&sample_map)?;
+
+                Some(Projection::Downsample(Box::new(Downsampling::from_shapes(
+                    sample_map.shape(),
+                    project_to,
+                    seed,
+                )?)))
+            }
+            Some(project) => {
+                let project_to = project.shape(&sample_map)?;
+
+                Some(Projection::Partial(PartialProjection::from_shapes(
+                    sample_map.shape(),
+                    project_to,
+                )?))
+            }
+            None => None,
+        };
+
+        let polarizer = self
+            .polarize
+            .unwrap_or(None)
+            .map(Polarizer::new)
+            .transpose()?;
+
+        let stratifier = match self.stratify.unwrap_or(None) {
+            Some(Stratify::MutationClasses(classes)) => {
+                Some(Stratifier::MutationClasses(Classifier::new(classes)?))
+            }
+            Some(Stratify::Annotation(categories)) => {
+                let mut seen = HashSet::new();
+                if let Some(category) = categories.iter().find(|&c| !seen.insert(c)) {
+                    return Err(Error::DuplicateAnnotationCategory {
+                        category: category.clone(),
+                    });
                 }
-                .into());
-            } else {
-                Some(PartialProjection::from_shape(project_to)?)
+
+                Some(Stratifier::Annotation(Annotator::new(categories)))
             }
-        } else {
-            None
+            None => None,
         };
 
-        Ok(super::Reader::new_unchecked(reader, sample_map, projection))
+        let regions = self.regions.unwrap_or(None);
+        let callable = self
+            .callable_path
+            .unwrap_or(None)
+            .map(RegionSet::from_bed_path)
+            .transpose()?
+            .map(|callable| match &regions {
+                Some(regions) => Callable::new(callable.intersect(regions)),
+                None => Callable::new(callable),
+            });
+
+        Ok(super::Reader::new_unchecked(
+            reader,
+            sample_map,
+            projection,
+            polarizer,
+            stratifier,
+            self.site_filters.unwrap_or_default(),
+            self.genotype_filters.unwrap_or_default(),
+            callable,
+        ))
+    }
+
+    /// Sets the path to a BED file of callable regions.
+    ///
+    /// Sites outside the callable regions are skipped, and the number of callable bases without
+    /// any record is available from [`super::Reader::callable_bases_without_records`]. By
+    /// default, no callable regions are used.
+    pub fn set_callable_path(mut self, path: Option<PathBuf>) -> Self {
+        self.callable_path = Some(path);
+        self
+    }
+
+    /// Sets the regions read by the genotype reader.
+    ///
+    /// These should be the same regions as set on the genotype reader, see
+    /// [`genotype::reader::Builder::set_regions`], and are used to restrict any callable regions,
+    /// so that callable bases outside the regions are not counted. By default, callable regions
+    /// are not restricted.
+    pub fn set_regions(mut self, regions: Option<Vec<Region>>) -> Self {
+        self.regions = Some(regions);
+        self
+    }
+
+    /// Sets samples to exclude from the sample mapping.
+    ///
+    /// Excluded samples are removed after the sample mapping has been created, regardless of how
+    /// it was created. By default, no samples are excluded.
+    pub fn set_exclude_samples(mut self, samples: Vec<Sample>) -> Self {
+        self.exclude_samples = Some(samples);
+        self
+    }
+
+    /// Sets the genotype filters used for reading.
+    ///
+    /// Genotypes failing any filter are skipped, and so treated the same as missing genotypes.
+    /// By default, no genotype filters are used.
+    pub fn set_genotype_filters(mut self, filters: Vec<GenotypeFilter>) -> Self {
+        self.genotype_filters = Some(filters);
+        self
+    }
+
+    /// Sets the polarization used for reading.
+    ///
+    /// By default, no polarization will be used, so that the reference allele is taken to be
+    /// ancestral.
+    pub fn set_polarize(mut self, polarize: Option<Polarize>) -> Self {
+        self.polarize = Some(polarize);
+        self
+    }
+
+    /// Sets the ploidy of all samples.
+    ///
+    /// By default, all samples are taken to be diploid. Genotypes with a ploidy different from
+    /// the ploidy of their sample cause an error when reading.
+    pub fn set_ploidy(mut self, ploidy: usize) -> Self {
+        self.ploidy = Some(ploidy);
+        self
+    }
+
+    /// Sets the path to a file with the ploidy of individual samples.
+    ///
+    /// Each line of the file should contain a sample name and its ploidy, separated by a tab.
+    /// The ploidy of samples in the file takes precedence over the ploidy set by
+    /// [`Builder::set_ploidy`]. By default, no such file is used.
+    pub fn set_ploidy_path(mut self, path: Option<PathBuf>) -> Self {
+        self.ploidy_path = Some(path);
+        self
+    }
+
+    /// Sets the order of populations, and so the order of dimensions in the spectrum.
+    ///
+    /// The order must contain each population in the sample mapping exactly once, and takes
+    /// precedence over any order given in a samples file. By default, populations are ordered
+    /// by first appearance in the sample mapping.
+    pub fn set_population_order(mut self, order: Option<Vec<Population>>) -> Self {
+        self.population_order = Some(order);
+        self
     }
 
     /// Sets the projection used for reading.
@@ -92,6 +249,54 @@ impl Builder {
         self.samples = Some(samples);
         self
     }
+
+    /// Sets the site filters used for reading.
+    ///
+    /// Sites failing any filter are skipped. By default, no site filters are used.
+    pub fn set_site_filters(mut self, filters: Vec<SiteFilter>) -> Self {
+        self.site_filters = Some(filters);
+        self
+    }
+
+    /// Sets the stratification used for reading.
+    ///
+    /// Using a stratification, the stratum of each site is available from
+    /// [`super::Reader::current_stratum`]. By default, sites are not stratified.
+    pub fn set_stratify(mut self, stratify: Option<Stratify>) -> Self {
+        self.stratify = Some(stratify);
+        self
+    }
+}
+
+/// A source for the ancestral allele used for polarization.
+#[derive(Debug)]
+pub enum Polarize {
+    /// Ancestral allele given by the `AA` INFO field.
+    AncestralAllele,
+    /// Ancestral allele given by an ancestral FASTA with a `.fai` index.
+    Fasta(PathBuf),
+}
+
+/// A specification of mutation classes.
+#[derive(Debug)]
+pub enum MutationClasses {
+    /// The six strand-collapsed single nucleotide substitutions.
+    Substitutions,
+    /// The six strand-collapsed single nucleotide substitutions, where substitutions from C are
+    /// further split into CpG and non-CpG contexts using a reference FASTA with a `.fai` index.
+    CpG(PathBuf),
+}
+
+/// A stratification of sites.
+#[derive(Debug)]
+pub enum Stratify {
+    /// Stratify by mutation class.
+    MutationClasses(MutationClasses),
+    /// Stratify by functional annotation category.
+    ///
+    /// Categories are matched against the consequence terms in the VEP `CSQ` or SnpEff `ANN`
+    /// INFO field, where the first matching category takes precedence.
+    Annotation(Vec<String>),
 }
 
 /// A source for a sample mapping.
@@ -101,40 +306,105 @@ pub enum Samples {
     Path(PathBuf),
     /// A list of samples and associated populations.
     List(Vec<(Sample, sample::Population)>),
+    /// A regular expression matched against all samples.
+    ///
+    /// See [`sample::Map::from_pattern`] for details.
+    Pattern(String),
 }
 
 /// A projection specification.
 #[derive(Debug)]
 pub enum Project {
     /// Project to specified number of individuals.
+    ///
+    /// Individuals are assumed to have the ploidy of the samples in their population, which must
+    /// be the same for all samples in the population, see [`Builder::set_ploidy_path`].
     Individuals(Vec<usize>),
+    /// Project to specified number of individuals in each named population.
+    ///
+    /// Each population in the sample mapping must be named exactly once. Individuals are assumed
+    /// to have the ploidy of the samples in their population, as for [`Project::Individuals`].
+    NamedIndividuals(Vec<(Population, usize)>),
     /// Project to specified shape.
     Shape(Shape),
+    /// Randomly downsample to the specified projection.
+    ///
+    /// Rather than projecting using hypergeometric weights, alleles are drawn without replacement
+    /// at each site, so that the resulting spectrum consists only of integers. The random number
+    /// generator is seeded by the provided seed.
+    Downsample {
+        /// The projection to downsample to.
+        project: Box<Project>,
+        /// The random seed.
+        seed: u64,
+    },
 }
 
 impl Project {
-    fn shape(self) -> Shape {
-        match self {
-            Project::Individuals(individuals) => {
-                Shape(individuals.into_iter().map(|i| 2 * i + 1).collect())
+    fn shape(self, sample_map: &sample::Map) -> Result<Shape, Error> {
+        let individuals = match self {
+            Project::Individuals(individuals) => individuals,
+            Project::NamedIndividuals(individuals) => {
+                sample_map.order_by_population(individuals)?
             }
-            Project::Shape(shape) => shape,
+            Project::Shape(shape) => return Ok(shape),
+            Project::Downsample { project, .. } => return project.shape(sample_map),
+        };
+
+        if individuals.len() != sample_map.number_of_populations() {
+            return Err(ProjectionError::UnequalDimensions {
+                from: sample_map.number_of_populations(),
+                to: individuals.len(),
+            }
+            .into());
         }
+
+        individuals
+            .into_iter()
+            .zip(sample_map.population_ploidies())
+            .zip(sample_map.populations())
+            .map(|((i, ploidy), population)| match ploidy {
+                Some(ploidy) => Ok(ploidy * i + 1),
+                None => Err(Error::MixedPloidy {
+                    population: population.to_string(),
+                }),
+            })
+            .collect::<Result<_, _>>()
+            .map(Shape)
     }
 }
 
 /// An error associated with building a site reader.
 #[derive(Debug)]
 pub enum Error {
+    /// Provided annotation categories contain a duplicate.
+    DuplicateAnnotationCategory {
+        /// The duplicated category.
+        category: String,
+    },
     /// Provided sample mappping is empty.
     EmptySamplesMap,
+    /// Provided sample pattern is not a valid regular expression.
+    InvalidSamplePattern {
+        /// The provided pattern.
+        pattern: String,
+        /// The reason the pattern is invalid.
+        reason: String,
+    },
     /// I/O error.
     Io(io::Error),
+    /// Projection by individuals was requested for a population with mixed ploidies.
+    MixedPloidy {
+        /// The population with mixed ploidies.
+        population: String,
+    },
     /// A provided path does not exist.
     PathDoesNotExist {
         /// The provided path.
         path: PathBuf,
     },
+    /// A population error.
+    Population(sample::population::Error),
     /// A projection error.
     Projection(ProjectionError),
     /// Provided sample mapping defines a sample not defined by the genotype reader.
@@ -142,6 +412,8 @@ pub enum Error {
         /// The unknown sample.
         sample: String,
     },
+    /// Provided ploidy is zero.
+    ZeroPloidy,
 }
 
 impl From<io::Error> for Error {
@@ -150,6 +422,12 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<sample::population::Error> for Error {
+    fn from(e: sample::population::Error) -> Self {
+        Self::Population(e)
+    }
+}
+
 impl From<ProjectionError> for Error {
     fn from(e: ProjectionError) -> Self {
         Self::Projection(e)
@@ -159,13 +437,26 @@ impl From<ProjectionError> for Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Error::DuplicateAnnotationCategory { category } => {
+                write!(f, "duplicate annotation category {category}")
+            }
             Error::EmptySamplesMap => f.write_str("empty samples mapping"),
+            Error::InvalidSamplePattern { pattern, reason } => {
+                write!(f, "invalid sample pattern '{pattern}': {reason}")
+            }
             Error::Io(e) => write!(f, "{e}"),
+            Error::MixedPloidy { population } => write!(
+                f,
+                "cannot project population '{population}' by individuals, \
+                since its samples have different ploidies; project by shape instead"
+            ),
             Error::PathDoesNotExist { path } => {
                 write!(f, "path '{}' not found", path.display())
             }
             Error::UnknownSample { sample } => write!(f, "unknown sample {sample}"),
+            Error::Population(e) => write!(f, "{e}"),
             Error::Projection(e) => write!(f, "{e}"),
+            Error::ZeroPloidy => f.write_str("ploidy must be greater than zero"),
         }
     }
 }
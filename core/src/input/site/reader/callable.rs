@@ -0,0 +1,50 @@
+//! Callable regions.
+
+use crate::input::RegionSet;
+
+/// Callable regions, keeping track of the callable bases covered by records.
+pub(super) struct Callable {
+    regions: RegionSet,
+    covered: usize,
+    last: Option<(String, usize)>,
+}
+
+impl Callable {
+    pub fn new(regions: RegionSet) -> Self {
+        Self {
+            regions,
+            covered: 0,
+            last: None,
+        }
+    }
+
+    /// Returns true if the position on the contig is callable, recording the base as covered.
+    ///
+    /// Records must be provided in sorted order, so that multiple records at the same position
+    /// only cover the base once.
+    pub fn check(&mut self, contig: &str, position: usize) -> bool {
+        if !self.regions.contains(contig, position) {
+            return false;
+        }
+
+        match self.last.as_mut() {
+            Some((last_contig, last_position)) if last_contig == contig => {
+                if *last_position != position {
+                    *last_position = position;
+                    self.covered += 1;
+                }
+            }
+            _ => {
+                self.last = Some((contig.to_string(), position));
+                self.covered += 1;
+            }
+        }
+
+        true
+    }
+
+    /// Returns the number of callable bases not covered by any record so far.
+    pub fn uncovered(&self) -> usize {
+        self.regions.bases() - self.covered
+    }
+}
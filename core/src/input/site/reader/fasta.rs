@@ -0,0 +1,53 @@
+//! Indexed FASTA reading.
+
+use std::{io, path::PathBuf};
+
+use noodles_core::Region;
+use noodles_fasta as fasta;
+
+/// An indexed FASTA, caching the sequence of the current contig.
+pub(super) struct Fasta {
+    reader: fasta::IndexedReader<Box<dyn fasta::io::BufReadSeek>>,
+    contig: Option<String>,
+    sequence: fasta::record::Sequence,
+}
+
+impl Fasta {
+    pub fn from_path(path: PathBuf) -> io::Result<Self> {
+        fasta::indexed_reader::Builder::default()
+            .build_from_path(path)
+            .map(|reader| Self {
+                reader,
+                contig: None,
+                sequence: fasta::record::Sequence::default(),
+            })
+    }
+
+    /// Returns the base at the 1-based position in the contig, if defined.
+    ///
+    /// Contigs missing from the FASTA are treated as having no defined bases.
+    pub fn get(&mut self, contig: &str, position: usize) -> io::Result<Option<u8>> {
+        if self.contig.as_deref() != Some(contig) {
+            self.sequence = if self
+                .reader
+                .index()
+                .iter()
+                .any(|record| record.name() == contig)
+            {
+                self.reader
+                    .query(&Region::new(contig, ..))?
+                    .sequence()
+                    .clone()
+            } else {
+                fasta::record::Sequence::default()
+            };
+
+            self.contig = Some(contig.to_string());
+        }
+
+        Ok(noodles_core::Position::new(position)
+            .and_then(|position| self.sequence.get(position))
+            .copied()
+            .filter(|base| !matches!(base, b'N' | b'n' | b'-' | b'.')))
+    }
+}
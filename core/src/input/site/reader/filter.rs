@@ -0,0 +1,262 @@
+//! Site and genotype filters.
+
+use std::{fmt, io, str::FromStr};
+
+use crate::input::genotype;
+
+/// A comparison of a value against a threshold.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Comparison {
+    /// Equal to threshold.
+    Equal,
+    /// Greater than threshold.
+    Greater,
+    /// Greater than or equal to threshold.
+    GreaterOrEqual,
+    /// Less than threshold.
+    Less,
+    /// Less than or equal to threshold.
+    LessOrEqual,
+    /// Not equal to threshold.
+    NotEqual,
+}
+
+impl Comparison {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Comparison::Equal => "==",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::NotEqual => "!=",
+        }
+    }
+
+    fn compare(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Equal => value == threshold,
+            Comparison::Greater => value > threshold,
+            Comparison::GreaterOrEqual => value >= threshold,
+            Comparison::Less => value < threshold,
+            Comparison::LessOrEqual => value <= threshold,
+            Comparison::NotEqual => value != threshold,
+        }
+    }
+}
+
+/// A numeric threshold on a named field, such as `DP>=10`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Threshold {
+    key: String,
+    comparison: Comparison,
+    value: f64,
+}
+
+impl Threshold {
+    /// Returns the key of the field to compare.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Creates a new threshold.
+    pub fn new<S>(key: S, comparison: Comparison, value: f64) -> Self
+    where
+        S: ToString,
+    {
+        Self {
+            key: key.to_string(),
+            comparison,
+            value,
+        }
+    }
+
+    /// Returns `true` if the value passes the threshold.
+    ///
+    /// Missing values never pass.
+    pub fn passes(&self, value: Option<f64>) -> bool {
+        value.is_some_and(|value| self.comparison.compare(value, self.value))
+    }
+}
+
+impl fmt::Display for Threshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.key, self.comparison.as_str(), self.value)
+    }
+}
+
+impl FromStr for Threshold {
+    type Err = ParseThresholdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseThresholdError(s.to_string());
+
+        let (key, rest) = s
+            .find(['<', '>', '=', '!'])
+            .map(|i| s.split_at(i))
+            .ok_or_else(invalid)?;
+
+        // Two-character operators must be checked first, since they share prefixes
+        let (comparison, value) = [
+            (">=", Comparison::GreaterOrEqual),
+            ("<=", Comparison::LessOrEqual),
+            ("==", Comparison::Equal),
+            ("!=", Comparison::NotEqual),
+            (">", Comparison::Greater),
+            ("<", Comparison::Less),
+            ("=", Comparison::Equal),
+        ]
+        .into_iter()
+        .find_map(|(op, comparison)| rest.strip_prefix(op).map(|value| (comparison, value)))
+        .ok_or_else(invalid)?;
+
+        let key = key.trim();
+        let value = value.trim().parse::<f64>().map_err(|_| invalid())?;
+
+        if key.is_empty() || value.is_nan() {
+            Err(invalid())
+        } else {
+            Ok(Self::new(key, comparison, value))
+        }
+    }
+}
+
+/// An error associated with parsing a threshold.
+#[derive(Debug, Eq, PartialEq)]
+pub struct ParseThresholdError(String);
+
+impl fmt::Display for ParseThresholdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid threshold '{}', expected format 'KEY<op>VALUE' \
+            with <op> one of '>=', '>', '<=', '<', '==', '!='",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseThresholdError {}
+
+/// A filter on sites.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SiteFilter {
+    /// Site must have a `PASS` FILTER field.
+    Pass,
+    /// Site must have a QUAL field of at least the provided value.
+    MinQuality(f64),
+    /// Site must have an INFO field passing the threshold.
+    Info(Threshold),
+}
+
+impl SiteFilter {
+    /// Returns `true` if the current site in the reader passes the filter.
+    pub(super) fn passes(&self, reader: &dyn genotype::Reader) -> io::Result<bool> {
+        match self {
+            SiteFilter::Pass => reader.current_filter_is_pass(),
+            SiteFilter::MinQuality(min) => Ok(reader.current_quality().is_some_and(|q| q >= *min)),
+            SiteFilter::Info(threshold) => {
+                let value = reader
+                    .current_info_field(threshold.key())?
+                    .map(|value| parse_info_value(threshold.key(), &value))
+                    .transpose()?;
+
+                Ok(threshold.passes(value))
+            }
+        }
+    }
+}
+
+impl fmt::Display for SiteFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SiteFilter::Pass => f.write_str("FILTER=PASS"),
+            SiteFilter::MinQuality(min) => write!(f, "QUAL>={min}"),
+            SiteFilter::Info(threshold) => write!(f, "INFO/{threshold}"),
+        }
+    }
+}
+
+/// A threshold on a per-sample FORMAT field, such as `DP>=10`.
+///
+/// Genotypes failing the threshold are skipped as [`genotype::Skipped::Filtered`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct GenotypeFilter(pub Threshold);
+
+impl fmt::Display for GenotypeFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FORMAT/{}", self.0)
+    }
+}
+
+/// Parses the first value of a numeric INFO field.
+fn parse_info_value(key: &str, value: &str) -> io::Result<f64> {
+    value
+        .split(',')
+        .next()
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("INFO field '{key}' with value '{value}' is not numeric"),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_threshold() {
+        assert_eq!(
+            "DP>=10".parse(),
+            Ok(Threshold::new("DP", Comparison::GreaterOrEqual, 10.0))
+        );
+        assert_eq!(
+            "MQ > 30.5".parse(),
+            Ok(Threshold::new("MQ", Comparison::Greater, 30.5))
+        );
+        assert_eq!(
+            "AF<=0.1".parse(),
+            Ok(Threshold::new("AF", Comparison::LessOrEqual, 0.1))
+        );
+        assert_eq!(
+            "GQ<20".parse(),
+            Ok(Threshold::new("GQ", Comparison::Less, 20.0))
+        );
+        assert_eq!(
+            "N=2".parse(),
+            Ok(Threshold::new("N", Comparison::Equal, 2.0))
+        );
+        assert_eq!(
+            "N!=-1".parse(),
+            Ok(Threshold::new("N", Comparison::NotEqual, -1.0))
+        );
+    }
+
+    #[test]
+    fn test_parse_threshold_invalid() {
+        assert!("DP".parse::<Threshold>().is_err());
+        assert!(">=10".parse::<Threshold>().is_err());
+        assert!("DP>=".parse::<Threshold>().is_err());
+        assert!("DP=>10".parse::<Threshold>().is_err());
+        assert!("DP>=x".parse::<Threshold>().is_err());
+    }
+
+    #[test]
+    fn test_threshold_passes() {
+        let threshold = Threshold::new("DP", Comparison::GreaterOrEqual, 10.0);
+
+        assert!(threshold.passes(Some(10.0)));
+        assert!(threshold.passes(Some(11.0)));
+        assert!(!threshold.passes(Some(9.0)));
+        assert!(!threshold.passes(None));
+    }
+
+    #[test]
+    fn test_parse_info_value() {
+        assert_eq!(parse_info_value("AF", "0.25,0.5").unwrap(), 0.25);
+        assert!(parse_info_value("DB", "").is_err());
+    }
+}
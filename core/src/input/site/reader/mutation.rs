@@ -0,0 +1,270 @@
+//! Mutation classes.
+
+use std::{fmt, io};
+
+use crate::input::genotype::{self, reader::Alleles};
+
+use super::{builder::MutationClasses, fasta::Fasta, polarize::Polarity};
+
+/// A single nucleotide substitution, collapsed across strands.
+///
+/// Substitutions are named by the pyrimidine base of the ancestral allele, so that e.g. a G>A
+/// substitution is collapsed with its reverse complement C>T.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Substitution {
+    /// C>A or G>T.
+    CA,
+    /// C>G or G>C.
+    CG,
+    /// C>T or G>A.
+    CT,
+    /// T>A or A>T.
+    TA,
+    /// T>C or A>G.
+    TC,
+    /// T>G or A>C.
+    TG,
+}
+
+impl Substitution {
+    /// All substitutions.
+    pub const ALL: [Self; 6] = [Self::CA, Self::CG, Self::CT, Self::TA, Self::TC, Self::TG];
+
+    /// Returns the substitution from one base to another, if both are distinct bases.
+    ///
+    /// Bases are case-insensitive.
+    pub fn from_bases(from: u8, to: u8) -> Option<Self> {
+        let (from, to) = match from.to_ascii_uppercase() {
+            b'A' | b'G' => (complement(from)?, complement(to)?),
+            from => (from, to.to_ascii_uppercase()),
+        };
+
+        match (from, to) {
+            (b'C', b'A') => Some(Self::CA),
+            (b'C', b'G') => Some(Self::CG),
+            (b'C', b'T') => Some(Self::CT),
+            (b'T', b'A') => Some(Self::TA),
+            (b'T', b'C') => Some(Self::TC),
+            (b'T', b'G') => Some(Self::TG),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the substitution is from a C, and so may occur in a CpG context.
+    pub fn is_from_c(&self) -> bool {
+        matches!(self, Self::CA | Self::CG | Self::CT)
+    }
+}
+
+impl fmt::Display for Substitution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::CA => "C>A",
+            Self::CG => "C>G",
+            Self::CT => "C>T",
+            Self::TA => "T>A",
+            Self::TC => "T>C",
+            Self::TG => "T>G",
+        })
+    }
+}
+
+/// The sequence context of a substitution.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Context {
+    /// The substituted C is followed by a G, or the substituted G is preceded by a C.
+    CpG,
+    /// Any other context.
+    NonCpG,
+}
+
+impl fmt::Display for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::CpG => "CpG",
+            Self::NonCpG => "nonCpG",
+        })
+    }
+}
+
+/// A mutation class.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Class {
+    /// The substitution.
+    pub substitution: Substitution,
+    /// The sequence context of the substitution.
+    ///
+    /// Only defined for substitutions from C when distinguishing CpG contexts.
+    pub context: Option<Context>,
+}
+
+impl Class {
+    /// Returns all classes in order, optionally distinguishing CpG contexts.
+    pub fn all(cpg: bool) -> Vec<Self> {
+        Substitution::ALL
+            .into_iter()
+            .flat_map(|substitution| {
+                let contexts = if cpg && substitution.is_from_c() {
+                    vec![Some(Context::CpG), Some(Context::NonCpG)]
+                } else {
+                    vec![None]
+                };
+
+                contexts.into_iter().map(move |context| Self {
+                    substitution,
+                    context,
+                })
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.context {
+            Some(context) => write!(f, "{}_{context}", self.substitution),
+            None => write!(f, "{}", self.substitution),
+        }
+    }
+}
+
+/// A classifier of sites into mutation classes.
+pub(super) struct Classifier {
+    classes: Vec<Class>,
+    fasta: Option<Fasta>,
+}
+
+impl Classifier {
+    /// Returns all classes of the classifier in order.
+    pub fn classes(&self) -> &[Class] {
+        &self.classes
+    }
+
+    /// Returns the class of the current site in the reader with the provided polarity.
+    ///
+    /// Returns `None` for sites that are not biallelic single nucleotide polymorphisms, and for
+    /// substitutions from C where the CpG context cannot be determined.
+    pub fn classify(
+        &mut self,
+        reader: &dyn genotype::Reader,
+        polarity: Polarity,
+    ) -> io::Result<Option<Class>> {
+        let Some((reference, alternative)) = single_bases(&reader.current_alleles()?) else {
+            return Ok(None);
+        };
+
+        let substitution = match polarity {
+            Polarity::Reference => Substitution::from_bases(reference, alternative),
+            Polarity::Alternative => Substitution::from_bases(alternative, reference),
+            Polarity::Unknown => None,
+        };
+        let Some(substitution) = substitution else {
+            return Ok(None);
+        };
+
+        let context = match self.fasta.as_mut() {
+            Some(fasta) if substitution.is_from_c() => {
+                // The ancestral base is a C on one of the strands, so that the context is given
+                // by the following base for a C, and by the preceding base for a G
+                let position = reader.current_position();
+                let (position, cpg) = match ancestral_base(polarity, reference, alternative) {
+                    b'C' => (position + 1, b'G'),
+                    _ => (position.saturating_sub(1), b'C'),
+                };
+
+                match fasta.get(reader.current_contig(), position)? {
+                    Some(base) if base.eq_ignore_ascii_case(&cpg) => Some(Context::CpG),
+                    Some(_) => Some(Context::NonCpG),
+                    None => return Ok(None),
+                }
+            }
+            _ => None,
+        };
+
+        Ok(Some(Class {
+            substitution,
+            context,
+        }))
+    }
+
+    pub fn new(classes: MutationClasses) -> io::Result<Self> {
+        let fasta = match classes {
+            MutationClasses::Substitutions => None,
+            MutationClasses::CpG(path) => Some(Fasta::from_path(path)?),
+        };
+
+        Ok(Self {
+            classes: Class::all(fasta.is_some()),
+            fasta,
+        })
+    }
+}
+
+fn ancestral_base(polarity: Polarity, reference: u8, alternative: u8) -> u8 {
+    match polarity {
+        Polarity::Alternative => alternative.to_ascii_uppercase(),
+        _ => reference.to_ascii_uppercase(),
+    }
+}
+
+fn complement(base: u8) -> Option<u8> {
+    match base.to_ascii_uppercase() {
+        b'A' => Some(b'T'),
+        b'C' => Some(b'G'),
+        b'G' => Some(b'C'),
+        b'T' => Some(b'A'),
+        _ => None,
+    }
+}
+
+/// Returns the reference and alternative bases of a biallelic single nucleotide polymorphism.
+fn single_bases(alleles: &Alleles) -> Option<(u8, u8)> {
+    match (alleles.reference.as_bytes(), alleles.alternative.as_slice()) {
+        (&[reference], [alternative]) => match alternative.as_bytes() {
+            &[alternative] => Some((reference, alternative)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_substitution_from_bases() {
+        assert_eq!(Substitution::from_bases(b'C', b'T'), Some(Substitution::CT));
+        assert_eq!(Substitution::from_bases(b'g', b'a'), Some(Substitution::CT));
+        assert_eq!(Substitution::from_bases(b'A', b'C'), Some(Substitution::TG));
+        assert_eq!(Substitution::from_bases(b'T', b'C'), Some(Substitution::TC));
+        assert_eq!(Substitution::from_bases(b'A', b'A'), None);
+        assert_eq!(Substitution::from_bases(b'A', b'N'), None);
+    }
+
+    #[test]
+    fn test_class_all() {
+        let names = |cpg| {
+            Class::all(cpg)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(false), ["C>A", "C>G", "C>T", "T>A", "T>C", "T>G"]);
+        assert_eq!(
+            names(true),
+            [
+                "C>A_CpG",
+                "C>A_nonCpG",
+                "C>G_CpG",
+                "C>G_nonCpG",
+                "C>T_CpG",
+                "C>T_nonCpG",
+                "T>A",
+                "T>C",
+                "T>G"
+            ]
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Ancestral allele polarization.
+
+use std::io;
+
+use crate::input::genotype::{self, reader::Alleles};
+
+use super::{builder::Polarize, fasta::Fasta};
+
+/// The INFO key for the ancestral allele.
+const ANCESTRAL_ALLELE_KEY: &str = "AA";
+
+/// The polarity of a site, i.e. which of the alleles at the site is ancestral.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum Polarity {
+    /// The reference allele is ancestral.
+    Reference,
+    /// The (single) alternative allele is ancestral.
+    Alternative,
+    /// The ancestral allele is unknown, or matches none of the alleles.
+    Unknown,
+}
+
+impl Polarity {
+    fn from_ancestral(ancestral: &str, alleles: &Alleles) -> Self {
+        if ancestral.eq_ignore_ascii_case(&alleles.reference) {
+            Self::Reference
+        } else {
+            match alleles.alternative.as_slice() {
+                [alternative] if ancestral.eq_ignore_ascii_case(alternative) => Self::Alternative,
+                _ => Self::Unknown,
+            }
+        }
+    }
+}
+
+/// A source of ancestral alleles.
+pub(super) enum Polarizer {
+    AncestralAllele,
+    Fasta(Fasta),
+}
+
+impl Polarizer {
+    pub fn new(polarize: Polarize) -> io::Result<Self> {
+        match polarize {
+            Polarize::AncestralAllele => Ok(Self::AncestralAllele),
+            Polarize::Fasta(path) => Fasta::from_path(path).map(Self::Fasta),
+        }
+    }
+
+    /// Returns the polarity of the current site in the reader.
+    pub fn polarity(&mut self, reader: &dyn genotype::Reader) -> io::Result<Polarity> {
+        let ancestral = match self {
+            Polarizer::AncestralAllele => reader
+                .current_info_field(ANCESTRAL_ALLELE_KEY)?
+                // Some sources (e.g. 1000 Genomes) add indel information after a pipe
+                .map(|value| value.split('|').next().unwrap_or_default().to_string()),
+            Polarizer::Fasta(fasta) => fasta
+                .get(reader.current_contig(), reader.current_position())?
+                .map(|base| char::from(base).to_string()),
+        };
+
+        match ancestral {
+            Some(ancestral) => Ok(Polarity::from_ancestral(
+                &ancestral,
+                &reader.current_alleles()?,
+            )),
+            None => Ok(Polarity::Unknown),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alleles(reference: &str, alternative: &[&str]) -> Alleles {
+        Alleles {
+            reference: reference.to_string(),
+            alternative: alternative.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn test_polarity_from_ancestral() {
+        let alleles = alleles("A", &["C"]);
+
+        assert_eq!(Polarity::from_ancestral("A", &alleles), Polarity::Reference);
+        assert_eq!(
+            Polarity::from_ancestral("c", &alleles),
+            Polarity::Alternative
+        );
+        assert_eq!(Polarity::from_ancestral("T", &alleles), Polarity::Unknown);
+        assert_eq!(Polarity::from_ancestral("", &alleles), Polarity::Unknown);
+    }
+
+    #[test]
+    fn test_polarity_from_ancestral_multiallelic() {
+        let alleles = alleles("A", &["C", "G"]);
+
+        assert_eq!(Polarity::from_ancestral("A", &alleles), Polarity::Reference);
+        assert_eq!(Polarity::from_ancestral("C", &alleles), Polarity::Unknown);
+    }
+}
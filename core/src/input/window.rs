@@ -0,0 +1,328 @@
+//! Genomic windows.
+
+use std::{collections::VecDeque, num::NonZeroUsize};
+
+use super::Region;
+
+/// A specification of windows along contigs.
+///
+/// Windows are defined separately for each contig, starting from the beginning of the contig.
+/// If the step equals the size, windows are tiled; if the step is smaller than the size, windows
+/// are sliding and overlap; and if the step is larger than the size, there are gaps between
+/// windows. Alternatively, each contig may make up a single window.
+///
+/// For sliding windows with a fixed number of sites, windows at the end of a contig with fewer
+/// sites than the window size are dropped, except for the first window on the contig, so that all
+/// windows contain the same number of sites where possible.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Window {
+    /// Windows with a fixed number of bases.
+    Bases {
+        /// The number of bases in each window.
+        size: NonZeroUsize,
+        /// The number of bases between the starts of successive windows.
+        step: NonZeroUsize,
+    },
+    /// Windows with a fixed number of sites.
+    Sites {
+        /// The number of sites in each window.
+        size: NonZeroUsize,
+        /// The number of sites between the starts of successive windows.
+        step: NonZeroUsize,
+    },
+    /// Windows spanning entire contigs.
+    Contigs,
+}
+
+/// A value associated with a genomic window.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Windowed<T> {
+    /// The region of the window.
+    pub region: Region,
+    /// The value in the window.
+    pub value: T,
+}
+
+/// A collection of values accumulated in windows.
+///
+/// Sites must be provided in order of position within contigs, and all sites on a contig must be
+/// provided before moving on to the next contig. Windows are only created once they contain a
+/// site, so that windows without any sites are never returned. See [`Window`] for incomplete
+/// windows at the end of contigs.
+#[derive(Clone, Debug)]
+pub struct Windows<T> {
+    window: Window,
+    zero: T,
+    contig: Option<String>,
+    sites: usize,
+    next: usize,
+    open: VecDeque<Open<T>>,
+}
+
+impl<T> Windows<T>
+where
+    T: Clone,
+{
+    /// Returns all remaining windows, leaving the collection empty.
+    ///
+    /// This should be called once all sites have been added, to get the final windows.
+    pub fn finish(&mut self) -> Vec<Windowed<T>> {
+        let contig = self.contig.take().unwrap_or_default();
+        let window = self.window;
+
+        self.open
+            .drain(..)
+            .filter(|open| open.index == 0 || !window.is_incomplete(open.sites))
+            .map(|open| open.into_windowed(&contig, &window))
+            .collect()
+    }
+
+    /// Returns an iterator over the values in the windows containing the most recently added
+    /// site.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.open.iter_mut().map(|open| &mut open.value)
+    }
+
+    /// Creates a new, empty collection of windows.
+    ///
+    /// The provided zero value will be used as the initial value of each window.
+    pub fn new(window: Window, zero: T) -> Self {
+        Self {
+            window,
+            zero,
+            contig: None,
+            sites: 0,
+            next: 0,
+            open: VecDeque::new(),
+        }
+    }
+
+    /// Adds a site at the position on the contig, returning any windows that have finished.
+    ///
+    /// After adding a site, the windows containing the site can be updated by
+    /// [`Windows::iter_mut`].
+    pub fn push(&mut self, contig: &str, position: usize) -> Vec<Windowed<T>> {
+        let mut finished = Vec::new();
+
+        if self.contig.as_deref() != Some(contig) {
+            finished = self.finish();
+
+            self.contig = Some(contig.to_string());
+            self.sites = 0;
+            self.next = 0;
+        }
+        self.sites += 1;
+
+        let (coordinate, size, step) = match self.window {
+            Window::Bases { size, step } => (position, size.get(), step.get()),
+            Window::Sites { size, step } => (self.sites, size.get(), step.get()),
+            Window::Contigs => {
+                if self.open.is_empty() {
+                    self.open
+                        .push_back(Open::new(0, position, self.zero.clone()));
+                }
+                self.open.iter_mut().for_each(|open| open.add(position));
+
+                return finished;
+            }
+        };
+
+        while let Some(open) = self.open.front() {
+            if open.index * step + size < coordinate {
+                let open = self.open.pop_front().unwrap();
+                finished.push(open.into_windowed(contig, &self.window));
+            } else {
+                break;
+            }
+        }
+
+        // Window `i` spans coordinates `i * step + 1` to `i * step + size`, both inclusive
+        let first = (coordinate.saturating_sub(size) + step - 1) / step;
+        let last = coordinate.saturating_sub(1) / step;
+
+        for index in first.max(self.next)..=last {
+            self.open
+                .push_back(Open::new(index, position, self.zero.clone()));
+        }
+        self.next = self.next.max(last + 1);
+
+        self.open.iter_mut().for_each(|open| open.add(position));
+
+        finished
+    }
+}
+
+impl Window {
+    /// Returns true if a window with the provided number of sites is incomplete and should be
+    /// dropped at the end of a contig.
+    fn is_incomplete(&self, sites: usize) -> bool {
+        match self {
+            Window::Sites { size, step } => step < size && sites < size.get(),
+            Window::Bases { .. } | Window::Contigs => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Open<T> {
+    index: usize,
+    first: usize,
+    last: usize,
+    sites: usize,
+    value: T,
+}
+
+impl<T> Open<T> {
+    fn new(index: usize, position: usize, value: T) -> Self {
+        Self {
+            index,
+            first: position,
+            last: position,
+            sites: 0,
+            value,
+        }
+    }
+
+    fn add(&mut self, position: usize) {
+        self.last = position;
+        self.sites += 1;
+    }
+
+    fn into_windowed(self, contig: &str, window: &Window) -> Windowed<T> {
+        let region = match window {
+            Window::Bases { size, step } => {
+                let start = self.index * step.get() + 1;
+                Region::new(contig, start, Some(start + size.get() - 1))
+            }
+            Window::Sites { .. } | Window::Contigs => {
+                Region::new(contig, self.first, Some(self.last))
+            }
+        };
+
+        Windowed {
+            region,
+            value: self.value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bases(size: usize, step: usize) -> Window {
+        Window::Bases {
+            size: NonZeroUsize::new(size).unwrap(),
+            step: NonZeroUsize::new(step).unwrap(),
+        }
+    }
+
+    fn sites(size: usize, step: usize) -> Window {
+        Window::Sites {
+            size: NonZeroUsize::new(size).unwrap(),
+            step: NonZeroUsize::new(step).unwrap(),
+        }
+    }
+
+    /// Counts sites in windows, returning the non-empty windows as strings.
+    fn count(window: Window, sites: &[(&str, usize)]) -> Vec<String> {
+        let mut windows = Windows::new(window, 0);
+        let mut finished = Vec::new();
+
+        for &(contig, position) in sites {
+            finished.extend(windows.push(contig, position));
+            windows.iter_mut().for_each(|count| *count += 1);
+        }
+        finished.extend(windows.finish());
+
+        finished
+            .into_iter()
+            .map(|windowed| format!("{}={}", windowed.region, windowed.value))
+            .collect()
+    }
+
+    #[test]
+    fn test_tiled_bases() {
+        assert_eq!(
+            count(
+                bases(10, 10),
+                &[("chr1", 1), ("chr1", 10), ("chr1", 11), ("chr1", 35)]
+            ),
+            vec!["chr1:1-10=2", "chr1:11-20=1", "chr1:31-40=1"]
+        );
+    }
+
+    #[test]
+    fn test_sliding_bases() {
+        assert_eq!(
+            count(bases(10, 5), &[("chr1", 3), ("chr1", 7), ("chr1", 12)]),
+            vec!["chr1:1-10=2", "chr1:6-15=2", "chr1:11-20=1"]
+        );
+    }
+
+    #[test]
+    fn test_gapped_bases() {
+        assert_eq!(
+            count(bases(5, 10), &[("chr1", 3), ("chr1", 7), ("chr1", 12)]),
+            vec!["chr1:1-5=1", "chr1:11-15=1"]
+        );
+    }
+
+    #[test]
+    fn test_bases_multiple_contigs() {
+        assert_eq!(
+            count(bases(10, 10), &[("chr1", 5), ("chr2", 5), ("chr2", 25)]),
+            vec!["chr1:1-10=1", "chr2:1-10=1", "chr2:21-30=1"]
+        );
+    }
+
+    #[test]
+    fn test_tiled_sites() {
+        assert_eq!(
+            count(
+                sites(2, 2),
+                &[("chr1", 3), ("chr1", 7), ("chr1", 12), ("chr2", 1)]
+            ),
+            vec!["chr1:3-7=2", "chr1:12-12=1", "chr2:1-1=1"]
+        );
+    }
+
+    #[test]
+    fn test_contigs() {
+        assert_eq!(
+            count(
+                Window::Contigs,
+                &[("chr1", 3), ("chr1", 7), ("chr2", 12), ("chr3", 1)]
+            ),
+            vec!["chr1:3-7=2", "chr2:12-12=1", "chr3:1-1=1"]
+        );
+    }
+
+    #[test]
+    fn test_sliding_sites() {
+        assert_eq!(
+            count(sites(3, 1), &[("chr1", 3), ("chr1", 7), ("chr1", 12)]),
+            vec!["chr1:3-12=3"]
+        );
+    }
+
+    #[test]
+    fn test_sliding_sites_end_of_contig() {
+        assert_eq!(
+            count(
+                sites(3, 2),
+                &[
+                    ("chr1", 1),
+                    ("chr1", 2),
+                    ("chr1", 3),
+                    ("chr1", 4),
+                    ("chr1", 5),
+                    ("chr1", 6),
+                    ("chr2", 1),
+                    ("chr2", 2),
+                ]
+            ),
+            vec!["chr1:1-3=3", "chr1:3-5=3", "chr2:1-2=2"]
+        );
+    }
+}
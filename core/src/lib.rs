@@ -45,4 +45,6 @@ pub use spectrum::{Scs, Sfs, Spectrum};
 pub mod array;
 pub use array::Array;
 
+pub mod saf;
+
 pub mod utils;
@@ -0,0 +1,229 @@
+//! Sample allele frequency likelihoods.
+//!
+//! Where genotypes cannot be called reliably, a spectrum may instead be estimated from genotype
+//! likelihoods. For each site and population, the genotype likelihoods of the samples are
+//! combined into sample allele frequency (SAF) likelihoods, i.e. the likelihood of the data
+//! given each possible number of derived alleles in the population. See Nielsen et al. (2012)
+//! for details. A spectrum can then be estimated from the SAF likelihoods across many sites by
+//! expectation-maximisation, see [`Em`].
+
+use crate::{
+    array::{Shape, ShapeError},
+    input::genotype::Likelihoods,
+    utils::ln_binomial,
+};
+
+pub mod angsd;
+
+pub mod em;
+pub use em::Em;
+
+/// Sample allele frequency likelihoods for a number of sites.
+///
+/// The likelihoods are stored on linear scale, site-major, and with populations concatenated
+/// for each site. That is, each site contains likelihoods for each of the elements in the
+/// shape for the first population, followed by the second population, and so on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Saf {
+    values: Vec<f64>,
+    shape: Shape,
+}
+
+impl Saf {
+    /// Creates a new container for SAF likelihoods with no sites.
+    pub fn from_shape<S>(shape: S) -> Self
+    where
+        S: Into<Shape>,
+    {
+        Self {
+            values: Vec::new(),
+            shape: shape.into(),
+        }
+    }
+
+    /// Returns true if no sites are defined, false otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns an iterator over the sites, each with the concatenated likelihoods for all
+    /// populations.
+    pub fn iter_sites(&self) -> std::slice::ChunksExact<'_, f64> {
+        self.values.chunks_exact(self.width())
+    }
+
+    /// Creates new SAF likelihoods from concatenated data in site-major order and a shape.
+    ///
+    /// # Errors
+    ///
+    /// If the number of items in the data does not fit the provided shape.
+    pub fn new<D, S>(values: D, shape: S) -> Result<Self, ShapeError>
+    where
+        D: Into<Vec<f64>>,
+        S: Into<Shape>,
+    {
+        let values = values.into();
+        let shape = shape.into();
+
+        let width = shape.iter().sum::<usize>();
+        if width > 0 && values.len() % width == 0 {
+            Ok(Self { values, shape })
+        } else {
+            Err(ShapeError::Elements {
+                shape,
+                n: values.len(),
+            })
+        }
+    }
+
+    /// Adds a site to the likelihoods.
+    ///
+    /// # Errors
+    ///
+    /// If the number of concatenated likelihoods in the site does not fit the shape.
+    pub fn push(&mut self, site: &[f64]) -> Result<(), ShapeError> {
+        if site.len() == self.width() {
+            self.values.extend_from_slice(site);
+            Ok(())
+        } else {
+            Err(ShapeError::Elements {
+                shape: self.shape.clone(),
+                n: site.len(),
+            })
+        }
+    }
+
+    /// Returns the shape of the likelihoods.
+    ///
+    /// This corresponds to the shape of a spectrum estimated from the likelihoods.
+    pub fn shape(&self) -> &Shape {
+        &self.shape
+    }
+
+    /// Returns the number of sites.
+    pub fn sites(&self) -> usize {
+        self.values.len() / self.width()
+    }
+
+    fn width(&self) -> usize {
+        self.shape.iter().sum()
+    }
+}
+
+/// Returns the SAF likelihoods for a single population at a site from the genotype likelihoods
+/// of the samples in the population.
+///
+/// The likelihoods are returned on linear scale, rescaled so that the maximum is one. Missing
+/// data may be represented by flat genotype likelihoods.
+pub fn from_genotype_likelihoods<'a, I>(likelihoods: I) -> Vec<f64>
+where
+    I: IntoIterator<Item = &'a Likelihoods>,
+{
+    // Using the dynamic programming algorithm from Nielsen et al. (2012), with intermediate
+    // rescaling to avoid underflow
+    let mut saf = vec![1.0];
+    let mut buf = Vec::new();
+
+    for likelihoods in likelihoods {
+        let ploidy = likelihoods.ploidy();
+        let max = max(likelihoods.as_slice());
+
+        buf.clear();
+        buf.resize(saf.len() + ploidy, 0.0);
+
+        for (g, &v) in likelihoods.as_slice().iter().enumerate() {
+            let scaled = if max.is_finite() {
+                (v - max + ln_binomial(ploidy as u64, g as u64)).exp()
+            } else {
+                // No information in the likelihoods, treat as flat
+                ln_binomial(ploidy as u64, g as u64).exp()
+            };
+
+            buf.iter_mut()
+                .skip(g)
+                .zip(saf.iter())
+                .for_each(|(x, &h)| *x += h * scaled);
+        }
+
+        rescale(&mut buf);
+        std::mem::swap(&mut saf, &mut buf);
+    }
+
+    let n = saf.len() as u64 - 1;
+    saf.iter_mut()
+        .enumerate()
+        .for_each(|(k, x)| *x = (x.ln() - ln_binomial(n, k as u64)).exp());
+    rescale(&mut saf);
+
+    saf
+}
+
+fn max(values: &[f64]) -> f64 {
+    values.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+}
+
+fn rescale(values: &mut [f64]) {
+    let max = max(values);
+
+    if max > 0.0 {
+        values.iter_mut().for_each(|x| *x /= max);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEG_INF: f64 = f64::NEG_INFINITY;
+
+    #[test]
+    fn test_from_genotype_likelihoods_known_genotypes() {
+        let likelihoods = [
+            Likelihoods::new(vec![NEG_INF, 0., NEG_INF]),
+            Likelihoods::new(vec![NEG_INF, NEG_INF, 0.]),
+        ];
+
+        assert_approx_eq!(
+            from_genotype_likelihoods(&likelihoods),
+            vec![0., 0., 0., 1., 0.]
+        );
+    }
+
+    #[test]
+    fn test_from_genotype_likelihoods_flat() {
+        let likelihoods = [Likelihoods::new(vec![0., 0., 0.])];
+
+        assert_approx_eq!(from_genotype_likelihoods(&likelihoods), vec![1., 1., 1.]);
+    }
+
+    #[test]
+    fn test_from_genotype_likelihoods_two_samples() {
+        let likelihoods = [
+            Likelihoods::from_phred([0., 10., 20.]),
+            Likelihoods::from_phred([30., 0., 30.]),
+        ];
+
+        // Before rescaling: [0.001, 2.0002, 0.40101, 0.0202, 0.00001] / [1, 4, 6, 4, 1]
+        assert_approx_eq!(
+            from_genotype_likelihoods(&likelihoods),
+            vec![0.002000, 1.0, 0.133657, 0.010099, 0.000020]
+        );
+    }
+
+    #[test]
+    fn test_new_shape_error() {
+        assert!(Saf::new(vec![0.; 7], [3, 2]).is_err());
+        assert!(Saf::new(vec![0.; 10], [3, 2]).is_ok());
+    }
+
+    #[test]
+    fn test_push_and_iter_sites() {
+        let mut saf = Saf::from_shape([2, 3]);
+        saf.push(&[1., 2., 3., 4., 5.]).unwrap();
+        saf.push(&[6., 7., 8., 9., 10.]).unwrap();
+        assert!(saf.push(&[0.]).is_err());
+
+        assert_eq!(saf.sites(), 2);
+        assert_eq!(saf.iter_sites().nth(1), Some(&[6., 7., 8., 9., 10.][..]));
+    }
+}
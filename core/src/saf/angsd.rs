@@ -0,0 +1,530 @@
+//! Reading SAF likelihoods from ANGSD.
+//!
+//! ANGSD stores SAF likelihoods for a single population in three files sharing a common prefix:
+//! an index file (`.saf.idx`), a BGZF-compressed file of positions (`.saf.pos.gz`), and a
+//! BGZF-compressed file of likelihoods (`.saf.gz`). Only version 3 of the format is supported,
+//! as written by `angsd -doSaf` and read by `realSFS`.
+
+use std::{
+    fs::File,
+    io::{self, Read, Seek},
+    path::{Path, PathBuf},
+};
+
+use noodles_bgzf as bgzf;
+
+use super::Saf;
+
+/// The magic number at the start of each of the files in version 3 of the SAF format.
+pub const MAGIC_NUMBER: [u8; 8] = *b"safv3\0\0\0";
+
+/// The index of SAF likelihoods for a single population.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Index {
+    alleles: usize,
+    records: Vec<IndexRecord>,
+}
+
+impl Index {
+    /// Returns the number of alleles in the population.
+    ///
+    /// Each site contains likelihoods for one more than this number of derived alleles.
+    pub fn alleles(&self) -> usize {
+        self.alleles
+    }
+
+    /// Reads an index from a path.
+    pub fn from_path<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::read(io::BufReader::new(open(path.as_ref())?))
+    }
+
+    /// Returns the index record for a contig, if any.
+    pub fn get(&self, contig: &str) -> Option<&IndexRecord> {
+        self.records.iter().find(|record| record.name == contig)
+    }
+
+    /// Reads an index from a reader.
+    pub fn read<R>(mut reader: R) -> io::Result<Self>
+    where
+        R: Read,
+    {
+        read_magic(&mut reader, "index")?;
+
+        let alleles = read_u64(&mut reader)? as usize;
+
+        let mut records = Vec::new();
+        while let Some(name_length) = read_u64_or_eof(&mut reader)? {
+            let mut name = vec![0; name_length as usize];
+            reader.read_exact(&mut name)?;
+
+            records.push(IndexRecord {
+                name: String::from_utf8(name).map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, "invalid SAF index contig name")
+                })?,
+                sites: read_u64(&mut reader)? as usize,
+                position_offset: bgzf::VirtualPosition::from(read_u64(&mut reader)?),
+                item_offset: bgzf::VirtualPosition::from(read_u64(&mut reader)?),
+            });
+        }
+
+        Ok(Self { alleles, records })
+    }
+
+    /// Returns the index records, one for each contig.
+    pub fn records(&self) -> &[IndexRecord] {
+        &self.records
+    }
+
+    /// Returns the total number of sites across contigs.
+    pub fn sites(&self) -> usize {
+        self.records.iter().map(|record| record.sites).sum()
+    }
+}
+
+/// An index record for a single contig.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IndexRecord {
+    name: String,
+    sites: usize,
+    position_offset: bgzf::VirtualPosition,
+    item_offset: bgzf::VirtualPosition,
+}
+
+impl IndexRecord {
+    /// Returns the name of the contig.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the number of sites on the contig.
+    pub fn sites(&self) -> usize {
+        self.sites
+    }
+}
+
+/// A reader of SAF likelihoods for a single population.
+pub struct Reader<R> {
+    index: Index,
+    positions: bgzf::Reader<R>,
+    items: bgzf::Reader<R>,
+}
+
+impl Reader<io::BufReader<File>> {
+    /// Creates a new reader from the path to an index file.
+    ///
+    /// The positions and likelihoods files are expected next to the index, sharing the prefix
+    /// before the `.saf.idx` extension.
+    pub fn from_index_path<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let prefix = prefix(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "SAF index path '{}' does not end with '.saf.idx'",
+                    path.display()
+                ),
+            )
+        })?;
+
+        let open_bgzf = |extension| {
+            let mut path = prefix.clone().into_os_string();
+            path.push(extension);
+
+            open(Path::new(&path)).map(|file| bgzf::Reader::new(io::BufReader::new(file)))
+        };
+
+        Self::new(
+            Index::from_path(path)?,
+            open_bgzf(".saf.pos.gz")?,
+            open_bgzf(".saf.gz")?,
+        )
+    }
+}
+
+impl<R> Reader<R>
+where
+    R: Read + Seek,
+{
+    /// Creates a new reader from an index, and BGZF readers of positions and likelihoods.
+    pub fn new(
+        index: Index,
+        mut positions: bgzf::Reader<R>,
+        mut items: bgzf::Reader<R>,
+    ) -> io::Result<Self> {
+        read_magic(&mut positions, "positions")?;
+        read_magic(&mut items, "likelihoods")?;
+
+        Ok(Self {
+            index,
+            positions,
+            items,
+        })
+    }
+
+    /// Returns the index of the reader.
+    pub fn index(&self) -> &Index {
+        &self.index
+    }
+
+    /// Reads the positions and likelihoods of all sites on a contig into the provided buffers.
+    ///
+    /// The buffers are cleared before reading. Likelihoods are read in natural log space, with
+    /// the likelihoods of each site following one another. Returns false if the contig is not
+    /// in the index, true otherwise.
+    pub fn read_contig(
+        &mut self,
+        contig: &str,
+        positions: &mut Vec<u32>,
+        items: &mut Vec<f32>,
+    ) -> io::Result<bool> {
+        let Some(record) = self.index.get(contig) else {
+            return Ok(false);
+        };
+
+        self.positions.seek(record.position_offset)?;
+        read_values(
+            &mut self.positions,
+            record.sites,
+            positions,
+            u32::from_le_bytes,
+        )?;
+
+        self.items.seek(record.item_offset)?;
+        read_values(
+            &mut self.items,
+            record.sites * (self.index.alleles + 1),
+            items,
+            f32::from_le_bytes,
+        )?;
+
+        Ok(true)
+    }
+}
+
+/// Reads the SAF likelihoods of the sites shared by all populations.
+///
+/// Contigs are taken in the order of the first population, and contigs not present in all
+/// populations are left out. Within each contig, sites are matched by position, and positions
+/// must be sorted. The likelihoods are converted to linear scale, rescaled so that the maximum
+/// of each population at each site is one, and concatenated across populations in the order of
+/// the readers.
+///
+/// # Panics
+///
+/// If no readers are provided.
+pub fn read_intersect<R>(readers: &mut [Reader<R>]) -> io::Result<Saf>
+where
+    R: Read + Seek,
+{
+    let shape = readers
+        .iter()
+        .map(|reader| reader.index.alleles + 1)
+        .collect::<Vec<_>>();
+    let mut saf = Saf::from_shape(shape.clone());
+
+    let contigs = readers
+        .first()
+        .expect("cannot read SAF likelihoods without readers")
+        .index
+        .records
+        .iter()
+        .map(|record| record.name.clone())
+        .collect::<Vec<_>>();
+
+    let mut positions = vec![Vec::new(); readers.len()];
+    let mut items = vec![Vec::new(); readers.len()];
+    let mut site = Vec::with_capacity(shape.iter().sum());
+
+    'contigs: for contig in contigs {
+        for ((reader, positions), items) in readers
+            .iter_mut()
+            .zip(positions.iter_mut())
+            .zip(items.iter_mut())
+        {
+            if !reader.read_contig(&contig, positions, items)? {
+                continue 'contigs;
+            }
+        }
+
+        let mut indices = vec![0; readers.len()];
+        while let Some(current) = indices
+            .iter()
+            .zip(positions.iter())
+            .map(|(&i, positions)| positions.get(i).copied())
+            .collect::<Option<Vec<_>>>()
+        {
+            let max = current.iter().copied().max().unwrap_or_default();
+
+            if current.iter().all(|&position| position == max) {
+                site.clear();
+                for ((&i, items), &width) in indices.iter().zip(items.iter()).zip(shape.iter()) {
+                    let start = site.len();
+                    site.extend(items[i * width..][..width].iter().map(|&v| f64::from(v)));
+                    exp_rescale(&mut site[start..]);
+                }
+
+                saf.push(&site)
+                    .expect("SAF likelihoods width does not match shape");
+                indices.iter_mut().for_each(|i| *i += 1);
+            } else {
+                indices
+                    .iter_mut()
+                    .zip(current.iter())
+                    .filter(|(_, &position)| position < max)
+                    .for_each(|(i, _)| *i += 1);
+            }
+        }
+    }
+
+    Ok(saf)
+}
+
+/// Returns the prefix of a path to an index file, i.e. the path without `.saf.idx`.
+fn prefix(path: &Path) -> Option<PathBuf> {
+    path.to_str()
+        .and_then(|s| s.strip_suffix(".saf.idx"))
+        .map(PathBuf::from)
+}
+
+fn open(path: &Path) -> io::Result<File> {
+    File::open(path).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("failed to open SAF file '{}': {e}", path.display()),
+        )
+    })
+}
+
+fn read_magic<R>(reader: &mut R, kind: &str) -> io::Result<()>
+where
+    R: Read,
+{
+    let mut magic = [0; MAGIC_NUMBER.len()];
+    reader.read_exact(&mut magic)?;
+
+    if magic == MAGIC_NUMBER {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid SAF {kind} magic number, only SAF version 3 is supported"),
+        ))
+    }
+}
+
+fn read_u64<R>(reader: &mut R) -> io::Result<u64>
+where
+    R: Read,
+{
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_u64_or_eof<R>(reader: &mut R) -> io::Result<Option<u64>>
+where
+    R: Read,
+{
+    let mut buf = [0; 8];
+
+    match reader.read(&mut buf[..1])? {
+        0 => Ok(None),
+        _ => {
+            reader.read_exact(&mut buf[1..])?;
+            Ok(Some(u64::from_le_bytes(buf)))
+        }
+    }
+}
+
+fn read_values<R, T, F>(reader: &mut R, n: usize, values: &mut Vec<T>, f: F) -> io::Result<()>
+where
+    R: Read,
+    F: Fn([u8; 4]) -> T,
+{
+    let mut bytes = vec![0; 4 * n];
+    reader.read_exact(&mut bytes).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => io::Error::new(
+            io::ErrorKind::InvalidData,
+            "SAF file has fewer sites than given by index",
+        ),
+        _ => e,
+    })?;
+
+    values.clear();
+    values.extend(
+        bytes
+            .chunks_exact(4)
+            .map(|chunk| f(chunk.try_into().expect("chunk has four bytes"))),
+    );
+
+    Ok(())
+}
+
+/// Converts log-likelihoods to linear scale, rescaled so that the maximum is one.
+fn exp_rescale(values: &mut [f64]) {
+    let max = super::max(values);
+
+    if max.is_finite() {
+        values.iter_mut().for_each(|x| *x = (*x - max).exp());
+    } else {
+        // No information in the likelihoods, treat as flat
+        values.iter_mut().for_each(|x| *x = 1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::{Cursor, Write};
+
+    type Site<'a> = (u32, &'a [f32]);
+
+    /// Writes the SAF files for a single population with the provided contigs, each with sites
+    /// given by a position and log-likelihoods.
+    fn write_saf(alleles: usize, contigs: &[(&str, &[Site])]) -> Reader<Cursor<Vec<u8>>> {
+        let mut index = MAGIC_NUMBER.to_vec();
+        index.extend((alleles as u64).to_le_bytes());
+
+        let mut positions = bgzf::Writer::new(Vec::new());
+        let mut items = bgzf::Writer::new(Vec::new());
+        positions.write_all(&MAGIC_NUMBER).unwrap();
+        items.write_all(&MAGIC_NUMBER).unwrap();
+
+        for (name, sites) in contigs {
+            index.extend((name.len() as u64).to_le_bytes());
+            index.extend(name.as_bytes());
+            index.extend((sites.len() as u64).to_le_bytes());
+            index.extend(u64::from(positions.virtual_position()).to_le_bytes());
+            index.extend(u64::from(items.virtual_position()).to_le_bytes());
+
+            for (position, values) in sites.iter() {
+                assert_eq!(values.len(), alleles + 1);
+
+                positions.write_all(&position.to_le_bytes()).unwrap();
+                values
+                    .iter()
+                    .for_each(|v| items.write_all(&v.to_le_bytes()).unwrap());
+            }
+        }
+
+        Reader::new(
+            Index::read(index.as_slice()).unwrap(),
+            bgzf::Reader::new(Cursor::new(positions.finish().unwrap())),
+            bgzf::Reader::new(Cursor::new(items.finish().unwrap())),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_read_index() {
+        let reader = write_saf(2, &[("chr1", &[(1, &[0., -1., -2.])]), ("chr2", &[])]);
+        let index = reader.index();
+
+        assert_eq!(index.alleles(), 2);
+        assert_eq!(
+            index
+                .records()
+                .iter()
+                .map(|record| (record.name(), record.sites()))
+                .collect::<Vec<_>>(),
+            vec![("chr1", 1), ("chr2", 0)]
+        );
+        assert_eq!(index.sites(), 1);
+    }
+
+    #[test]
+    fn test_read_contig() {
+        let mut reader = write_saf(
+            1,
+            &[
+                ("chr1", &[(1, &[0., -1.]), (3, &[-2., 0.])]),
+                ("chr2", &[(2, &[-3., 0.])]),
+            ],
+        );
+
+        let mut positions = Vec::new();
+        let mut items = Vec::new();
+
+        assert!(reader
+            .read_contig("chr2", &mut positions, &mut items)
+            .unwrap());
+        assert_eq!(
+            (positions.as_slice(), items.as_slice()),
+            (&[2][..], &[-3., 0.][..])
+        );
+
+        assert!(reader
+            .read_contig("chr1", &mut positions, &mut items)
+            .unwrap());
+        assert_eq!(
+            (positions.as_slice(), items.as_slice()),
+            (&[1, 3][..], &[0., -1., -2., 0.][..])
+        );
+
+        assert!(!reader
+            .read_contig("chr3", &mut positions, &mut items)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_read_intersect() {
+        let mut readers = [
+            write_saf(
+                1,
+                &[
+                    ("chr1", &[(1, &[0., -1.]), (2, &[0., -2.]), (4, &[0., -3.])]),
+                    ("chr2", &[(1, &[-1., 0.])]),
+                    ("chr3", &[(1, &[0., 0.])]),
+                ],
+            ),
+            write_saf(
+                2,
+                &[
+                    ("chr2", &[(1, &[0., -1., -2.]), (2, &[0., 0., 0.])]),
+                    ("chr1", &[(2, &[-1., 0., -1.]), (3, &[0., 0., 0.])]),
+                ],
+            ),
+        ];
+
+        let saf = read_intersect(&mut readers).unwrap();
+        let e = std::f64::consts::E;
+
+        assert_eq!(saf.shape().as_ref(), &[2, 3]);
+        assert_eq!(saf.sites(), 2);
+
+        let sites = saf.iter_sites().collect::<Vec<_>>();
+        let expected = [
+            [1., e.powi(-2), e.powi(-1), 1., e.powi(-1)],
+            [e.powi(-1), 1., 1., e.powi(-1), e.powi(-2)],
+        ];
+        for (site, expected) in sites.iter().zip(expected.iter()) {
+            for (x, y) in site.iter().zip(expected.iter()) {
+                assert_approx_eq!(x, y, epsilon = 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_magic_number() {
+        let mut index = b"safv4\0\0\0".to_vec();
+        index.extend(2u64.to_le_bytes());
+
+        assert!(Index::read(index.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_prefix() {
+        assert_eq!(
+            prefix(Path::new("dir/pop.saf.idx")),
+            Some(PathBuf::from("dir/pop"))
+        );
+        assert_eq!(prefix(Path::new("dir/pop.saf.gz")), None);
+    }
+}
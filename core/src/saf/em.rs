@@ -0,0 +1,276 @@
+//! Expectation-maximisation.
+
+use crate::{array::Shape, Scs};
+
+use super::Saf;
+
+/// An expectation-maximisation (EM) algorithm for estimating a spectrum from SAF likelihoods.
+///
+/// This is the standard EM algorithm used e.g. by realSFS, see Nielsen et al. (2012). Starting
+/// from a uniform spectrum, each iteration updates the spectrum to the average posterior
+/// over sites. The algorithm stops when the change in log-likelihood between iterations falls
+/// below the tolerance, or when the maximum number of iterations is reached.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Em {
+    max_iterations: usize,
+    tolerance: f64,
+}
+
+impl Em {
+    /// Estimates a spectrum from SAF likelihoods.
+    pub fn estimate(&self, saf: &Saf) -> Estimate {
+        let shape = saf.shape().clone();
+        let indices = SiteIndices::new(&shape);
+
+        let elements = shape.elements();
+        let mut sfs = vec![1.0 / elements as f64; elements];
+        let mut bufs = Buffers::from_zeros(elements);
+
+        let mut log_likelihood = f64::NEG_INFINITY;
+        let mut iterations = 0;
+        let mut converged = false;
+
+        if !saf.is_empty() {
+            while iterations < self.max_iterations {
+                let new_log_likelihood = step(&mut sfs, &mut bufs, saf, &indices);
+                iterations += 1;
+
+                let difference = new_log_likelihood - log_likelihood;
+                log_likelihood = new_log_likelihood;
+
+                if difference.abs() < self.tolerance {
+                    converged = true;
+                    break;
+                }
+            }
+        }
+
+        let sites = saf.sites() as f64;
+        sfs.iter_mut().for_each(|x| *x *= sites);
+
+        Estimate {
+            scs: Scs::new(sfs, shape).expect("estimated spectrum does not fit shape"),
+            iterations,
+            log_likelihood,
+            converged,
+        }
+    }
+
+    /// Sets the maximum number of iterations.
+    ///
+    /// By default, at most 100 iterations will be used.
+    pub fn set_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Sets the tolerance.
+    ///
+    /// The algorithm will stop when the difference in log-likelihood between two successive
+    /// iterations is below the tolerance. By default, a tolerance of 10⁻⁶ will be used.
+    pub fn set_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+}
+
+impl Default for Em {
+    fn default() -> Self {
+        Self {
+            max_iterations: 100,
+            tolerance: 1e-6,
+        }
+    }
+}
+
+/// A spectrum estimated by [`Em`].
+#[derive(Clone, Debug)]
+pub struct Estimate {
+    /// The estimated spectrum, scaled to sum to the number of sites.
+    pub scs: Scs,
+    /// The number of iterations run.
+    pub iterations: usize,
+    /// The log-likelihood of the spectrum before the last iteration, up to a constant.
+    pub log_likelihood: f64,
+    /// Whether the algorithm converged before reaching the maximum number of iterations.
+    pub converged: bool,
+}
+
+/// Runs a single EM step, updating the spectrum in-place and returning the log-likelihood of
+/// the spectrum prior to the update.
+fn step(sfs: &mut [f64], bufs: &mut Buffers, saf: &Saf, indices: &SiteIndices) -> f64 {
+    let Buffers { posterior, sum } = bufs;
+    sum.iter_mut().for_each(|x| *x = 0.0);
+
+    let mut log_likelihood = 0.0;
+    let mut sites = 0;
+
+    for site in saf.iter_sites() {
+        posterior
+            .iter_mut()
+            .zip(sfs.iter())
+            .zip(indices.iter())
+            .for_each(|((x, p), cell)| *x = p * cell.iter().map(|&i| site[i]).product::<f64>());
+
+        let site_likelihood = posterior.iter().sum::<f64>();
+
+        // A site with zero likelihood under the current spectrum carries no information
+        if site_likelihood > 0.0 {
+            sum.iter_mut()
+                .zip(posterior.iter())
+                .for_each(|(x, v)| *x += v / site_likelihood);
+
+            log_likelihood += site_likelihood.ln();
+            sites += 1;
+        }
+    }
+
+    if sites > 0 {
+        sfs.iter_mut()
+            .zip(sum.iter())
+            .for_each(|(p, x)| *p = x / sites as f64);
+    }
+
+    log_likelihood
+}
+
+#[derive(Debug)]
+struct Buffers {
+    posterior: Vec<f64>,
+    sum: Vec<f64>,
+}
+
+impl Buffers {
+    fn from_zeros(elements: usize) -> Self {
+        Self {
+            posterior: vec![0.0; elements],
+            sum: vec![0.0; elements],
+        }
+    }
+}
+
+/// For each cell in a spectrum, the indices into a site of concatenated SAF likelihoods.
+#[derive(Debug)]
+struct SiteIndices {
+    indices: Vec<usize>,
+    dimensions: usize,
+}
+
+impl SiteIndices {
+    fn new(shape: &Shape) -> Self {
+        let offsets = shape
+            .iter()
+            .scan(0, |offset, &n| {
+                let current = *offset;
+                *offset += n;
+                Some(current)
+            })
+            .collect::<Vec<_>>();
+
+        let indices = (0..shape.elements())
+            .flat_map(|flat| {
+                shape
+                    .index_from_flat_unchecked(flat)
+                    .into_iter()
+                    .zip(offsets.iter())
+                    .map(|(i, offset)| i + offset)
+            })
+            .collect();
+
+        Self {
+            indices,
+            dimensions: shape.dimensions(),
+        }
+    }
+
+    fn iter(&self) -> std::slice::ChunksExact<'_, usize> {
+        self.indices.chunks_exact(self.dimensions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_site_indices() {
+        let indices = SiteIndices::new(&Shape(vec![2, 3]));
+
+        assert_eq!(
+            indices.iter().collect::<Vec<_>>(),
+            vec![[0, 2], [0, 3], [0, 4], [1, 2], [1, 3], [1, 4]]
+        );
+    }
+
+    #[test]
+    fn test_em_known_1d() {
+        #[rustfmt::skip]
+        let saf = Saf::new(
+            vec![
+                1., 0., 0.,
+                1., 0., 0.,
+                0., 1., 0.,
+                1., 0., 0.,
+            ],
+            3,
+        )
+        .unwrap();
+
+        let estimate = Em::default().estimate(&saf);
+
+        assert!(estimate.converged);
+        assert_approx_eq!(estimate.scs, Scs::new([3., 1., 0.], 3).unwrap());
+    }
+
+    #[test]
+    fn test_em_known_2d() {
+        #[rustfmt::skip]
+        let saf = Saf::new(
+            vec![
+                1., 0., 0., 1.,
+                0., 1., 1., 0.,
+                0., 1., 0., 1.,
+            ],
+            [2, 2],
+        )
+        .unwrap();
+
+        let estimate = Em::default().estimate(&saf);
+
+        assert_approx_eq!(estimate.scs, Scs::new([0., 1., 1., 1.], [2, 2]).unwrap());
+    }
+
+    #[test]
+    fn test_em_uncertain_1d() {
+        // Half the sites are known to be fixed ancestral, the other half are uncertain between
+        // being fixed ancestral or singletons with equal likelihood
+        #[rustfmt::skip]
+        let saf = Saf::new(
+            vec![
+                1., 0., 0.,
+                1., 0., 0.,
+                1., 1., 0.,
+                1., 1., 0.,
+            ],
+            3,
+        )
+        .unwrap();
+
+        let estimate = Em::default().set_max_iterations(1000).estimate(&saf);
+
+        // The maximum likelihood estimate puts all mass on fixed ancestral
+        assert_approx_eq!(
+            estimate.scs,
+            Scs::new([4., 0., 0.], 3).unwrap(),
+            epsilon = 1e-2
+        );
+    }
+
+    #[test]
+    fn test_em_empty() {
+        let estimate = Em::default().estimate(&Saf::from_shape(3));
+
+        assert_eq!(estimate.iterations, 0);
+        assert_eq!(estimate.scs, Scs::from_zeros(3));
+    }
+}
@@ -3,9 +3,13 @@
 use std::{
     fmt,
     marker::PhantomData,
-    ops::{AddAssign, Index, IndexMut, Range},
+    ops::{Add, AddAssign, Index, IndexMut, Mul, Range, Sub},
 };
 
+pub mod bootstrap;
+
+pub mod jackknife;
+
 mod count;
 pub use count::Count;
 
@@ -456,12 +460,61 @@ impl<S: State> fmt::Debug for Spectrum<S> {
     }
 }
 
+/// Element-wise addition, returning an error if the shapes of the spectra differ.
+///
+/// The result is a count spectrum, since the sum of spectra is not normalized in general.
+impl<S: State, T: State> Add<&Spectrum<T>> for Spectrum<S> {
+    type Output = Result<Scs, ShapeError>;
+
+    fn add(self, rhs: &Spectrum<T>) -> Self::Output {
+        (self.array + &rhs.array).map(Scs::from)
+    }
+}
+
+impl<S: State, T: State> Add<Spectrum<T>> for Spectrum<S> {
+    type Output = Result<Scs, ShapeError>;
+
+    fn add(self, rhs: Spectrum<T>) -> Self::Output {
+        self + &rhs
+    }
+}
+
 impl AddAssign<&Count> for Scs {
     fn add_assign(&mut self, count: &Count) {
         self[count] += 1.0;
     }
 }
 
+/// Element-wise multiplication by a scalar.
+///
+/// The result is a count spectrum, since a scaled spectrum is not normalized in general.
+impl<S: State> Mul<f64> for Spectrum<S> {
+    type Output = Scs;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Scs::from(self.array * rhs)
+    }
+}
+
+/// Element-wise subtraction, returning an error if the shapes of the spectra differ.
+///
+/// The result is a count spectrum, since the difference of spectra is not normalized in general.
+impl<S: State, T: State> Sub<&Spectrum<T>> for Spectrum<S> {
+    type Output = Result<Scs, ShapeError>;
+
+    fn sub(self, rhs: &Spectrum<T>) -> Self::Output {
+        (self.array - &rhs.array).map(Scs::from)
+    }
+}
+
+impl<S: State, T: State> Sub<Spectrum<T>> for Spectrum<S> {
+    type Output = Result<Scs, ShapeError>;
+
+    fn sub(self, rhs: Spectrum<T>) -> Self::Output {
+        self - &rhs
+    }
+}
+
 impl From<Array<f64>> for Scs {
     fn from(array: Array<f64>) -> Self {
         Self {
@@ -551,6 +604,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_add_sub_mul() {
+        let scs = Scs::from_range(0..6, [2, 3]).unwrap();
+        let other = Scs::new([1., 1., 1., 2., 2., 2.], [2, 3]).unwrap();
+
+        assert_eq!(
+            (scs.clone() + &other).unwrap(),
+            Scs::new([1., 2., 3., 5., 6., 7.], [2, 3]).unwrap()
+        );
+        assert_eq!(
+            (scs.clone() - other).unwrap(),
+            Scs::new([-1., 0., 1., 1., 2., 3.], [2, 3]).unwrap()
+        );
+        assert_eq!(
+            scs.clone() * 0.5,
+            Scs::new([0., 0.5, 1., 1.5, 2., 2.5], [2, 3]).unwrap()
+        );
+
+        let sfs = Scs::from_vec([1., 3.]).into_normalized();
+        assert_eq!((sfs.clone() + &sfs).unwrap(), Scs::from_vec([0.5, 1.5]));
+    }
+
+    #[test]
+    fn test_add_sub_shape_mismatch() {
+        let scs = Scs::from_range(0..6, [2, 3]).unwrap();
+        let other = Scs::from_range(0..6, [3, 2]).unwrap();
+
+        assert!(matches!(
+            scs.clone() + &other,
+            Err(ShapeError::Mismatch { .. })
+        ));
+        assert!(matches!(scs - other, Err(ShapeError::Mismatch { .. })));
+    }
+
     #[test]
     fn test_marginalize_axis_2d() {
         let scs = Scs::from_range(0..9, [3, 3]).unwrap();
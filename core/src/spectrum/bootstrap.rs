@@ -0,0 +1,70 @@
+//! Block bootstrap of spectra.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::Scs;
+
+/// Returns block bootstrap replicates of the spectrum made up by the provided blocks.
+///
+/// Each replicate is the sum of as many blocks as provided, sampled with replacement. Sampling
+/// uses a random number generator seeded by the provided seed, so that the same replicates are
+/// returned for the same blocks and seed.
+///
+/// # Panics
+///
+/// If no blocks are provided, or if the blocks do not all have the same shape.
+pub fn block_bootstrap(blocks: &[Scs], replicates: usize, seed: u64) -> Vec<Scs> {
+    let first = blocks.first().expect("cannot bootstrap without blocks");
+    assert!(
+        blocks.iter().all(|block| block.shape() == first.shape()),
+        "cannot bootstrap blocks with different shapes"
+    );
+
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    (0..replicates)
+        .map(|_| {
+            let mut replicate = Scs::from_zeros(first.shape().clone());
+
+            for _ in 0..blocks.len() {
+                let block = &blocks[rng.gen_range(0..blocks.len())];
+
+                replicate
+                    .inner_mut()
+                    .iter_mut()
+                    .zip(block.inner().iter())
+                    .for_each(|(x, y)| *x += y);
+            }
+
+            replicate
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_bootstrap() {
+        let blocks = vec![
+            Scs::new([1., 0., 0.], 3).unwrap(),
+            Scs::new([0., 2., 0.], 3).unwrap(),
+            Scs::new([0., 0., 3.], 3).unwrap(),
+        ];
+
+        let replicates = block_bootstrap(&blocks, 10, 1);
+        assert_eq!(replicates.len(), 10);
+
+        for replicate in replicates.iter() {
+            // Each replicate contains three blocks, identified by their counts
+            let [a, b, c] = replicate.inner().as_slice() else {
+                panic!("replicate has wrong shape")
+            };
+            assert_eq!(a + b / 2. + c / 3., 3.);
+        }
+
+        assert_eq!(block_bootstrap(&blocks, 10, 1), replicates);
+        assert_ne!(block_bootstrap(&blocks, 10, 2), replicates);
+    }
+}
@@ -77,6 +77,13 @@ impl<S: State> Folded<S> {
         }
     }
 
+    /// Returns the mask of the folded spectrum in row-major order.
+    ///
+    /// Elements folded onto other elements are masked.
+    pub fn mask(&self) -> Vec<bool> {
+        self.array.iter().map(Option::is_none).collect()
+    }
+
     /// Returns an unfolded spectrum based on the folded spectrum, filling the folded elements with
     /// the provided element.
     pub fn into_spectrum(&self, fill: f64) -> Spectrum<S> {
@@ -1,30 +1,168 @@
 //! Utilities for reading and writing spectrum.
 
+pub(crate) mod angsd;
+pub mod container;
+pub(crate) mod dadi;
+pub mod fsc;
 pub mod read;
-mod text;
+pub(crate) mod text;
 pub mod write;
 
-use crate::array::npy;
+use std::fmt;
+
+use crate::{
+    array::{npy, Axis},
+    input::Region,
+};
+
+/// Optional metadata describing a spectrum.
+///
+/// Metadata is stored in the plain text format and in [`container`]s, and partly in the dadi
+/// format, and is lost when writing other formats.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Metadata {
+    /// The names of the populations, in the order of the dimensions of the spectrum.
+    pub populations: Option<Vec<String>>,
+    /// Whether the spectrum is folded.
+    pub folded: bool,
+    /// Whether the spectrum is polarized by ancestral alleles, rather than the reference.
+    pub polarized: bool,
+    /// Which elements of the spectrum are masked, in flat, row-major order.
+    ///
+    /// Masked elements should be ignored by downstream inference, as in dadi and moments.
+    pub mask: Option<Vec<bool>>,
+    /// The genomic region of the spectrum, e.g. a window along a contig.
+    pub region: Option<Region>,
+}
+
+impl Metadata {
+    /// Returns metadata for a spectrum with the provided axes marginalized out.
+    ///
+    /// The axes are assumed to be valid for the spectrum, see
+    /// [`Spectrum::marginalize`](crate::Spectrum::marginalize). Any mask is removed, since it
+    /// does not apply to the marginalized spectrum.
+    pub fn marginalize(&self, axes: &[Axis]) -> Self {
+        Self {
+            populations: self.populations.as_ref().map(|populations| {
+                populations
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| !axes.contains(&Axis(*i)))
+                    .map(|(_, population)| population.clone())
+                    .collect()
+            }),
+            mask: None,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the axis of the population with the provided name if defined, otherwise `None`.
+    pub fn population_axis(&self, name: &str) -> Option<Axis> {
+        self.populations
+            .as_ref()?
+            .iter()
+            .position(|population| population == name)
+            .map(Axis)
+    }
+}
+
+/// A value associated with a label, e.g. one of several named spectra.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Labelled<T> {
+    /// The label of the value.
+    pub label: String,
+    /// The value.
+    pub value: T,
+}
 
 /// Supported formats.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Format {
+    /// ANGSD `realSFS` plain format.
+    ///
+    /// This format cannot be detected and requires a shape to be provided when reading.
+    Angsd,
+    /// dadi and moments `.fs` format.
+    Dadi,
+    /// fastsimcoal2 `.obs` format.
+    Fsc,
     /// Numpy binary npy format.
     Npy,
+    /// Numpy npz archive of several spectra, see [`container`].
+    ///
+    /// This format can only be used for several spectra.
+    Npz,
     /// Plain text format.
     Text,
 }
 
 impl Format {
     fn detect(bytes: &[u8]) -> Option<Self> {
-        Self::detect_npy(bytes).xor(Self::detect_plain_text(bytes))
+        Self::detect_npy(bytes)
+            .xor(Self::detect_plain_text(bytes))
+            .xor(Self::detect_dadi(bytes))
+            .xor(Self::detect_fsc(bytes))
+            .xor(Self::detect_npz(bytes))
+    }
+
+    fn detect_dadi(bytes: &[u8]) -> Option<Self> {
+        dadi::detect(bytes).then_some(Self::Dadi)
+    }
+
+    fn detect_fsc(bytes: &[u8]) -> Option<Self> {
+        fsc::detect(bytes).then_some(Self::Fsc)
     }
 
     fn detect_npy(bytes: &[u8]) -> Option<Self> {
-        (bytes[..npy::MAGIC.len()] == npy::MAGIC).then_some(Self::Npy)
+        bytes.starts_with(&npy::MAGIC).then_some(Self::Npy)
+    }
+
+    fn detect_npz(bytes: &[u8]) -> Option<Self> {
+        (container::Format::detect(bytes) == Some(container::Format::Npz)).then_some(Self::Npz)
     }
 
     fn detect_plain_text(bytes: &[u8]) -> Option<Self> {
-        (bytes[..text::START.len()] == text::START).then_some(Self::Text)
+        bytes.starts_with(&text::START).then_some(Self::Text)
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::Angsd => "angsd",
+            Format::Dadi => "dadi",
+            Format::Fsc => "fsc",
+            Format::Npy => "npy",
+            Format::Npz => "npz",
+            Format::Text => "text",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_marginalize() {
+        let metadata = Metadata {
+            populations: Some(vec![
+                String::from("a"),
+                String::from("b"),
+                String::from("c"),
+            ]),
+            folded: false,
+            polarized: true,
+            mask: Some(vec![false; 8]),
+            region: None,
+        };
+
+        let marginalized = metadata.marginalize(&[Axis(0), Axis(2)]);
+        assert_eq!(marginalized.populations, Some(vec![String::from("b")]));
+        assert!(marginalized.polarized);
+        assert_eq!(marginalized.mask, None);
+
+        assert_eq!(marginalized.population_axis("b"), Some(Axis(0)));
+        assert_eq!(marginalized.population_axis("a"), None);
     }
 }
@@ -0,0 +1,108 @@
+//! Reading and writing for the ANGSD format.
+//!
+//! The ANGSD format is the plain output format of `realSFS`, also used by `winsfs`. It consists
+//! of a single line giving the spectrum in flat, row-major order separated by whitespace. For
+//! spectra with more than one dimension, this means that the first population varies slowest,
+//! as in the other supported formats. The format has no header, and so the shape of the spectrum
+//! must be provided when reading.
+
+use std::{io, str::FromStr};
+
+use crate::{
+    spectrum::{Shape, State},
+    Scs, Spectrum,
+};
+
+fn invalid_data<E>(e: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Reads an SCS in ANGSD format with the provided shape from a reader.
+///
+/// The stream is assumed to be positioned at the start.
+pub fn read_scs<R>(reader: &mut R, shape: Shape) -> io::Result<Scs>
+where
+    R: io::BufRead,
+{
+    let mut lines = io::BufRead::lines(reader).filter(|line| match line {
+        Ok(line) => !line.trim().is_empty(),
+        Err(_) => true,
+    });
+
+    let line = lines
+        .next()
+        .transpose()?
+        .ok_or_else(|| invalid_data("missing data line in ANGSD format"))?;
+    let data = line
+        .split_ascii_whitespace()
+        .map(f64::from_str)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(invalid_data)?;
+
+    if lines.next().transpose()?.is_some() {
+        return Err(invalid_data(
+            "expected a single spectrum in ANGSD format, found trailing lines",
+        ));
+    }
+
+    Scs::new(data, shape).map_err(invalid_data)
+}
+
+/// Writes a spectrum in ANGSD format to a writer.
+pub fn write_spectrum<W, S: State>(
+    writer: &mut W,
+    spectrum: &Spectrum<S>,
+    precision: usize,
+) -> io::Result<()>
+where
+    W: io::Write,
+{
+    let data_line = spectrum
+        .array
+        .iter()
+        .map(|x| format!("{x:.precision$}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    writeln!(writer, "{data_line}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_scs() -> io::Result<()> {
+        let src = b"0.000000 1.5 2e1 3 4 5 \n";
+
+        assert_eq!(
+            read_scs(&mut &src[..], Shape(vec![2, 3]))?,
+            Scs::new(vec![0., 1.5, 20., 3., 4., 5.], [2, 3]).unwrap()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_scs_invalid() {
+        assert!(read_scs(&mut &b"0 1 2 3\n"[..], Shape(vec![2, 3])).is_err());
+        assert!(read_scs(&mut &b"0 1 2\n3 4 5\n"[..], Shape(vec![3])).is_err());
+        assert!(read_scs(&mut &b""[..], Shape(vec![3])).is_err());
+    }
+
+    #[test]
+    fn test_write_read_roundtrip() -> io::Result<()> {
+        let scs = Scs::from_range(0..6, [3, 2]).unwrap();
+
+        let mut dest = Vec::new();
+        write_spectrum(&mut dest, &scs, 1)?;
+        assert_eq!(dest, b"0.0 1.0 2.0 3.0 4.0 5.0\n");
+
+        assert_eq!(read_scs(&mut &dest[..], Shape(vec![3, 2]))?, scs);
+
+        Ok(())
+    }
+}
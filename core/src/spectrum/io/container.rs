@@ -0,0 +1,472 @@
+//! Reading and writing containers of several labelled spectra.
+//!
+//! A container stores any number of spectra, each with a label and its metadata, and is read and
+//! written one spectrum at a time. Two container formats are supported.
+//!
+//! The npz container is a zip archive as written by `numpy.savez`, where each spectrum is stored
+//! in npy format in an entry named by its label followed by `.npy`. Where the metadata of a
+//! spectrum is not empty, it is stored before the spectrum in an entry named by its label
+//! followed by `.metadata`, containing the header lines of the plain text format. Such entries
+//! are available as raw bytes when loading the archive using `numpy.load`.
+//!
+//! The text container is a sequence of plain text format records, where each record may have a
+//! `#LABEL` header line and its own metadata header lines. A record without a label is labelled
+//! by its region where defined, as for windowed spectra, and otherwise by its 0-based index in the
+//! container. Accordingly, the label is not written where it equals the region.
+
+mod npz;
+
+use std::{collections::HashSet, fmt, io};
+
+use crate::{input::ReadStatus, spectrum::State, Array, Scs, Spectrum};
+
+use super::{
+    text::{self, Header},
+    Labelled, Metadata,
+};
+
+/// The suffix of npz archive entries containing spectra.
+const NPY_SUFFIX: &str = ".npy";
+
+/// The suffix of npz archive entries containing metadata.
+const METADATA_SUFFIX: &str = ".metadata";
+
+/// A labelled spectrum with its metadata.
+pub type Record = Labelled<(Scs, Metadata)>;
+
+/// Supported container formats.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    /// Numpy npz archive.
+    Npz,
+    /// Plain text format with several labelled records.
+    Text,
+}
+
+impl Format {
+    pub(super) fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&npz::MAGIC) {
+            Some(Self::Npz)
+        } else if bytes.starts_with(&text::START) {
+            Some(Self::Text)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::Npz => "npz",
+            Format::Text => "text",
+        })
+    }
+}
+
+fn invalid_data<E>(e: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// A reader of spectra from a container.
+pub struct Reader<R> {
+    inner: ReaderInner<R>,
+}
+
+enum ReaderInner<R> {
+    Npz {
+        reader: npz::Reader<R>,
+        metadata: Option<(String, Header)>,
+    },
+    Text {
+        reader: text::RecordReader<R>,
+        index: usize,
+    },
+}
+
+impl<R> Reader<R>
+where
+    R: io::BufRead,
+{
+    /// Creates a new reader, detecting the container format from the start of the stream.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let format = Format::detect(reader.fill_buf()?)
+            .ok_or_else(|| invalid_data("invalid container format"))?;
+
+        Ok(Self::with_format(reader, format))
+    }
+
+    /// Creates a new reader of the provided container format.
+    pub fn with_format(reader: R, format: Format) -> Self {
+        let inner = match format {
+            Format::Npz => ReaderInner::Npz {
+                reader: npz::Reader::new(reader),
+                metadata: None,
+            },
+            Format::Text => ReaderInner::Text {
+                reader: text::RecordReader::new(reader),
+                index: 0,
+            },
+        };
+
+        Self { inner }
+    }
+
+    /// Returns the container format.
+    pub fn format(&self) -> Format {
+        match self.inner {
+            ReaderInner::Npz { .. } => Format::Npz,
+            ReaderInner::Text { .. } => Format::Text,
+        }
+    }
+
+    /// Reads the next spectrum in the container.
+    pub fn read_record(&mut self) -> ReadStatus<Record> {
+        match self.read_record_inner() {
+            Ok(Some(record)) => ReadStatus::Read(record),
+            Ok(None) => ReadStatus::Done,
+            Err(e) => ReadStatus::Error(e),
+        }
+    }
+
+    /// Reads all remaining spectra in the container.
+    pub fn read_records(&mut self) -> io::Result<Vec<Record>> {
+        let mut records = Vec::new();
+
+        loop {
+            match self.read_record() {
+                ReadStatus::Read(record) => records.push(record),
+                ReadStatus::Error(e) => return Err(e),
+                ReadStatus::Done => return Ok(records),
+            }
+        }
+    }
+
+    /// Reads spectra until the spectrum with the provided label, returning `None` if not found.
+    pub fn find_record(&mut self, label: &str) -> io::Result<Option<Record>> {
+        loop {
+            match self.read_record() {
+                ReadStatus::Read(record) if record.label == label => return Ok(Some(record)),
+                ReadStatus::Read(_) => (),
+                ReadStatus::Error(e) => return Err(e),
+                ReadStatus::Done => return Ok(None),
+            }
+        }
+    }
+
+    fn read_record_inner(&mut self) -> io::Result<Option<Record>> {
+        match &mut self.inner {
+            ReaderInner::Npz { reader, metadata } => loop {
+                let Some(entry) = reader.read_entry()? else {
+                    return match metadata.take() {
+                        Some((label, _)) => Err(invalid_data(format!(
+                            "missing spectrum for metadata with label '{label}'"
+                        ))),
+                        None => Ok(None),
+                    };
+                };
+
+                if let Some(label) = entry.name.strip_suffix(METADATA_SUFFIX) {
+                    let header = std::str::from_utf8(&entry.data)
+                        .map_err(invalid_data)
+                        .and_then(|s| Header::parse(s).map_err(invalid_data))?;
+
+                    if let Some((label, _)) = metadata.replace((label.to_string(), header)) {
+                        return Err(invalid_data(format!(
+                            "missing spectrum for metadata with label '{label}'"
+                        )));
+                    }
+                } else if let Some(label) = entry.name.strip_suffix(NPY_SUFFIX) {
+                    let scs = Array::read_npy(&mut &entry.data[..]).map(Scs::from)?;
+
+                    let metadata = match metadata.take() {
+                        Some((metadata_label, header)) if metadata_label == label => {
+                            if &header.shape != scs.shape() {
+                                return Err(invalid_data(format!(
+                                    "metadata shape does not match spectrum with label '{label}'"
+                                )));
+                            }
+
+                            header.metadata
+                        }
+                        Some((metadata_label, _)) => {
+                            return Err(invalid_data(format!(
+                                "missing spectrum for metadata with label '{metadata_label}'"
+                            )))
+                        }
+                        None => Metadata::default(),
+                    };
+
+                    return Ok(Some(Labelled {
+                        label: label.to_string(),
+                        value: (scs, metadata),
+                    }));
+                } else {
+                    return Err(invalid_data(format!(
+                        "unexpected entry '{}' in npz container",
+                        entry.name
+                    )));
+                }
+            },
+            ReaderInner::Text { reader, index } => match reader.read_record()? {
+                Some((header, scs)) => {
+                    let label = header
+                        .label
+                        .or_else(|| header.metadata.region.as_ref().map(ToString::to_string))
+                        .unwrap_or_else(|| index.to_string());
+                    *index += 1;
+
+                    Ok(Some(Labelled {
+                        label,
+                        value: (scs, header.metadata),
+                    }))
+                }
+                None => Ok(None),
+            },
+        }
+    }
+}
+
+/// A writer of spectra to a container.
+///
+/// The container must be finished using [`Writer::finish`] after writing all spectra.
+pub struct Writer<W> {
+    inner: WriterInner<W>,
+    labels: HashSet<String>,
+}
+
+enum WriterInner<W> {
+    Npz(npz::Writer<W>),
+    Text { writer: W, precision: usize },
+}
+
+impl<W> Writer<W>
+where
+    W: io::Write,
+{
+    /// Creates a new writer of the provided container format.
+    pub fn new(writer: W, format: Format) -> Self {
+        let inner = match format {
+            Format::Npz => WriterInner::Npz(npz::Writer::new(writer)),
+            Format::Text => WriterInner::Text {
+                writer,
+                precision: 6,
+            },
+        };
+
+        Self {
+            inner,
+            labels: HashSet::new(),
+        }
+    }
+
+    /// Set precision.
+    ///
+    /// This is only used for the text container format.
+    /// If unset, a precision of six digits will be used.
+    pub fn set_precision(mut self, precision: usize) -> Self {
+        if let WriterInner::Text {
+            precision: current, ..
+        } = &mut self.inner
+        {
+            *current = precision;
+        }
+
+        self
+    }
+
+    /// Writes a spectrum with its label and metadata to the container.
+    ///
+    /// Labels must be unique within a container.
+    pub fn write_record<S: State>(
+        &mut self,
+        label: &str,
+        spectrum: &Spectrum<S>,
+        metadata: &Metadata,
+    ) -> io::Result<()> {
+        if !self.labels.insert(label.to_string()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("duplicate label '{label}' in container"),
+            ));
+        }
+
+        match &mut self.inner {
+            WriterInner::Npz(writer) => {
+                if metadata != &Metadata::default() {
+                    let mut header = Header::new(spectrum.shape().clone());
+                    header.metadata = metadata.clone();
+
+                    let mut data = Vec::new();
+                    header.write(&mut data)?;
+                    writer.write_entry(&format!("{label}{METADATA_SUFFIX}"), &data)?;
+                }
+
+                let mut data = Vec::new();
+                spectrum.array.write_npy(&mut data)?;
+                writer.write_entry(&format!("{label}{NPY_SUFFIX}"), &data)
+            }
+            WriterInner::Text { writer, precision } => {
+                let label = match &metadata.region {
+                    Some(region) if region.to_string() == label => None,
+                    _ => Some(label),
+                };
+
+                text::write_record(writer, label, spectrum, metadata, *precision)
+            }
+        }
+    }
+
+    /// Finishes the container, returning the inner writer.
+    pub fn finish(self) -> io::Result<W> {
+        match self.inner {
+            WriterInner::Npz(writer) => writer.finish(),
+            WriterInner::Text { mut writer, .. } => {
+                writer.flush()?;
+                Ok(writer)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::input::Region;
+
+    fn records() -> Vec<Record> {
+        vec![
+            Labelled {
+                label: String::from("C>T"),
+                value: (
+                    Scs::from_range(0..6, [2, 3]).unwrap(),
+                    Metadata {
+                        populations: Some(vec![String::from("a"), String::from("b")]),
+                        folded: true,
+                        mask: Some(vec![true, false, false, false, false, true]),
+                        region: Some(Region::new("chr1", 1, Some(10))),
+                        ..Default::default()
+                    },
+                ),
+            },
+            Labelled {
+                label: String::from("T>C"),
+                value: (Scs::from_range(6..9, 3).unwrap(), Metadata::default()),
+            },
+        ]
+    }
+
+    fn write_read_roundtrip(format: Format) -> io::Result<()> {
+        let mut writer = Writer::new(Vec::new(), format).set_precision(0);
+        for Labelled {
+            label,
+            value: (scs, metadata),
+        } in records()
+        {
+            writer.write_record(&label, &scs, &metadata)?;
+        }
+        let dest = writer.finish()?;
+
+        let mut reader = Reader::new(&dest[..])?;
+        assert_eq!(reader.format(), format);
+
+        assert_eq!(reader.read_records()?, records());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_read_roundtrip_npz() -> io::Result<()> {
+        write_read_roundtrip(Format::Npz)
+    }
+
+    #[test]
+    fn test_write_read_roundtrip_text() -> io::Result<()> {
+        write_read_roundtrip(Format::Text)
+    }
+
+    #[test]
+    fn test_find_record() -> io::Result<()> {
+        let mut writer = Writer::new(Vec::new(), Format::Npz);
+        for Labelled {
+            label,
+            value: (scs, metadata),
+        } in records()
+        {
+            writer.write_record(&label, &scs, &metadata)?;
+        }
+        let dest = writer.finish()?;
+
+        assert_eq!(
+            Reader::new(&dest[..])?.find_record("T>C")?,
+            Some(records().remove(1))
+        );
+        assert_eq!(Reader::new(&dest[..])?.find_record("A>G")?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_duplicate_label() {
+        let scs = Scs::from_range(0..3, 3).unwrap();
+
+        let mut writer = Writer::new(Vec::new(), Format::Npz);
+        writer
+            .write_record("a", &scs, &Metadata::default())
+            .unwrap();
+        assert!(writer
+            .write_record("a", &scs, &Metadata::default())
+            .is_err());
+    }
+
+    #[test]
+    fn test_write_text_region_label() -> io::Result<()> {
+        let region = Region::new("chr1", 1, Some(10));
+        let metadata = Metadata {
+            region: Some(region.clone()),
+            ..Default::default()
+        };
+
+        let mut writer = Writer::new(Vec::new(), Format::Text).set_precision(0);
+        writer.write_record(
+            &region.to_string(),
+            &Scs::from_range(0..2, 2).unwrap(),
+            &metadata,
+        )?;
+        writer.write_record("a", &Scs::from_range(2..4, 2).unwrap(), &metadata)?;
+        let dest = writer.finish()?;
+
+        assert_eq!(
+            dest,
+            b"#SHAPE=<2>\n#REGION=<chr1:1-10>\n0 1\n#SHAPE=<2>\n#REGION=<chr1:1-10>\n#LABEL=<a>\n2 3\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_text_default_labels() -> io::Result<()> {
+        let src =
+            b"#SHAPE=<2>\n#LABEL=<a>\n0 1\n#SHAPE=<2>\n#REGION=<chr1:1-10>\n2 3\n#SHAPE=<2>\n4 5\n";
+
+        let labels = Reader::new(&src[..])?
+            .read_records()?
+            .into_iter()
+            .map(|record| record.label)
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["a", "chr1:1-10", "2"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Format::detect(&npz::MAGIC), Some(Format::Npz));
+        assert_eq!(Format::detect(b"#SHAPE=<2>\n"), Some(Format::Text));
+        assert_eq!(Format::detect(b"5 unfolded\n"), None);
+    }
+}
@@ -0,0 +1,420 @@
+//! Reading and writing of npz archives.
+//!
+//! An npz archive is a zip archive of arrays in npy format, as written by `numpy.savez`. Only
+//! the subset of the zip format required for such archives is supported. When writing, entries
+//! are stored uncompressed without any zip64 extensions, as by `numpy.savez`. When reading,
+//! entries may in addition be deflated, as by `numpy.savez_compressed`, and have zip64 sizes,
+//! but local file headers must give the sizes of entries.
+
+use std::io::{self, Read};
+
+use flate2::{read::DeflateDecoder, Crc};
+
+/// The signature of a local file header.
+const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x04034b50;
+
+/// The signature of a central directory file header.
+const CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x02014b50;
+
+/// The signature of the end of central directory record.
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+
+/// The magic bytes at the start of a zip archive.
+pub(crate) const MAGIC: [u8; 4] = LOCAL_FILE_HEADER_SIGNATURE.to_le_bytes();
+
+/// The zip version required to extract entries written here.
+const VERSION: u16 = 20;
+
+/// The general purpose flag bit for a trailing data descriptor.
+const DATA_DESCRIPTOR_FLAG: u16 = 1 << 3;
+
+/// The general purpose flag bit for UTF-8 encoded names.
+const UTF8_FLAG: u16 = 1 << 11;
+
+/// The compression method for stored entries.
+const STORED: u16 = 0;
+
+/// The compression method for deflated entries.
+const DEFLATED: u16 = 8;
+
+/// The MS-DOS date of 1980-01-01, the earliest representable date, used for all entries.
+const DATE: u16 = (1 << 5) | 1;
+
+/// The header ID of the zip64 extended information extra field.
+const ZIP64_EXTRA_FIELD_ID: u16 = 0x0001;
+
+/// The size marking that the actual size is given in the zip64 extra field.
+const ZIP64_SIZE: u32 = u32::MAX;
+
+fn invalid_data<E>(e: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+fn read_u16<R: io::Read>(reader: &mut R) -> io::Result<u16> {
+    let mut buf = [0; 2];
+    reader.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32<R: io::Read>(reader: &mut R) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: io::Read>(reader: &mut R) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// An entry in an npz archive.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(super) struct Entry {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// A reader of npz archive entries one at a time.
+pub(super) struct Reader<R> {
+    inner: R,
+    done: bool,
+}
+
+impl<R> Reader<R>
+where
+    R: io::Read,
+{
+    pub fn new(inner: R) -> Self {
+        Self { inner, done: false }
+    }
+
+    /// Reads the next entry, returning `None` when there are no more entries.
+    ///
+    /// The central directory is not read, since local file headers are sufficient to read the
+    /// entries in order.
+    pub fn read_entry(&mut self) -> io::Result<Option<Entry>> {
+        if self.done {
+            return Ok(None);
+        }
+
+        match read_u32(&mut self.inner)? {
+            LOCAL_FILE_HEADER_SIGNATURE => (),
+            CENTRAL_DIRECTORY_SIGNATURE | END_OF_CENTRAL_DIRECTORY_SIGNATURE => {
+                self.done = true;
+                return Ok(None);
+            }
+            signature => {
+                return Err(invalid_data(format!(
+                    "unexpected signature {signature:#010x} in npz archive"
+                )))
+            }
+        }
+
+        let _version = read_u16(&mut self.inner)?;
+        let flags = read_u16(&mut self.inner)?;
+        let method = read_u16(&mut self.inner)?;
+        let _time = read_u16(&mut self.inner)?;
+        let _date = read_u16(&mut self.inner)?;
+        let crc = read_u32(&mut self.inner)?;
+        let compressed_size = read_u32(&mut self.inner)?;
+        let uncompressed_size = read_u32(&mut self.inner)?;
+        let name_len = read_u16(&mut self.inner)?;
+        let extra_len = read_u16(&mut self.inner)?;
+
+        let mut name = vec![0; usize::from(name_len)];
+        self.inner.read_exact(&mut name)?;
+        let name = String::from_utf8(name).map_err(invalid_data)?;
+
+        let mut extra = vec![0; usize::from(extra_len)];
+        self.inner.read_exact(&mut extra)?;
+
+        if flags & DATA_DESCRIPTOR_FLAG != 0 {
+            return Err(invalid_data(format!(
+                "npz archive entry '{name}' with trailing data descriptor not supported"
+            )));
+        }
+
+        let (compressed_size, uncompressed_size) =
+            if compressed_size == ZIP64_SIZE || uncompressed_size == ZIP64_SIZE {
+                read_zip64_sizes(&extra).ok_or_else(|| {
+                    invalid_data(format!(
+                        "missing zip64 sizes for npz archive entry '{name}'"
+                    ))
+                })?
+            } else {
+                (u64::from(compressed_size), u64::from(uncompressed_size))
+            };
+
+        let mut data = Vec::with_capacity(usize::try_from(uncompressed_size).unwrap_or(0));
+        let mut raw = (&mut self.inner).take(compressed_size);
+        match method {
+            STORED => {
+                raw.read_to_end(&mut data)?;
+            }
+            DEFLATED => {
+                let mut decoder = DeflateDecoder::new(raw);
+                decoder.read_to_end(&mut data)?;
+                // Skip any trailing compressed bytes to be positioned at the next entry
+                io::copy(&mut decoder.into_inner(), &mut io::sink())?;
+            }
+            method => {
+                return Err(invalid_data(format!(
+                    "unsupported compression method {method} for npz archive entry '{name}'"
+                )))
+            }
+        }
+
+        let mut actual_crc = Crc::new();
+        actual_crc.update(&data);
+        if data.len() as u64 != uncompressed_size || actual_crc.sum() != crc {
+            return Err(invalid_data(format!("corrupt npz archive entry '{name}'")));
+        }
+
+        Ok(Some(Entry { name, data }))
+    }
+}
+
+/// Returns the compressed and uncompressed sizes from the zip64 extra field of a local file
+/// header, if present.
+fn read_zip64_sizes(mut extra: &[u8]) -> Option<(u64, u64)> {
+    while extra.len() >= 4 {
+        let id = read_u16(&mut extra).ok()?;
+        let size = usize::from(read_u16(&mut extra).ok()?);
+        let (mut field, rest) = (extra.get(..size)?, extra.get(size..)?);
+
+        if id == ZIP64_EXTRA_FIELD_ID {
+            let uncompressed_size = read_u64(&mut field).ok()?;
+            let compressed_size = read_u64(&mut field).ok()?;
+
+            return Some((compressed_size, uncompressed_size));
+        }
+
+        extra = rest;
+    }
+
+    None
+}
+
+/// A central directory record of an entry already written.
+#[derive(Debug)]
+struct Record {
+    name: String,
+    crc: u32,
+    size: u32,
+    offset: u32,
+}
+
+/// A writer of npz archive entries one at a time.
+///
+/// The archive must be finished using [`Writer::finish`] after writing all entries.
+pub(super) struct Writer<W> {
+    inner: W,
+    offset: u64,
+    records: Vec<Record>,
+}
+
+impl<W> Writer<W>
+where
+    W: io::Write,
+{
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            offset: 0,
+            records: Vec::new(),
+        }
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.inner.write_all(buf)?;
+        self.offset += buf.len() as u64;
+        Ok(())
+    }
+
+    /// Writes an uncompressed entry.
+    pub fn write_entry(&mut self, name: &str, data: &[u8]) -> io::Result<()> {
+        let too_large = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("npz archive entry '{name}' too large"),
+            )
+        };
+
+        let size = u32::try_from(data.len()).map_err(|_| too_large())?;
+        let offset = u32::try_from(self.offset).map_err(|_| too_large())?;
+        let name_len = u16::try_from(name.len()).map_err(|_| too_large())?;
+
+        let mut crc = Crc::new();
+        crc.update(data);
+        let record = Record {
+            name: name.to_string(),
+            crc: crc.sum(),
+            size,
+            offset,
+        };
+
+        let mut header = Vec::with_capacity(30 + name.len());
+        header.extend(LOCAL_FILE_HEADER_SIGNATURE.to_le_bytes());
+        header.extend(VERSION.to_le_bytes());
+        header.extend(UTF8_FLAG.to_le_bytes());
+        header.extend(STORED.to_le_bytes());
+        header.extend(0u16.to_le_bytes());
+        header.extend(DATE.to_le_bytes());
+        header.extend(record.crc.to_le_bytes());
+        header.extend(size.to_le_bytes());
+        header.extend(size.to_le_bytes());
+        header.extend(name_len.to_le_bytes());
+        header.extend(0u16.to_le_bytes());
+        header.extend(name.as_bytes());
+
+        self.write_all(&header)?;
+        self.write_all(data)?;
+        self.records.push(record);
+
+        Ok(())
+    }
+
+    /// Writes the central directory to finish the archive, returning the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "npz archive too large");
+
+        let entries = u16::try_from(self.records.len()).map_err(|_| too_large())?;
+        let start = u32::try_from(self.offset).map_err(|_| too_large())?;
+
+        let mut directory = Vec::new();
+        for record in self.records.iter() {
+            directory.extend(CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+            directory.extend(VERSION.to_le_bytes());
+            directory.extend(VERSION.to_le_bytes());
+            directory.extend(UTF8_FLAG.to_le_bytes());
+            directory.extend(STORED.to_le_bytes());
+            directory.extend(0u16.to_le_bytes());
+            directory.extend(DATE.to_le_bytes());
+            directory.extend(record.crc.to_le_bytes());
+            directory.extend(record.size.to_le_bytes());
+            directory.extend(record.size.to_le_bytes());
+            directory.extend((record.name.len() as u16).to_le_bytes());
+            // Extra field length, comment length, disk number, and internal attributes
+            directory.extend([0; 8]);
+            // External attributes
+            directory.extend([0; 4]);
+            directory.extend(record.offset.to_le_bytes());
+            directory.extend(record.name.as_bytes());
+        }
+        let size = u32::try_from(directory.len()).map_err(|_| too_large())?;
+
+        directory.extend(END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        // Disk numbers
+        directory.extend([0; 4]);
+        directory.extend(entries.to_le_bytes());
+        directory.extend(entries.to_le_bytes());
+        directory.extend(size.to_le_bytes());
+        directory.extend(start.to_le_bytes());
+        // Comment length
+        directory.extend([0; 2]);
+
+        self.write_all(&directory)?;
+        self.inner.flush()?;
+
+        Ok(self.inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    use flate2::{write::DeflateEncoder, Compression};
+
+    #[test]
+    fn test_write_read_roundtrip() -> io::Result<()> {
+        let mut writer = Writer::new(Vec::new());
+        writer.write_entry("a.npy", b"foo")?;
+        writer.write_entry("b/c.npy", b"")?;
+        let dest = writer.finish()?;
+
+        assert!(dest.starts_with(&MAGIC));
+        // The end of central directory record is 22 bytes and gives the number of entries
+        let end = &dest[dest.len() - 22..];
+        assert_eq!(&end[..4], END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        assert_eq!(&end[8..12], [2, 0, 2, 0]);
+
+        let mut reader = Reader::new(&dest[..]);
+        assert_eq!(
+            reader.read_entry()?,
+            Some(Entry {
+                name: String::from("a.npy"),
+                data: b"foo".to_vec()
+            })
+        );
+        assert_eq!(
+            reader.read_entry()?,
+            Some(Entry {
+                name: String::from("b/c.npy"),
+                data: Vec::new()
+            })
+        );
+        assert_eq!(reader.read_entry()?, None);
+        assert_eq!(reader.read_entry()?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_deflated_zip64() -> io::Result<()> {
+        let data = b"foofoofoofoo";
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        let compressed = encoder.finish()?;
+
+        let mut crc = Crc::new();
+        crc.update(data);
+
+        let mut src = Vec::new();
+        src.extend(LOCAL_FILE_HEADER_SIGNATURE.to_le_bytes());
+        src.extend(45u16.to_le_bytes());
+        src.extend(0u16.to_le_bytes());
+        src.extend(DEFLATED.to_le_bytes());
+        src.extend([0; 4]);
+        src.extend(crc.sum().to_le_bytes());
+        src.extend(ZIP64_SIZE.to_le_bytes());
+        src.extend(ZIP64_SIZE.to_le_bytes());
+        src.extend(1u16.to_le_bytes());
+        src.extend(20u16.to_le_bytes());
+        src.extend(b"a");
+        src.extend(ZIP64_EXTRA_FIELD_ID.to_le_bytes());
+        src.extend(16u16.to_le_bytes());
+        src.extend((data.len() as u64).to_le_bytes());
+        src.extend((compressed.len() as u64).to_le_bytes());
+        src.extend(&compressed);
+        src.extend(END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+
+        let mut reader = Reader::new(&src[..]);
+        assert_eq!(
+            reader.read_entry()?.map(|entry| entry.data),
+            Some(data.to_vec())
+        );
+        assert_eq!(reader.read_entry()?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_corrupt() {
+        let mut writer = Writer::new(Vec::new());
+        writer.write_entry("a.npy", b"foo").unwrap();
+        let mut dest = writer.finish().unwrap();
+
+        // Flip a byte of the data of the first entry
+        dest[30 + 5] ^= 1;
+
+        assert!(Reader::new(&dest[..]).read_entry().is_err());
+    }
+}
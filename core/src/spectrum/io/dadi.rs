@@ -0,0 +1,333 @@
+//! Reading and writing for the dadi format.
+//!
+//! The dadi format is the `.fs` format used by dadi and moments. Any number of leading comment
+//! lines starting with `#` are followed by three lines. The first line gives the shape of the
+//! spectrum separated by a single space, optionally followed by `folded` or `unfolded`, and by the
+//! population names in double quotes. The next line gives the spectrum in flat, row-major order
+//! separated by a single space. The final line gives the mask in the same order, where `1` marks
+//! a masked element and `0` an unmasked element. The mask line is optional when reading.
+
+use std::{fmt::Write, io, str::FromStr};
+
+use crate::{
+    spectrum::{Shape, State},
+    Scs, Spectrum,
+};
+
+use super::Metadata;
+
+/// The dadi format comment start character.
+const COMMENT: char = '#';
+
+/// The folded keyword in the dadi format shape line.
+const FOLDED: &str = "folded";
+
+/// The unfolded keyword in the dadi format shape line.
+const UNFOLDED: &str = "unfolded";
+
+/// The delimiter around population names in the dadi format shape line.
+const QUOTE: char = '"';
+
+fn invalid_data<E>(e: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Returns true if the bytes look like the start of the dadi format.
+///
+/// The dadi format starts with either a comment line, or a shape line starting with a digit
+/// that is not a fastsimcoal2 observations line.
+pub(crate) fn detect(bytes: &[u8]) -> bool {
+    match bytes.first() {
+        Some(b'#') => !bytes.starts_with(&super::text::START),
+        Some(byte) => byte.is_ascii_digit() && !super::fsc::detect(bytes),
+        None => false,
+    }
+}
+
+/// Reads an SCS in dadi format with its metadata from a reader.
+///
+/// The stream is assumed to be positioned at the start. Where the mask line is missing, no
+/// mask is set in the metadata.
+pub fn read_scs_with_metadata<R>(reader: &mut R) -> io::Result<(Scs, Metadata)>
+where
+    R: io::BufRead,
+{
+    let mut lines = io::BufRead::lines(reader).filter(|line| match line {
+        Ok(line) => !(line.starts_with(COMMENT) || line.trim().is_empty()),
+        Err(_) => true,
+    });
+
+    let shape_line = lines
+        .next()
+        .transpose()?
+        .ok_or_else(|| invalid_data("missing shape line in dadi format"))?;
+    let (shape, mut metadata) = parse_shape_line(&shape_line)?;
+
+    let data_line = lines
+        .next()
+        .transpose()?
+        .ok_or_else(|| invalid_data("missing data line in dadi format"))?;
+    let data = data_line
+        .split_ascii_whitespace()
+        .map(f64::from_str)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(invalid_data)?;
+    let scs = Scs::new(data, shape).map_err(invalid_data)?;
+
+    if let Some(mask_line) = lines.next().transpose()? {
+        let mask = mask_line
+            .split_ascii_whitespace()
+            .map(|s| match s {
+                "0" => Ok(false),
+                "1" => Ok(true),
+                s => Err(invalid_data(format!(
+                    "invalid mask value '{s}' in dadi format"
+                ))),
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        if mask.len() != scs.elements() {
+            return Err(invalid_data(format!(
+                "found {} mask values for spectrum with {} elements in dadi format",
+                mask.len(),
+                scs.elements()
+            )));
+        }
+
+        metadata.mask = Some(mask);
+    }
+
+    if lines.next().transpose()?.is_some() {
+        return Err(invalid_data(
+            "expected a single spectrum in dadi format, found trailing lines",
+        ));
+    }
+
+    Ok((scs, metadata))
+}
+
+fn parse_shape_line(s: &str) -> io::Result<(Shape, Metadata)> {
+    let invalid = || invalid_data(format!("failed to parse '{s}' as dadi format shape line"));
+
+    let (head, populations) = match s.split_once(QUOTE) {
+        Some((head, rest)) => {
+            // Names are found between every other pair of quotes
+            let populations = rest
+                .split(QUOTE)
+                .step_by(2)
+                .map(String::from)
+                .collect::<Vec<_>>();
+
+            (head, Some(populations))
+        }
+        None => (s, None),
+    };
+
+    let mut shape = Vec::new();
+    let mut folded = false;
+    for token in head.split_ascii_whitespace() {
+        match token {
+            FOLDED => folded = true,
+            UNFOLDED => folded = false,
+            token => shape.push(token.parse().map_err(|_| invalid())?),
+        }
+    }
+
+    if shape.is_empty() {
+        return Err(invalid());
+    }
+
+    if let Some(populations) = &populations {
+        if populations.len() != shape.len() {
+            return Err(invalid_data(format!(
+                "found {} population names for spectrum with {} dimensions in dadi format",
+                populations.len(),
+                shape.len()
+            )));
+        }
+    }
+
+    let metadata = Metadata {
+        populations,
+        folded,
+        ..Default::default()
+    };
+
+    Ok((Shape(shape), metadata))
+}
+
+/// Returns the mask to write for a spectrum with the provided metadata.
+///
+/// Where the metadata has no mask, the corners of the spectrum are masked as by default in dadi,
+/// together with the elements folded onto other elements if the spectrum is folded.
+fn mask<S: State>(spectrum: &Spectrum<S>, metadata: &Metadata) -> io::Result<Vec<bool>> {
+    match &metadata.mask {
+        Some(mask) if mask.len() == spectrum.elements() => Ok(mask.clone()),
+        Some(mask) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "cannot write mask with {} values for spectrum with {} elements",
+                mask.len(),
+                spectrum.elements()
+            ),
+        )),
+        None => {
+            let mut mask = if metadata.folded {
+                spectrum.fold().mask()
+            } else {
+                vec![false; spectrum.elements()]
+            };
+
+            if let Some(first) = mask.first_mut() {
+                *first = true;
+            }
+            if let Some(last) = mask.last_mut() {
+                *last = true;
+            }
+
+            Ok(mask)
+        }
+    }
+}
+
+/// Writes a spectrum with its metadata in dadi format to a writer.
+pub fn write_spectrum<W, S: State>(
+    writer: &mut W,
+    spectrum: &Spectrum<S>,
+    metadata: &Metadata,
+    precision: usize,
+) -> io::Result<()>
+where
+    W: io::Write,
+{
+    let mut shape_line = spectrum
+        .shape()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    shape_line.push(' ');
+    shape_line.push_str(if metadata.folded { FOLDED } else { UNFOLDED });
+
+    if let Some(populations) = &metadata.populations {
+        for population in populations {
+            write!(shape_line, " {QUOTE}{population}{QUOTE}").unwrap();
+        }
+    }
+
+    let data_line = spectrum
+        .array
+        .iter()
+        .map(|x| format!("{x:.precision$}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mask_line = mask(spectrum, metadata)?
+        .into_iter()
+        .map(|masked| if masked { "1" } else { "0" })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    writeln!(writer, "{shape_line}")?;
+    writeln!(writer, "{data_line}")?;
+    writeln!(writer, "{mask_line}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert!(detect(b"5 unfolded\n0 1 2 3 4\n"));
+        assert!(detect(b"# comment\n5 unfolded\n"));
+        assert!(!detect(b"#SHAPE=<5>\n0 1 2 3 4\n"));
+        assert!(!detect(b""));
+    }
+
+    #[test]
+    fn test_read_scs_with_metadata() -> io::Result<()> {
+        let src = b"# Comment\n\
+            2 3 folded \"pop a\" \"b\"\n\
+            0 1 2 3 4 5\n\
+            1 0 0 0 1 1\n";
+
+        let (scs, metadata) = read_scs_with_metadata(&mut &src[..])?;
+
+        assert_eq!(scs, Scs::from_range(0..6, [2, 3]).unwrap());
+        assert_eq!(
+            metadata,
+            Metadata {
+                populations: Some(vec![String::from("pop a"), String::from("b")]),
+                folded: true,
+                polarized: false,
+                mask: Some(vec![true, false, false, false, true, true]),
+                region: None,
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_scs_without_mask() -> io::Result<()> {
+        let (scs, metadata) = read_scs_with_metadata(&mut &b"3\n0 1 2\n"[..])?;
+
+        assert_eq!(scs, Scs::from_range(0..3, 3).unwrap());
+        assert_eq!(metadata, Metadata::default());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_scs_invalid() {
+        assert!(read_scs_with_metadata(&mut &b"3\n0 1\n"[..]).is_err());
+        assert!(read_scs_with_metadata(&mut &b"3\n0 1 2\n0 1\n"[..]).is_err());
+        assert!(read_scs_with_metadata(&mut &b"3 \"a\" \"b\"\n0 1 2\n"[..]).is_err());
+        assert!(read_scs_with_metadata(&mut &b"3\n0 1 2\n0 0 0\n0 1 2\n"[..]).is_err());
+    }
+
+    #[test]
+    fn test_write_spectrum_default_mask() -> io::Result<()> {
+        let scs = Scs::from_range(0..5, 5).unwrap();
+
+        let mut dest = Vec::new();
+        write_spectrum(&mut dest, &scs, &Metadata::default(), 0)?;
+        assert_eq!(dest, b"5 unfolded\n0 1 2 3 4\n1 0 0 0 1\n");
+
+        let folded = Metadata {
+            folded: true,
+            ..Default::default()
+        };
+        let mut dest = Vec::new();
+        write_spectrum(&mut dest, &scs.fold().into_spectrum(0.0), &folded, 0)?;
+        assert_eq!(dest, b"5 folded\n4 4 2 0 0\n1 0 0 1 1\n");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_read_roundtrip() -> io::Result<()> {
+        let scs = Scs::from_range(0..6, [3, 2]).unwrap();
+        let metadata = Metadata {
+            populations: Some(vec![String::from("a"), String::from("b")]),
+            mask: Some(vec![false, true, false, false, false, false]),
+            ..Default::default()
+        };
+
+        let mut dest = Vec::new();
+        write_spectrum(&mut dest, &scs, &metadata, 0)?;
+        assert_eq!(
+            dest,
+            b"3 2 unfolded \"a\" \"b\"\n0 1 2 3 4 5\n0 1 0 0 0 0\n"
+        );
+
+        assert_eq!(read_scs_with_metadata(&mut &dest[..])?, (scs, metadata));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,324 @@
+//! Reading and writing for the fastsimcoal2 format.
+//!
+//! The fastsimcoal2 format is the `.obs` format used by fastsimcoal2, which differs by the number
+//! of dimensions. All variants start with a line giving the number of observations, which must be
+//! one here, and are tab-separated.
+//!
+//! A 1-dimensional spectrum is given by a line of column labels `d0_[i]` followed by a line of
+//! values. A 2-dimensional spectrum is given by a line of column labels `d0_[i]` for the first
+//! population, prefixed by a tab, followed by one line per derived allele count `j` in the second
+//! population starting with the row label `d1_[j]`. Hence, rows correspond to the second
+//! dimension and columns to the first. A spectrum with more dimensions is given as a
+//! multi-dimensional SFS, where the observation line is followed by a line with the number of
+//! populations and the number of haploid samples in each, followed by a single line of values
+//! with the derived allele count in the first population varying fastest.
+
+use std::{io, str::FromStr};
+
+use crate::{
+    array::{Axis, Shape},
+    spectrum::State,
+    Array, Scs, Spectrum,
+};
+
+use super::Metadata;
+
+/// The fastsimcoal2 format observations line start.
+const OBSERVATIONS: &str = "1 observations";
+
+/// The fastsimcoal2 format observations line for multi-dimensional spectra.
+const MULTI_OBSERVATIONS: &str = "1 observations. No. of demes and sample sizes are on next line";
+
+/// The fastsimcoal2 format delimiter.
+const DELIMITER: char = '\t';
+
+fn invalid_data<E>(e: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Returns true if the bytes look like the start of the fastsimcoal2 format.
+pub(crate) fn detect(bytes: &[u8]) -> bool {
+    bytes
+        .split(|&b| b == b'\n')
+        .next()
+        .and_then(|line| std::str::from_utf8(line).ok())
+        .is_some_and(|line| {
+            line.split_ascii_whitespace()
+                .nth(1)
+                .is_some_and(|word| word.starts_with("observations"))
+        })
+}
+
+/// Returns the fastsimcoal2 file name suffix for a spectrum with the provided number of
+/// dimensions.
+///
+/// fastsimcoal2 expects files named by the prefix of the model template file followed by this
+/// suffix, e.g. `_DAFpop0.obs` for an unfolded 1-dimensional spectrum, `_jointMAFpop1_0.obs` for
+/// a folded 2-dimensional spectrum, or `_MSFS.obs` for a spectrum with more dimensions.
+pub fn file_suffix(dimensions: usize, folded: bool) -> String {
+    let kind = if folded { "MAF" } else { "DAF" };
+
+    match dimensions {
+        1 => format!("_{kind}pop0.obs"),
+        2 => format!("_joint{kind}pop1_0.obs"),
+        _ => String::from("_MSFS.obs"),
+    }
+}
+
+fn parse_values(s: &str) -> io::Result<Vec<f64>> {
+    s.split_ascii_whitespace()
+        .map(f64::from_str)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(invalid_data)
+}
+
+/// Reads an SCS in fastsimcoal2 format with its metadata from a reader.
+///
+/// The stream is assumed to be positioned at the start. The file name carries whether the
+/// spectrum is folded, so the metadata is always empty.
+pub fn read_scs_with_metadata<R>(reader: &mut R) -> io::Result<(Scs, Metadata)>
+where
+    R: io::BufRead,
+{
+    let mut lines = io::BufRead::lines(reader).filter(|line| match line {
+        Ok(line) => !line.trim().is_empty(),
+        Err(_) => true,
+    });
+    let mut next_line = |name: &str| {
+        lines
+            .next()
+            .transpose()?
+            .ok_or_else(|| invalid_data(format!("missing {name} line in fastsimcoal2 format")))
+    };
+
+    let observations = next_line("observations")?;
+    if !observations.starts_with(OBSERVATIONS) {
+        return Err(invalid_data(format!(
+            "expected a single observation in fastsimcoal2 format, found '{observations}'"
+        )));
+    }
+
+    let scs = if observations.starts_with(MULTI_OBSERVATIONS) {
+        let sizes = next_line("sample sizes")?
+            .split_ascii_whitespace()
+            .map(usize::from_str)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(invalid_data)?;
+        let Some((&demes, sizes)) = sizes.split_first() else {
+            return Err(invalid_data(
+                "empty sample sizes line in fastsimcoal2 format",
+            ));
+        };
+        if demes != sizes.len() {
+            return Err(invalid_data(format!(
+                "found {} sample sizes for {demes} demes in fastsimcoal2 format",
+                sizes.len()
+            )));
+        }
+
+        // The first population varies fastest, so the reversed shape is row-major
+        let shape = Shape(sizes.iter().rev().map(|n| n + 1).collect());
+        let values = parse_values(&next_line("values")?)?;
+        let array = Array::new(values, shape).map_err(invalid_data)?;
+
+        Scs::from(reverse_axes(&array))
+    } else {
+        let labels = next_line("labels")?;
+
+        if labels.starts_with(DELIMITER) {
+            let columns = labels.split_ascii_whitespace().count();
+
+            let mut transposed = Vec::new();
+            let mut rows = 0;
+            for line in lines {
+                let line = line?;
+                let mut fields = line.split_ascii_whitespace();
+                fields.next();
+
+                let values = parse_values(&fields.collect::<Vec<_>>().join(" "))?;
+                if values.len() != columns {
+                    return Err(invalid_data(format!(
+                        "found {} values in row with {columns} columns in fastsimcoal2 format",
+                        values.len()
+                    )));
+                }
+
+                transposed.extend(values);
+                rows += 1;
+            }
+
+            let array = Array::new(transposed, [rows, columns]).map_err(invalid_data)?;
+            Scs::from(reverse_axes(&array))
+        } else {
+            let values = parse_values(&next_line("values")?)?;
+            if lines.next().transpose()?.is_some() {
+                return Err(invalid_data(
+                    "expected a single spectrum in fastsimcoal2 format, found trailing lines",
+                ));
+            }
+
+            Scs::from_vec(values)
+        }
+    };
+
+    Ok((scs, Metadata::default()))
+}
+
+/// Returns a copy of the array with the order of its axes reversed.
+fn reverse_axes(array: &Array<f64>) -> Array<f64> {
+    let reversed = Shape(array.shape().iter().rev().copied().collect());
+
+    let data = (0..array.elements())
+        .map(|i| {
+            let mut index = reversed.index_from_flat_unchecked(i);
+            index.reverse();
+            array[&index]
+        })
+        .collect::<Vec<_>>();
+
+    Array::new_unchecked(data, reversed)
+}
+
+/// Writes a spectrum in fastsimcoal2 format to a writer.
+///
+/// See [`file_suffix`] for the file name expected by fastsimcoal2.
+pub fn write_spectrum<W, S: State>(
+    writer: &mut W,
+    spectrum: &Spectrum<S>,
+    precision: usize,
+) -> io::Result<()>
+where
+    W: io::Write,
+{
+    let format_values = |values: &[f64]| {
+        values
+            .iter()
+            .map(|x| format!("{x:.precision$}"))
+            .collect::<Vec<_>>()
+            .join(&DELIMITER.to_string())
+    };
+    let labels = |population: usize, n: usize| {
+        (0..n)
+            .map(|i| format!("d{population}_{i}"))
+            .collect::<Vec<_>>()
+            .join(&DELIMITER.to_string())
+    };
+
+    let shape = spectrum.shape();
+    match shape.as_ref() {
+        &[n] => {
+            writeln!(writer, "{OBSERVATIONS}")?;
+            writeln!(writer, "{}", labels(0, n))?;
+            writeln!(writer, "{}", format_values(spectrum.array.as_slice()))
+        }
+        &[columns, _] => {
+            writeln!(writer, "{OBSERVATIONS}")?;
+            writeln!(writer, "{DELIMITER}{}", labels(0, columns))?;
+
+            for (j, row) in spectrum.array.iter_axis(Axis(1)).enumerate() {
+                let row = row.iter().copied().collect::<Vec<_>>();
+                writeln!(writer, "d1_{j}{DELIMITER}{}", format_values(&row))?;
+            }
+
+            Ok(())
+        }
+        shape => {
+            let sizes = shape
+                .iter()
+                .map(|n| (n - 1).to_string())
+                .collect::<Vec<_>>()
+                .join(&DELIMITER.to_string());
+
+            writeln!(writer, "{MULTI_OBSERVATIONS}")?;
+            writeln!(writer, "{}{DELIMITER}{sizes}", shape.len())?;
+            writeln!(
+                writer,
+                "{}",
+                format_values(reverse_axes(&spectrum.array).as_slice())
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert!(detect(b"1 observations\nd0_0\td0_1\n"));
+        assert!(detect(MULTI_OBSERVATIONS.as_bytes()));
+        assert!(!detect(b"5 unfolded\n0 1 2 3 4\n"));
+        assert!(!detect(b"#SHAPE=<5>\n0 1 2 3 4\n"));
+    }
+
+    #[test]
+    fn test_file_suffix() {
+        assert_eq!(file_suffix(1, false), "_DAFpop0.obs");
+        assert_eq!(file_suffix(2, true), "_jointMAFpop1_0.obs");
+        assert_eq!(file_suffix(3, false), "_MSFS.obs");
+    }
+
+    #[test]
+    fn test_write_read_1d() -> io::Result<()> {
+        let scs = Scs::from_range(0..4, 4).unwrap();
+
+        let mut dest = Vec::new();
+        write_spectrum(&mut dest, &scs, 0)?;
+        assert_eq!(
+            dest,
+            b"1 observations\nd0_0\td0_1\td0_2\td0_3\n0\t1\t2\t3\n"
+        );
+
+        assert_eq!(read_scs_with_metadata(&mut &dest[..])?.0, scs);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_read_2d() -> io::Result<()> {
+        let scs = Scs::from_range(0..6, [2, 3]).unwrap();
+
+        let mut dest = Vec::new();
+        write_spectrum(&mut dest, &scs, 0)?;
+        assert_eq!(
+            dest,
+            b"1 observations\n\
+            \td0_0\td0_1\n\
+            d1_0\t0\t3\n\
+            d1_1\t1\t4\n\
+            d1_2\t2\t5\n"
+        );
+
+        assert_eq!(read_scs_with_metadata(&mut &dest[..])?.0, scs);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_read_3d() -> io::Result<()> {
+        let scs = Scs::from_range(0..12, [2, 3, 2]).unwrap();
+
+        let mut dest = Vec::new();
+        write_spectrum(&mut dest, &scs, 0)?;
+
+        let mut expected = format!("{MULTI_OBSERVATIONS}\n3\t1\t2\t1\n").into_bytes();
+        expected.extend(b"0\t6\t2\t8\t4\t10\t1\t7\t3\t9\t5\t11\n");
+        assert_eq!(dest, expected);
+
+        assert_eq!(read_scs_with_metadata(&mut &dest[..])?.0, scs);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        assert!(read_scs_with_metadata(&mut &b"2 observations\nd0_0\n0\n"[..]).is_err());
+        assert!(
+            read_scs_with_metadata(&mut &b"1 observations\n\td0_0\td0_1\nd1_0\t0\n"[..]).is_err()
+        );
+    }
+}
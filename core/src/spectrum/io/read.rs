@@ -2,20 +2,56 @@
 
 use std::io::{self, Read};
 
-use crate::{Array, Input, Scs};
+use crate::{array::Shape, Array, Input, Scs};
 
-use super::{text, Format};
+use super::{angsd, container, dadi, fsc, text, Format, Metadata};
+
+/// One or more spectra read from an input.
+pub enum Spectra {
+    /// A single spectrum with its metadata.
+    Single(Scs, Metadata),
+    /// A container of several labelled spectra.
+    Container(container::Reader<io::Cursor<Vec<u8>>>),
+}
 
 /// A builder to read a spectrum.
 #[derive(Debug, Default)]
 pub struct Builder {
     input: Option<Input>,
     format: Option<Format>,
+    shape: Option<Shape>,
 }
 
 impl Builder {
     /// Read a spectrum from reader.
     pub fn read(self) -> io::Result<Scs> {
+        let shape = self.shape.clone();
+        let (raw, format) = self.read_raw()?;
+
+        let reader = &mut &raw[..];
+        match format {
+            Some(Format::Text) => text::read_scs(reader),
+            Some(Format::Angsd) => read_angsd(reader, shape),
+            Some(Format::Dadi) => dadi::read_scs_with_metadata(reader).map(|(scs, _)| scs),
+            Some(Format::Fsc) => fsc::read_scs_with_metadata(reader).map(|(scs, _)| scs),
+            Some(Format::Npy) => Array::read_npy(reader).map(Scs::from),
+            Some(Format::Npz) => read_npz(raw).map(|(scs, _)| scs),
+            None => Err(io::Error::new(io::ErrorKind::InvalidData, "invalid format")),
+        }
+    }
+
+    /// Read a spectrum with its metadata from reader.
+    ///
+    /// Metadata can be read from the plain text and npz formats and partly from the dadi format,
+    /// and is empty for other formats. An npz container must hold a single spectrum.
+    pub fn read_with_metadata(self) -> io::Result<(Scs, Metadata)> {
+        let shape = self.shape.clone();
+        let (raw, format) = self.read_raw()?;
+
+        read_with_metadata(raw, format, shape)
+    }
+
+    fn read_raw(self) -> io::Result<(Vec<u8>, Option<Format>)> {
         let mut raw = Vec::new();
 
         _ = match self.input.unwrap_or(Input::Stdin).open()? {
@@ -25,12 +61,31 @@ impl Builder {
 
         let format = self.format.or_else(|| Format::detect(&raw));
 
-        let reader = &mut &raw[..];
-        match format {
-            Some(Format::Text) => text::read_scs(reader),
-            Some(Format::Npy) => Array::read_npy(reader).map(Scs::from),
-            None => Err(io::Error::new(io::ErrorKind::InvalidData, "invalid format")),
-        }
+        Ok((raw, format))
+    }
+
+    /// Read either a single spectrum or a container of several spectra from reader.
+    ///
+    /// The npz format is always read as a container. The plain text format is read as a container
+    /// when it has more than one spectrum, or when a spectrum has a label or region, such as
+    /// windowed spectra. Other formats hold a single spectrum.
+    pub fn read_spectra(self) -> io::Result<Spectra> {
+        let shape = self.shape.clone();
+        let (raw, format) = self.read_raw()?;
+
+        let container_format = match format {
+            Some(Format::Npz) => container::Format::Npz,
+            Some(Format::Text) if text::is_container(&raw) => container::Format::Text,
+            _ => {
+                return read_with_metadata(raw, format, shape)
+                    .map(|(scs, metadata)| Spectra::Single(scs, metadata))
+            }
+        };
+
+        Ok(Spectra::Container(container::Reader::with_format(
+            io::Cursor::new(raw),
+            container_format,
+        )))
     }
 
     /// Set input source.
@@ -43,11 +98,64 @@ impl Builder {
 
     /// Set format to read.
     ///
-    /// If unset, the format will automatically be detected when reading.
+    /// If unset, the format will automatically be detected when reading. The ANGSD format cannot
+    /// be detected and must be set explicitly.
     pub fn set_format(mut self, format: Format) -> Self {
         self.format = Some(format);
         self
     }
+
+    /// Set shape of spectrum to read.
+    ///
+    /// This is required for the ANGSD format, which does not store the shape, and is ignored for
+    /// other formats.
+    pub fn set_shape(mut self, shape: Shape) -> Self {
+        self.shape = Some(shape);
+        self
+    }
+}
+
+fn read_with_metadata(
+    raw: Vec<u8>,
+    format: Option<Format>,
+    shape: Option<Shape>,
+) -> io::Result<(Scs, Metadata)> {
+    let reader = &mut &raw[..];
+    match format {
+        Some(Format::Text) => text::read_scs_with_metadata(reader),
+        Some(Format::Angsd) => read_angsd(reader, shape).map(|scs| (scs, Metadata::default())),
+        Some(Format::Dadi) => dadi::read_scs_with_metadata(reader),
+        Some(Format::Fsc) => fsc::read_scs_with_metadata(reader),
+        Some(Format::Npy) => {
+            Array::read_npy(reader).map(|array| (Scs::from(array), Metadata::default()))
+        }
+        Some(Format::Npz) => read_npz(raw),
+        None => Err(io::Error::new(io::ErrorKind::InvalidData, "invalid format")),
+    }
+}
+
+fn read_npz(raw: Vec<u8>) -> io::Result<(Scs, Metadata)> {
+    let mut records = container::Reader::with_format(io::Cursor::new(raw), container::Format::Npz)
+        .read_records()?;
+
+    match records.len() {
+        1 => Ok(records.remove(0).value),
+        n => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("expected a single spectrum in npz format, found {n}"),
+        )),
+    }
+}
+
+fn read_angsd(reader: &mut &[u8], shape: Option<Shape>) -> io::Result<Scs> {
+    let shape = shape.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "shape must be set to read ANGSD format",
+        )
+    })?;
+
+    angsd::read_scs(reader, shape)
 }
 
 #[cfg(test)]
@@ -65,6 +173,23 @@ mod tests {
         assert_eq!(Format::detect(&bytes), Some(Format::Npy));
     }
 
+    #[test]
+    fn test_detect_dadi() {
+        assert_eq!(
+            Format::detect(b"5 unfolded\n0 1 2 3 4\n"),
+            Some(Format::Dadi)
+        );
+        assert_eq!(Format::detect(b"# comment\n1\n5\n"), Some(Format::Dadi));
+    }
+
+    #[test]
+    fn test_detect_fsc() {
+        assert_eq!(
+            Format::detect(b"1 observations\nd0_0\td0_1\n0\t1\n"),
+            Some(Format::Fsc)
+        );
+    }
+
     #[test]
     fn test_detect_plain_text() {
         assert_eq!(Format::detect_plain_text(&text::START), Some(Format::Text));
@@ -4,6 +4,17 @@
 //! The first line contains a header line `#SHAPE=<[shape]>`, where `[shape]`
 //! is a `/`-separated representation of the shape of the spectrum. The next line
 //! gives the spectrum in flat, row-major order separated by a single space.
+//!
+//! Several spectra may be stored in a single file as a sequence of such records, see
+//! [`container`](super::container). Optionally, additional header lines of the form
+//! `#KEY=<[value]>` may follow the shape header line. The [`Metadata`] of a spectrum is stored in
+//! header lines `#POPULATIONS=<[populations]>`, where `[populations]` is a `/`-separated list of
+//! population names in the same order as the shape, `#FOLDED=<true>` and `#POLARIZED=<true>`,
+//! which are only written when true, `#MASK=<[indices]>`, where `[indices]` is a `/`-separated
+//! list of the flat indices of masked elements, and `#REGION=<[contig]:[start]-[end]>`, giving the
+//! genomic region of a spectrum with 1-based, inclusive positions. A header line
+//! `#LABEL=<[label]>` gives the label of a spectrum among several labelled spectra. Population
+//! names cannot contain `/`, and no header value can contain line breaks.
 
 use std::{
     fmt::{self, Write},
@@ -16,9 +27,29 @@ use crate::{
     Scs, Spectrum,
 };
 
+use super::Metadata;
+
 /// The text format start string.
 pub(crate) const START: [u8; 6] = *b"#SHAPE";
 
+/// The header key for the genomic region of a spectrum.
+const REGION_KEY: &str = "REGION";
+
+/// The header key for the label of a spectrum.
+const LABEL_KEY: &str = "LABEL";
+
+/// The header key for the population names of a spectrum.
+const POPULATIONS_KEY: &str = "POPULATIONS";
+
+/// The header key for whether a spectrum is folded.
+const FOLDED_KEY: &str = "FOLDED";
+
+/// The header key for whether a spectrum is polarized.
+const POLARIZED_KEY: &str = "POLARIZED";
+
+/// The header key for the masked elements of a spectrum.
+const MASK_KEY: &str = "MASK";
+
 fn parse_scs(s: &str, shape: Shape) -> io::Result<Scs> {
     s.split_ascii_whitespace()
         .map(f64::from_str)
@@ -31,17 +62,164 @@ fn parse_scs(s: &str, shape: Shape) -> io::Result<Scs> {
 
 /// Reads an SCS in text format from a reader.
 ///
-/// The stream is assumed to be positioned at the start.
+/// The stream is assumed to be positioned at the start, and must contain a single spectrum.
 pub fn read_scs<R>(reader: &mut R) -> io::Result<Scs>
 where
     R: io::BufRead,
 {
-    let header = Header::read(reader)?;
+    read_scs_with_metadata(reader).map(|(scs, _)| scs)
+}
 
-    let mut buf = String::new();
-    let _bytes_read = reader.read_to_string(&mut buf)?;
+/// Reads an SCS in text format with its metadata from a reader.
+///
+/// The stream is assumed to be positioned at the start, and must contain a single spectrum.
+pub fn read_scs_with_metadata<R>(reader: &mut R) -> io::Result<(Scs, Metadata)>
+where
+    R: io::BufRead,
+{
+    let mut records = read_records(reader)?;
+
+    match records.len() {
+        1 => {
+            let (header, scs) = records.remove(0);
+            Ok((scs, header.metadata))
+        }
+        n => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("expected a single spectrum in plain text format, found {n}"),
+        )),
+    }
+}
+
+/// Returns whether text format bytes contain several spectra, rather than a single spectrum.
+///
+/// This is the case when there is more than one record, or when a record has a label or region.
+pub(crate) fn is_container(bytes: &[u8]) -> bool {
+    let is_key = |line: &[u8], key: &str| {
+        line.strip_prefix(b"#").is_some_and(|line| {
+            line.starts_with(key.as_bytes()) && line.get(key.len()) == Some(&b'=')
+        })
+    };
+
+    let mut records = 0;
+    for line in bytes.split(|&b| b == b'\n') {
+        if line.starts_with(&START) {
+            records += 1;
+        }
+
+        if records > 1 || is_key(line, LABEL_KEY) || is_key(line, REGION_KEY) {
+            return true;
+        }
+    }
+
+    false
+}
+
+fn read_records<R>(reader: &mut R) -> io::Result<Vec<(Header, Scs)>>
+where
+    R: io::BufRead,
+{
+    let mut record_reader = RecordReader::new(reader);
+
+    let mut records = Vec::new();
+    while let Some(record) = record_reader.read_record()? {
+        records.push(record);
+    }
+
+    if records.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            ParseHeaderError(String::new()),
+        ));
+    }
+
+    Ok(records)
+}
 
-    parse_scs(&buf, header.shape)
+/// A reader of text format records one at a time.
+pub(crate) struct RecordReader<R> {
+    inner: R,
+    buf: String,
+    next_header: Option<Header>,
+}
+
+impl<R> RecordReader<R>
+where
+    R: io::BufRead,
+{
+    pub(crate) fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buf: String::new(),
+            next_header: None,
+        }
+    }
+
+    /// Reads the next line into the buffer without line ending, returning `false` at the end.
+    fn read_line(&mut self) -> io::Result<bool> {
+        self.buf.clear();
+
+        if self.inner.read_line(&mut self.buf)? == 0 {
+            return Ok(false);
+        }
+
+        if self.buf.ends_with('\n') {
+            self.buf.pop();
+
+            if self.buf.ends_with('\r') {
+                self.buf.pop();
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Reads the next record, returning `None` when there are no more records.
+    pub(crate) fn read_record(&mut self) -> io::Result<Option<(Header, Scs)>> {
+        let invalid_data = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+
+        let mut header = match self.next_header.take() {
+            Some(header) => header,
+            None if !self.read_line()? => return Ok(None),
+            None if self.buf.as_bytes().starts_with(&START) => {
+                Header::from_str(&self.buf).map_err(invalid_data)?
+            }
+            None => return Err(invalid_data(ParseHeaderError(self.buf.clone()))),
+        };
+
+        let mut data = String::new();
+        while self.read_line()? {
+            if self.buf.as_bytes().starts_with(&START) {
+                self.next_header = Some(Header::from_str(&self.buf).map_err(invalid_data)?);
+                break;
+            } else if self.buf.starts_with('#') {
+                header.parse_field(&self.buf).map_err(invalid_data)?;
+            } else {
+                data.push_str(&self.buf);
+                data.push(' ');
+            }
+        }
+
+        parse_record(header, &data).map(Some)
+    }
+}
+
+fn parse_record(header: Header, data: &str) -> io::Result<(Header, Scs)> {
+    if let Some(populations) = &header.metadata.populations {
+        if populations.len() != header.shape.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "found {} population names for spectrum with {} dimensions",
+                    populations.len(),
+                    header.shape.len()
+                ),
+            ));
+        }
+    }
+
+    let scs = parse_scs(data, header.shape.clone())?;
+    Ok((header, scs))
 }
 
 fn format_spectrum<S: State>(spectrum: &Spectrum<S>, sep: &str, precision: usize) -> String {
@@ -64,48 +242,144 @@ fn format_spectrum<S: State>(spectrum: &Spectrum<S>, sep: &str, precision: usize
     }
 }
 
-/// Writes a spectrum in text format to a writer.
+/// Writes a spectrum with its metadata in text format to a writer.
 pub fn write_spectrum<W, S: State>(
     writer: &mut W,
     spectrum: &Spectrum<S>,
+    metadata: &Metadata,
+    precision: usize,
+) -> io::Result<()>
+where
+    W: io::Write,
+{
+    write_record(writer, None, spectrum, metadata, precision)
+}
+
+/// Writes a spectrum with its metadata and an optional label in text format to a writer.
+pub(crate) fn write_record<W, S: State>(
+    writer: &mut W,
+    label: Option<&str>,
+    spectrum: &Spectrum<S>,
+    metadata: &Metadata,
     precision: usize,
 ) -> io::Result<()>
 where
     W: io::Write,
 {
-    let header = Header::new(spectrum.array.shape().clone());
+    let mut header = Header::new(spectrum.array.shape().clone());
+    header.label = label.map(String::from);
+    header.metadata = metadata.clone();
     header.write(writer)?;
 
     writeln!(writer, "{}", format_spectrum(spectrum, " ", precision))
 }
 
 #[derive(Clone, Debug)]
-struct Header {
-    shape: Shape,
+pub(crate) struct Header {
+    pub(crate) shape: Shape,
+    pub(crate) label: Option<String>,
+    pub(crate) metadata: Metadata,
 }
 
 impl Header {
-    pub fn new(shape: Shape) -> Self {
-        Self { shape }
+    pub(crate) fn new(shape: Shape) -> Self {
+        Self {
+            shape,
+            label: None,
+            metadata: Metadata::default(),
+        }
     }
 
-    pub fn read<R>(reader: &mut R) -> io::Result<Self>
-    where
-        R: io::BufRead,
-    {
-        let mut buf = String::new();
+    /// Parses an additional `#KEY=<value>` header line into the header.
+    pub(crate) fn parse_field(&mut self, s: &str) -> Result<(), ParseHeaderError> {
+        let invalid = || ParseHeaderError(String::from(s));
+
+        let (key, value) = s
+            .strip_prefix('#')
+            .and_then(|s| s.split_once('='))
+            .and_then(|(key, value)| {
+                value
+                    .trim_end()
+                    .strip_prefix('<')
+                    .and_then(|value| value.strip_suffix('>'))
+                    .map(|value| (key, value))
+            })
+            .ok_or_else(invalid)?;
+
+        match key {
+            REGION_KEY => self.metadata.region = Some(value.parse().map_err(|_| invalid())?),
+            LABEL_KEY => self.label = Some(value.to_string()),
+            POPULATIONS_KEY => {
+                self.metadata.populations = Some(value.split('/').map(String::from).collect())
+            }
+            FOLDED_KEY => self.metadata.folded = value.parse().map_err(|_| invalid())?,
+            POLARIZED_KEY => self.metadata.polarized = value.parse().map_err(|_| invalid())?,
+            MASK_KEY => {
+                let mut mask = vec![false; self.shape.elements()];
+                for index in value.split('/').filter(|s| !s.is_empty()) {
+                    let index = index.parse::<usize>().map_err(|_| invalid())?;
+                    *mask.get_mut(index).ok_or_else(invalid)? = true;
+                }
+                self.metadata.mask = Some(mask);
+            }
+            _ => return Err(invalid()),
+        }
+
+        Ok(())
+    }
+
+    /// Parses a header from a shape header line followed by any additional header lines.
+    pub(crate) fn parse(s: &str) -> Result<Self, ParseHeaderError> {
+        let mut lines = s.lines();
 
-        reader.read_line(&mut buf)?;
+        let mut header = Self::from_str(lines.next().unwrap_or_default())?;
+        for line in lines {
+            header.parse_field(line)?;
+        }
 
-        Self::from_str(&buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        Ok(header)
     }
 
-    pub fn write<W>(&self, writer: &mut W) -> io::Result<()>
+    pub(crate) fn write<W>(&self, writer: &mut W) -> io::Result<()>
     where
         W: io::Write,
     {
+        self.check()?;
+
         writeln!(writer, "{self}")
     }
+
+    /// Checks that the header can be written and read back unchanged.
+    ///
+    /// Population names cannot contain the separator `/`, and no value can contain line breaks.
+    fn check(&self) -> io::Result<()> {
+        let invalid = |msg| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        let is_line_break = |c| c == '\n' || c == '\r';
+
+        if let Some(name) = self
+            .metadata
+            .populations
+            .iter()
+            .flatten()
+            .find(|name| name.contains(|c| c == '/' || is_line_break(c)))
+        {
+            return Err(invalid(format!(
+                "population name '{name}' cannot contain '/' or line breaks in plain text format"
+            )));
+        }
+
+        if let Some(label) = self
+            .label
+            .as_ref()
+            .filter(|label| label.contains(is_line_break))
+        {
+            return Err(invalid(format!(
+                "label '{label}' cannot contain line breaks in plain text format"
+            )));
+        }
+
+        Ok(())
+    }
 }
 
 impl fmt::Display for Header {
@@ -117,7 +391,41 @@ impl fmt::Display for Header {
             .collect::<Vec<_>>()
             .join("/");
 
-        write!(f, "#SHAPE=<{shape_fmt}>")
+        write!(f, "#SHAPE=<{shape_fmt}>")?;
+
+        if let Some(populations) = &self.metadata.populations {
+            write!(f, "\n#{POPULATIONS_KEY}=<{}>", populations.join("/"))?;
+        }
+
+        if self.metadata.folded {
+            write!(f, "\n#{FOLDED_KEY}=<true>")?;
+        }
+
+        if self.metadata.polarized {
+            write!(f, "\n#{POLARIZED_KEY}=<true>")?;
+        }
+
+        if let Some(mask) = &self.metadata.mask {
+            let indices = mask
+                .iter()
+                .enumerate()
+                .filter(|(_, &masked)| masked)
+                .map(|(i, _)| i.to_string())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            write!(f, "\n#{MASK_KEY}=<{indices}>")?;
+        }
+
+        if let Some(region) = &self.metadata.region {
+            write!(f, "\n#{REGION_KEY}=<{region}>")?;
+        }
+
+        if let Some(label) = &self.label {
+            write!(f, "\n#{LABEL_KEY}=<{label}>")?;
+        }
+
+        Ok(())
     }
 }
 
@@ -156,6 +464,8 @@ impl std::error::Error for ParseHeaderError {}
 mod tests {
     use super::*;
 
+    use crate::input::Region;
+
     #[test]
     fn test_parse_header() {
         assert_eq!(Header::from_str("#SHAPE=<3>").unwrap().shape.as_ref(), [3]);
@@ -192,10 +502,29 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_read_multiple_error() {
+        let src = b"#SHAPE=<2>\n0.0 1.0\n#SHAPE=<2>\n2.0 3.0\n";
+
+        assert!(read_scs(&mut &src[..]).is_err());
+    }
+
+    #[test]
+    fn test_read_unknown_header_key() {
+        let src = b"#SHAPE=<2>\n#FOO=<bar>\n0 1\n";
+
+        assert!(read_scs(&mut &src[..]).is_err());
+    }
+
     #[test]
     fn test_write_1d() -> io::Result<()> {
         let mut dest = Vec::new();
-        write_spectrum(&mut dest, &Scs::new([0., 1., 2.], 3).unwrap(), 2)?;
+        write_spectrum(
+            &mut dest,
+            &Scs::new([0., 1., 2.], 3).unwrap(),
+            &Metadata::default(),
+            2,
+        )?;
 
         assert_eq!(dest, b"#SHAPE=<3>\n0.00 1.00 2.00\n");
 
@@ -208,6 +537,7 @@ mod tests {
         write_spectrum(
             &mut dest,
             &Scs::new([0., 1., 2., 3., 4., 5.], [2, 3]).unwrap(),
+            &Metadata::default(),
             6,
         )?;
 
@@ -218,4 +548,80 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_metadata_round_trip() -> io::Result<()> {
+        let scs = Scs::new([0., 1., 2., 3., 4., 5.], [2, 3]).unwrap();
+        let metadata = Metadata {
+            populations: Some(vec![String::from("pop1"), String::from("pop0")]),
+            folded: true,
+            polarized: false,
+            mask: Some(vec![true, false, false, false, false, true]),
+            region: Some(Region::new("chr1", 1, Some(10))),
+        };
+
+        let mut dest = Vec::new();
+        write_spectrum(&mut dest, &scs, &metadata, 0)?;
+
+        assert_eq!(
+            dest,
+            b"#SHAPE=<2/3>\n#POPULATIONS=<pop1/pop0>\n#FOLDED=<true>\n#MASK=<0/5>\n\
+            #REGION=<chr1:1-10>\n0 1 2 3 4 5\n"
+        );
+        assert_eq!(read_scs_with_metadata(&mut &dest[..])?, (scs, metadata));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_metadata_round_trip_special_population_names() -> io::Result<()> {
+        let scs = Scs::new([0., 1., 2., 3.], [2, 2]).unwrap();
+        let metadata = Metadata {
+            populations: Some(vec![String::from("<a>"), String::from("b > c")]),
+            ..Default::default()
+        };
+
+        let mut dest = Vec::new();
+        write_spectrum(&mut dest, &scs, &metadata, 0)?;
+
+        assert_eq!(read_scs_with_metadata(&mut &dest[..])?, (scs, metadata));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_invalid_population_names() {
+        let scs = Scs::new([0., 1., 2., 3.], [2, 2]).unwrap();
+
+        for name in ["a/b", "a\nb"] {
+            let metadata = Metadata {
+                populations: Some(vec![String::from(name), String::from("c")]),
+                ..Default::default()
+            };
+
+            assert!(write_spectrum(&mut Vec::new(), &scs, &metadata, 0).is_err());
+        }
+    }
+
+    #[test]
+    fn test_is_container() {
+        assert!(!is_container(b"#SHAPE=<2>\n#FOLDED=<true>\n0 1\n"));
+        assert!(is_container(b"#SHAPE=<2>\n0 1\n#SHAPE=<2>\n2 3\n"));
+        assert!(is_container(b"#SHAPE=<2>\n#LABEL=<a>\n0 1\n"));
+        assert!(is_container(b"#SHAPE=<2>\n#REGION=<chr1:1-10>\n0 1\n"));
+    }
+
+    #[test]
+    fn test_read_mask_out_of_bounds() {
+        let src = b"#SHAPE=<2/3>\n#MASK=<0/6>\n0 1 2 3 4 5\n";
+
+        assert!(read_scs(&mut &src[..]).is_err());
+    }
+
+    #[test]
+    fn test_read_metadata_wrong_number_of_populations() {
+        let src = b"#SHAPE=<2/3>\n#POPULATIONS=<pop0>\n0 1 2 3 4 5\n";
+
+        assert!(read_scs(&mut &src[..]).is_err());
+    }
 }
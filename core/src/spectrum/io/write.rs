@@ -4,13 +4,14 @@ use std::{fs, io, path::Path};
 
 use crate::{spectrum::State, Spectrum};
 
-use super::{text, Format};
+use super::{angsd, container, dadi, fsc, text, Format, Metadata};
 
 /// A builder to write a spectrum.
 #[derive(Debug)]
 pub struct Builder {
     format: Format,
     precision: usize,
+    metadata: Metadata,
 }
 
 impl Builder {
@@ -22,9 +23,20 @@ impl Builder {
         self
     }
 
+    /// Set metadata.
+    ///
+    /// This is used for the plain text format, where the metadata is written for each spectrum,
+    /// and for the dadi format, where the folding, population names, and mask are written. If
+    /// unset, no metadata will be written.
+    pub fn set_metadata(mut self, metadata: Metadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
     /// Set precision.
     ///
-    /// This is only used for the plain text format.
+    /// This is only used for the plain text, dadi, fastsimcoal2, and ANGSD formats, including
+    /// plain text containers.
     /// If unset, a precision of six digits will be used.
     pub fn set_precision(mut self, precision: usize) -> Self {
         self.precision = precision;
@@ -37,11 +49,41 @@ impl Builder {
         W: io::Write,
     {
         match self.format {
-            Format::Text => text::write_spectrum(writer, spectrum, self.precision),
+            Format::Text => text::write_spectrum(writer, spectrum, &self.metadata, self.precision),
+            Format::Dadi => dadi::write_spectrum(writer, spectrum, &self.metadata, self.precision),
+            Format::Fsc => fsc::write_spectrum(writer, spectrum, self.precision),
+            Format::Angsd => angsd::write_spectrum(writer, spectrum, self.precision),
             Format::Npy => spectrum.array.write_npy(writer),
+            Format::Npz => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a single spectrum cannot be written in npz format",
+            )),
         }
     }
 
+    /// Build a writer of several spectra to a container.
+    ///
+    /// Several spectra can only be written in the plain text and npz formats. The metadata set on
+    /// the builder is not used, since metadata is provided for each spectrum written to the
+    /// container.
+    pub fn build_container<W>(self, writer: W) -> io::Result<container::Writer<W>>
+    where
+        W: io::Write,
+    {
+        let format = match self.format {
+            Format::Npz => container::Format::Npz,
+            Format::Text => container::Format::Text,
+            format @ (Format::Angsd | Format::Dadi | Format::Fsc | Format::Npy) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("several spectra cannot be written in {format} format"),
+                ))
+            }
+        };
+
+        Ok(container::Writer::new(writer, format).set_precision(self.precision))
+    }
+
     /// Write spectrum to stdout.
     pub fn write_to_stdout<S: State>(self, spectrum: &Spectrum<S>) -> io::Result<()> {
         self.write(&mut io::stdout().lock(), spectrum)
@@ -81,6 +123,7 @@ impl Default for Builder {
         Builder {
             format: Format::Text,
             precision: 6,
+            metadata: Metadata::default(),
         }
     }
 }
@@ -0,0 +1,297 @@
+//! Block jackknife of statistics calculated from spectra.
+
+use std::fmt;
+
+use crate::{input::window::Windowed, Scs};
+
+/// A weighted block jackknife estimate of a statistic.
+///
+/// See Busing et al. (1999), as used by e.g. ADMIXTOOLS.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Jackknife {
+    /// The point estimate of the statistic, calculated from the sum of all blocks.
+    pub estimate: f64,
+    /// The jackknife standard error of the statistic.
+    pub standard_error: f64,
+}
+
+impl Jackknife {
+    /// Returns the Z-score of the estimate, i.e. the estimate divided by its standard error.
+    pub fn z_score(&self) -> f64 {
+        self.estimate / self.standard_error
+    }
+}
+
+/// Returns the weighted block jackknife estimate of a statistic from blocks of spectra.
+///
+/// The point estimate is calculated from the sum of all blocks, while the standard error is based
+/// on the estimates leaving out each block in turn. Blocks are weighted by their number of sites,
+/// i.e. the sum of the block spectrum, and blocks without sites are ignored. The blocks must not
+/// overlap, which cannot be checked here. For blocks given by genomic windows, use
+/// [`windowed_block_jackknife`] instead.
+///
+/// # Errors
+///
+/// If fewer than two blocks contain sites, or if the statistic cannot be calculated.
+pub fn block_jackknife<F, E>(blocks: &[Scs], mut statistic: F) -> Result<Jackknife, E>
+where
+    F: FnMut(&Scs) -> Result<f64, E>,
+    E: From<JackknifeError>,
+{
+    let blocks = blocks
+        .iter()
+        .filter(|block| block.sum() > 0.0)
+        .collect::<Vec<_>>();
+
+    let (first, rest) = match blocks.as_slice() {
+        [first, rest @ ..] if !rest.is_empty() => (first, rest),
+        blocks => return Err(JackknifeError::TooFewBlocks { n: blocks.len() }.into()),
+    };
+
+    if let Some(block) = rest.iter().find(|block| block.shape() != first.shape()) {
+        return Err(JackknifeError::UnequalShapes {
+            first: first.shape().as_ref().to_vec(),
+            other: block.shape().as_ref().to_vec(),
+        }
+        .into());
+    }
+
+    let mut total = Scs::from_zeros(first.shape().clone());
+    for block in blocks.iter() {
+        add_scaled(&mut total, block, 1.0);
+    }
+
+    let estimate = statistic(&total)?;
+
+    let g = blocks.len() as f64;
+    let n = total.sum();
+
+    let mut weights = Vec::with_capacity(blocks.len());
+    let mut leave_one_out = Vec::with_capacity(blocks.len());
+    for block in blocks.iter() {
+        let mut without = total.clone();
+        add_scaled(&mut without, block, -1.0);
+
+        weights.push(block.sum());
+        leave_one_out.push(statistic(&without)?);
+    }
+
+    let jackknife_estimate = g * estimate
+        - weights
+            .iter()
+            .zip(leave_one_out.iter())
+            .map(|(m, estimate)| (1.0 - m / n) * estimate)
+            .sum::<f64>();
+
+    let variance = weights
+        .iter()
+        .zip(leave_one_out.iter())
+        .map(|(m, leave_one_out)| {
+            let h = n / m;
+            let pseudo_value = h * estimate - (h - 1.0) * leave_one_out;
+            (pseudo_value - jackknife_estimate).powi(2) / (h - 1.0)
+        })
+        .sum::<f64>()
+        / g;
+
+    Ok(Jackknife {
+        estimate,
+        standard_error: variance.sqrt(),
+    })
+}
+
+/// Returns the weighted block jackknife estimate of a statistic from windowed blocks of spectra.
+///
+/// See [`block_jackknife`] for details. The windows must not overlap, so that sliding windows
+/// cannot be used as blocks, and windows should be tiled, i.e. have a step equal to their size.
+///
+/// # Errors
+///
+/// If any two windows overlap, and otherwise as for [`block_jackknife`].
+pub fn windowed_block_jackknife<F, E>(
+    windows: &[Windowed<Scs>],
+    statistic: F,
+) -> Result<Jackknife, E>
+where
+    F: FnMut(&Scs) -> Result<f64, E>,
+    E: From<JackknifeError>,
+{
+    let mut regions = windows
+        .iter()
+        .map(|window| &window.region)
+        .collect::<Vec<_>>();
+    regions.sort_by(|a, b| (a.contig(), a.start()).cmp(&(b.contig(), b.start())));
+
+    if let Some(&[first, other]) = regions.windows(2).find(|pair| {
+        pair[0].contig() == pair[1].contig()
+            && pair[0].end().map_or(true, |end| end >= pair[1].start())
+    }) {
+        return Err(JackknifeError::OverlappingBlocks {
+            first: first.to_string(),
+            other: other.to_string(),
+        }
+        .into());
+    }
+
+    let blocks = windows
+        .iter()
+        .map(|window| window.value.clone())
+        .collect::<Vec<_>>();
+
+    block_jackknife(&blocks, statistic)
+}
+
+fn add_scaled(scs: &mut Scs, other: &Scs, scale: f64) {
+    scs.inner_mut()
+        .iter_mut()
+        .zip(other.inner().iter())
+        .for_each(|(x, y)| *x += scale * y);
+}
+
+/// An error associated with a block jackknife.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum JackknifeError {
+    /// Blocks given by genomic windows overlap.
+    OverlappingBlocks {
+        /// The first of the overlapping windows.
+        first: String,
+        /// The other of the overlapping windows.
+        other: String,
+    },
+    /// Fewer than two blocks contain sites.
+    TooFewBlocks {
+        /// The number of blocks with sites.
+        n: usize,
+    },
+    /// Blocks have different shapes.
+    UnequalShapes {
+        /// The shape of the first block.
+        first: Vec<usize>,
+        /// The shape of another block.
+        other: Vec<usize>,
+    },
+}
+
+impl fmt::Display for JackknifeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JackknifeError::OverlappingBlocks { first, other } => write!(
+                f,
+                "block jackknife requires non-overlapping blocks, found overlapping windows \
+                '{first}' and '{other}'"
+            ),
+            JackknifeError::TooFewBlocks { n } => {
+                write!(
+                    f,
+                    "block jackknife requires at least two blocks with sites, found {n}"
+                )
+            }
+            JackknifeError::UnequalShapes { first, other } => write!(
+                f,
+                "block jackknife requires blocks with equal shapes, found {first:?} and {other:?}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for JackknifeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::input::Region;
+
+    /// The proportion of sites in the first bin.
+    fn proportion(scs: &Scs) -> Result<f64, JackknifeError> {
+        Ok(scs[[0]] / scs.sum())
+    }
+
+    #[test]
+    fn test_block_jackknife_equal_weights() {
+        let blocks = vec![
+            Scs::new([1., 1.], 2).unwrap(),
+            Scs::new([2., 0.], 2).unwrap(),
+            Scs::new([0., 2.], 2).unwrap(),
+            Scs::new([1., 1.], 2).unwrap(),
+        ];
+
+        let jackknife = block_jackknife(&blocks, proportion).unwrap();
+
+        // With equal weights, this is the standard delete-one jackknife of the block means,
+        // where the block proportions 0.5, 1, 0, 0.5 have variance 1/6 and so SE sqrt(1/6/4)
+        assert_eq!(jackknife.estimate, 0.5);
+        assert_approx_eq!(
+            jackknife.standard_error,
+            (1f64 / 24.).sqrt(),
+            epsilon = 1e-12
+        );
+        assert_approx_eq!(
+            jackknife.z_score(),
+            0.5 / (1f64 / 24.).sqrt(),
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_block_jackknife_ignores_empty_blocks() {
+        let blocks = vec![
+            Scs::new([1., 1.], 2).unwrap(),
+            Scs::new([0., 0.], 2).unwrap(),
+            Scs::new([2., 0.], 2).unwrap(),
+        ];
+
+        let with_empty = block_jackknife(&blocks, proportion).unwrap();
+        let without_empty = block_jackknife(&[blocks[0].clone(), blocks[2].clone()], proportion);
+
+        assert_eq!(Ok(with_empty), without_empty);
+    }
+
+    #[test]
+    fn test_windowed_block_jackknife() {
+        let window = |contig, start, end, value: [f64; 2]| Windowed {
+            region: Region::new(contig, start, Some(end)),
+            value: Scs::new(value, 2).unwrap(),
+        };
+
+        let tiled = vec![
+            window("chr1", 11, 20, [2., 0.]),
+            window("chr1", 1, 10, [1., 1.]),
+            window("chr2", 1, 10, [0., 2.]),
+        ];
+        let blocks = tiled
+            .iter()
+            .map(|window| window.value.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            windowed_block_jackknife(&tiled, proportion),
+            block_jackknife(&blocks, proportion)
+        );
+
+        let sliding = vec![
+            window("chr1", 1, 10, [1., 1.]),
+            window("chr1", 6, 15, [2., 0.]),
+            window("chr2", 1, 10, [0., 2.]),
+        ];
+        assert_eq!(
+            windowed_block_jackknife(&sliding, proportion),
+            Err(JackknifeError::OverlappingBlocks {
+                first: String::from("chr1:1-10"),
+                other: String::from("chr1:6-15"),
+            })
+        );
+    }
+
+    #[test]
+    fn test_block_jackknife_too_few_blocks() {
+        let blocks = vec![
+            Scs::new([1., 1.], 2).unwrap(),
+            Scs::new([0., 0.], 2).unwrap(),
+        ];
+
+        assert_eq!(
+            block_jackknife(&blocks, proportion),
+            Err(JackknifeError::TooFewBlocks { n: 1 })
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use std::fmt;
 
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
 use crate::{array::Shape, utils::hypergeometric_pmf};
 
 use super::{Count, Scs};
@@ -11,6 +13,35 @@ pub struct PartialProjection {
 }
 
 impl PartialProjection {
+    /// Creates a new projection to a shape, checking that it is a valid projection of another.
+    pub fn from_shapes<S>(project_from: S, project_to: S) -> Result<Self, ProjectionError>
+    where
+        S: Into<Shape>,
+    {
+        let project_from = project_from.into();
+        let project_to = project_to.into();
+
+        if project_from.dimensions() != project_to.dimensions() {
+            Err(ProjectionError::UnequalDimensions {
+                from: project_from.dimensions(),
+                to: project_to.dimensions(),
+            })
+        } else if let Some((dimension, (&from, &to))) = project_from
+            .iter()
+            .zip(project_to.iter())
+            .enumerate()
+            .find(|(_, (from, to))| from < to)
+        {
+            Err(ProjectionError::InvalidProjection {
+                dimension,
+                from,
+                to,
+            })
+        } else {
+            Self::from_shape(project_to)
+        }
+    }
+
     pub fn from_shape<S>(project_to: S) -> Result<Self, ProjectionError>
     where
         S: Into<Shape>,
@@ -47,6 +78,72 @@ impl PartialProjection {
     }
 }
 
+/// A random downsampling to a shape.
+///
+/// Rather than spreading a count over all projected counts with hypergeometric weights, a single
+/// projected count is drawn by sampling alleles without replacement. Spectra created from
+/// downsampled counts therefore consist only of integers. The random number generator is seeded
+/// by the provided seed, so that downsampling is reproducible.
+#[derive(Clone, Debug)]
+pub struct Downsampling {
+    project_to: Count,
+    to_buf: Count,
+    rng: StdRng,
+}
+
+impl Downsampling {
+    /// Creates a new downsampling to a shape, checking that it is a valid projection of another.
+    pub fn from_shapes<S>(
+        project_from: S,
+        project_to: S,
+        seed: u64,
+    ) -> Result<Self, ProjectionError>
+    where
+        S: Into<Shape>,
+    {
+        PartialProjection::from_shapes(project_from, project_to)
+            .map(|projection| Self::new(projection.project_to, seed))
+    }
+
+    pub fn new<C>(project_to: C, seed: u64) -> Self
+    where
+        C: Into<Count>,
+    {
+        let project_to = project_to.into();
+
+        Self {
+            to_buf: Count::from_zeros(project_to.dimensions()),
+            project_to,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Draws a downsampled count of `from` derived alleles out of `project_from` alleles.
+    ///
+    /// The counts must be valid for the projection, so that no count in `from` exceeds the count
+    /// in `project_from`, which must in turn be at least the projected count in all dimensions.
+    pub fn downsample_unchecked(&mut self, project_from: &Count, from: &Count) -> &Count {
+        for i in 0..self.project_to.dimensions() {
+            let (mut size, mut successes) = (project_from[i], from[i]);
+
+            self.to_buf[i] = 0;
+            for _ in 0..self.project_to[i] {
+                if self.rng.gen_range(0..size) < successes {
+                    self.to_buf[i] += 1;
+                    successes -= 1;
+                }
+                size -= 1;
+            }
+        }
+
+        &self.to_buf
+    }
+
+    pub fn project_to(&self) -> &Count {
+        &self.project_to
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Projection {
     project_from: Count,
@@ -128,6 +225,21 @@ impl<'a> Projected<'a> {
             .for_each(|(to, projected)| *to += projected * self.weight);
     }
 
+    pub fn add_unchecked_to_each<'b, I>(self, to: I)
+    where
+        I: IntoIterator<Item = &'b mut Scs>,
+    {
+        let mut to = to
+            .into_iter()
+            .map(|scs| scs.inner_mut().as_mut_slice())
+            .collect::<Vec<_>>();
+
+        for (i, projected) in self.iter.enumerate() {
+            to.iter_mut()
+                .for_each(|to| to[i] += projected * self.weight);
+        }
+    }
+
     fn new_unchecked(
         project_from: &'a Count,
         project_to: &'a Count,
@@ -309,6 +421,43 @@ mod tests {
         assert_project_to!(projection from [6] is [0.000000, 0.000000, 1.000000]);
     }
 
+    #[test]
+    fn test_downsample() {
+        let mut downsampling = Downsampling::new(Count::from([2, 3]), 0);
+        let project_from = Count::from([6, 3]);
+
+        let mut sum = 0;
+        for _ in 0..1000 {
+            let to = downsampling.downsample_unchecked(&project_from, &Count::from([3, 1]));
+            assert!(to[0] <= 2);
+            assert_eq!(to[1], 1);
+            sum += to[0];
+        }
+
+        // Expected number of derived alleles in first dimension is 2 * 3 / 6 = 1
+        assert!((900..1100).contains(&sum));
+
+        assert_eq!(
+            downsampling.downsample_unchecked(&project_from, &Count::from([6, 0])),
+            &Count::from([2, 0])
+        );
+    }
+
+    #[test]
+    fn test_downsample_reproducible() {
+        let project_from = Count::from(10);
+        let from = Count::from(5);
+
+        let draws = |seed| {
+            let mut downsampling = Downsampling::new(Count::from(4), seed);
+            (0..10)
+                .map(|_| downsampling.downsample_unchecked(&project_from, &from)[0])
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(draws(1), draws(1));
+    }
+
     #[test]
     fn test_project_2x2_to_1x1() {
         let mut projection = Projection::new_unchecked(Count::from([2, 2]), Count::from([1, 1]));
@@ -67,6 +67,7 @@ impl private::Estimator for Watterson {
     }
 }
 
+#[allow(dead_code)]
 #[non_exhaustive]
 pub struct FayWu;
 
@@ -29,7 +29,16 @@ pub fn binomial(n: u64, k: u64) -> f64 {
     if k > n {
         0.0
     } else {
-        (0.5 + (ln_factorial(n) - ln_factorial(k) - ln_factorial(n - k)).exp()).floor()
+        (0.5 + ln_binomial(n, k).exp()).floor()
+    }
+}
+
+/// Returns the natural logarithm of the binomial coefficient.
+pub fn ln_binomial(n: u64, k: u64) -> f64 {
+    if k > n {
+        f64::NEG_INFINITY
+    } else {
+        ln_factorial(n) - ln_factorial(k) - ln_factorial(n - k)
     }
 }
 